@@ -5,6 +5,10 @@ use libtock_platform as platform;
 use libtock_platform::share;
 use libtock_platform::{DefaultConfig, ErrorCode, Syscalls};
 
+mod sampling;
+
+pub use sampling::{PowerBudget, SamplingScheduler};
+
 /// The alarm driver
 ///
 /// # Example
@@ -25,35 +29,58 @@ pub trait Convert {
     fn to_ticks(self, freq: Hz) -> Ticks;
 }
 
-#[derive(Copy, Clone, Debug)]
+/// A value of the alarm's 32-bit tick counter, or a number of ticks.
+///
+/// The counter wraps around past `u32::MAX`, so arithmetic on `Ticks` wraps as
+/// well. Durations computed this way are correct as long as they are shorter
+/// than a full period of the counter (`2^32` ticks, see `Alarm::get_frequency`
+/// for the tick rate).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Ticks(pub u32);
 
+impl Ticks {
+    /// The number of ticks from `earlier` to `self`, accounting for the
+    /// counter having wrapped around in between.
+    pub fn elapsed_since(self, earlier: Ticks) -> Ticks {
+        self - earlier
+    }
+}
+
+/// The counter value `duration` ticks after `self`.
+impl core::ops::Add for Ticks {
+    type Output = Ticks;
+
+    fn add(self, duration: Ticks) -> Ticks {
+        Ticks(self.0.wrapping_add(duration.0))
+    }
+}
+
+/// The counter value `duration` ticks before `self`.
+impl core::ops::Sub for Ticks {
+    type Output = Ticks;
+
+    fn sub(self, duration: Ticks) -> Ticks {
+        Ticks(self.0.wrapping_sub(duration.0))
+    }
+}
+
 impl Convert for Ticks {
     fn to_ticks(self, _freq: Hz) -> Ticks {
         self
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Milliseconds(pub u32);
 
 impl Convert for Milliseconds {
     fn to_ticks(self, freq: Hz) -> Ticks {
-        // Saturating multiplication will top out at about 1 hour at 1MHz.
-        // It's large enough for an alarm, and much simpler than failing
-        // or losing precision for short sleeps.
-
-        /// u32::div_ceil is still unstable.
-        fn div_ceil(a: u32, other: u32) -> u32 {
-            let d = a / other;
-            let m = a % other;
-            if m == 0 {
-                d
-            } else {
-                d + 1
-            }
-        }
-        Ticks(div_ceil(self.0.saturating_mul(freq.0), 1000))
+        // Computed in 64 bits so long durations at high frequencies don't
+        // overflow before the division. Durations longer than the counter's
+        // period saturate at u32::MAX ticks, which is large enough for an
+        // alarm and much simpler than failing.
+        let ticks = (self.0 as u64 * freq.0 as u64 + 999) / 1000;
+        Ticks(ticks.min(u32::MAX as u64) as u32)
     }
 }
 
@@ -64,12 +91,36 @@ impl<S: Syscalls, C: platform::subscribe::Config> Alarm<S, C> {
         S::command(DRIVER_NUM, command::DRIVER_CHECK, 0, 0).to_result()
     }
 
+    /// Returns the frequency at which the alarm's tick counter increments.
     pub fn get_frequency() -> Result<Hz, ErrorCode> {
         S::command(DRIVER_NUM, command::FREQUENCY, 0, 0)
             .to_result()
             .map(Hz)
     }
 
+    /// Returns the current value of the alarm's tick counter.
+    pub fn get_ticks() -> Result<Ticks, ErrorCode> {
+        S::command(DRIVER_NUM, command::TIME, 0, 0)
+            .to_result()
+            .map(Ticks)
+    }
+
+    /// Sets the alarm to fire `time` from now, without waiting for it, and
+    /// returns the counter value at which it fires. The upcall is delivered
+    /// to the listener subscribed to `DRIVER_NUM`'s subscribe number 0.
+    /// Replaces any alarm that is already set.
+    pub fn set_relative<T: Convert>(time: T) -> Result<Ticks, ErrorCode> {
+        let ticks = time.to_ticks(Self::get_frequency()?);
+        S::command(DRIVER_NUM, command::SET_RELATIVE, ticks.0, 0)
+            .to_result()
+            .map(Ticks)
+    }
+
+    /// Cancels the alarm set by `set_relative`, if it has not fired yet.
+    pub fn stop() -> Result<(), ErrorCode> {
+        S::command(DRIVER_NUM, command::STOP, 0, 0).to_result()
+    }
+
     pub fn sleep_for<T: Convert>(time: T) -> Result<(), ErrorCode> {
         let freq = Self::get_frequency()?;
         let ticks = time.to_ticks(freq);
@@ -99,7 +150,8 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 0;
+/// The alarm's driver number, for subscribing to alarm upcalls directly.
+pub const DRIVER_NUM: u32 = libtock_platform::driver_numbers::ALARM;
 
 // Command IDs
 #[allow(unused)]
@@ -0,0 +1,97 @@
+use crate::{Alarm, Convert, Milliseconds, Ticks};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use libtock_platform::{ErrorCode, Syscalls};
+
+/// The energy available for periodic sampling: each sample costs
+/// `sample_cost_uj` microjoules, and sampling may use at most `budget_uw`
+/// microwatts on average.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PowerBudget {
+    pub sample_cost_uj: u32,
+    pub budget_uw: u32,
+}
+
+impl PowerBudget {
+    /// The shortest average interval between samples that stays within the
+    /// budget. A budget of 0 allows no sampling, and returns the longest
+    /// representable interval.
+    pub fn min_interval(self) -> Milliseconds {
+        if self.budget_uw == 0 {
+            return Milliseconds(u32::MAX);
+        }
+        let budget_uw = self.budget_uw as u64;
+        let ms = (self.sample_cost_uj as u64 * 1000 + budget_uw - 1) / budget_uw;
+        Milliseconds(ms.min(u32::MAX as u64) as u32)
+    }
+}
+
+/// Schedules periodic sensor reads at a desired average rate, slowing down
+/// when the rate would exceed a power budget.
+///
+/// Reads are scheduled one interval apart. A read that happens late does not
+/// move the following ones, so the average rate is kept. If a whole interval
+/// was missed, the schedule restarts from the late read instead of catching up
+/// with a burst of reads, which would exceed the budget.
+///
+/// # Example
+/// ```ignore
+/// let budget = PowerBudget { sample_cost_uj: 50, budget_uw: 10 };
+/// let scheduler = SamplingScheduler::new(Milliseconds(1000), budget);
+/// loop {
+///     let intensity = scheduler.sample(AmbientLight::read_intensity_sync)??;
+///     // make use of the intensity value
+/// }
+/// ```
+pub struct SamplingScheduler<S: Syscalls> {
+    interval: Milliseconds,
+    next_due: Cell<Option<Ticks>>,
+    _syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls> SamplingScheduler<S> {
+    /// Creates a scheduler that reads every `period` on average, or less
+    /// often if `budget` does not allow that rate.
+    pub fn new(period: Milliseconds, budget: PowerBudget) -> Self {
+        SamplingScheduler {
+            interval: Milliseconds(period.0.max(budget.min_interval().0)),
+            next_due: Cell::new(None),
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// The interval between scheduled reads.
+    pub fn interval(&self) -> Milliseconds {
+        self.interval
+    }
+
+    /// The alarm counter value at which the next read is due. Before the first
+    /// read, that is the current time.
+    pub fn next_sample_due(&self) -> Result<Ticks, ErrorCode> {
+        match self.next_due.get() {
+            Some(due) => Ok(due),
+            None => Alarm::<S>::get_ticks(),
+        }
+    }
+
+    /// Sleeps until the next read is due, then calls `read` and returns its
+    /// result.
+    pub fn sample<R, F: FnOnce() -> R>(&self, read: F) -> Result<R, ErrorCode> {
+        let interval = self.interval.to_ticks(Alarm::<S>::get_frequency()?);
+        let mut now = Alarm::<S>::get_ticks()?;
+        let due = self.next_due.get().unwrap_or(now);
+        // The next read is never scheduled more than an interval ahead, so a
+        // larger distance means it is already overdue.
+        let remaining = due.elapsed_since(now);
+        if remaining.0 != 0 && remaining.0 <= interval.0 {
+            Alarm::<S>::sleep_for(remaining)?;
+            now = due;
+        }
+        let next_due = match now.elapsed_since(due).0 >= interval.0 {
+            true => now + interval,
+            false => due + interval,
+        };
+        self.next_due.set(Some(next_due));
+        Ok(read())
+    }
+}
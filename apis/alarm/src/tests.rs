@@ -1,6 +1,7 @@
+use libtock_platform::ErrorCode;
 use libtock_unittest::fake;
 
-use crate::{Hz, Milliseconds, Ticks};
+use crate::{Convert, Hz, Milliseconds, PowerBudget, SamplingScheduler, Ticks};
 
 type Alarm = crate::Alarm<fake::Syscalls>;
 
@@ -12,6 +13,19 @@ fn get_freq() {
     assert_eq!(Alarm::get_frequency(), Ok(Hz(1000)));
 }
 
+#[test]
+fn get_ticks() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+
+    assert_eq!(Alarm::get_ticks(), Ok(Ticks(0)));
+    driver.advance(250);
+    assert_eq!(Alarm::get_ticks(), Ok(Ticks(250)));
+    assert_eq!(Alarm::sleep_for(Milliseconds(100)), Ok(()));
+    assert_eq!(Alarm::get_ticks(), Ok(Ticks(350)));
+}
+
 #[test]
 fn sleep() {
     let kernel = fake::Kernel::new();
@@ -22,3 +36,98 @@ fn sleep() {
     assert_eq!(Alarm::sleep_for(Ticks(1000)), Ok(()));
     assert_eq!(Alarm::sleep_for(Milliseconds(1000)), Ok(()));
 }
+
+#[test]
+fn set_relative() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+
+    driver.advance(50);
+    assert_eq!(Alarm::set_relative(Milliseconds(100)), Ok(Ticks(150)));
+    // The fake alarm does not support cancelling.
+    assert_eq!(Alarm::stop(), Err(ErrorCode::NoSupport));
+}
+
+#[test]
+fn ticks_wraparound() {
+    assert_eq!(Ticks(10) + Ticks(5), Ticks(15));
+    assert_eq!(Ticks(u32::MAX) + Ticks(1), Ticks(0));
+    assert_eq!(Ticks(u32::MAX - 2) + Ticks(10), Ticks(7));
+
+    assert_eq!(Ticks(15) - Ticks(5), Ticks(10));
+    assert_eq!(Ticks(0) - Ticks(1), Ticks(u32::MAX));
+    assert_eq!(Ticks(7) - Ticks(10), Ticks(u32::MAX - 2));
+
+    assert_eq!(Ticks(15).elapsed_since(Ticks(10)), Ticks(5));
+    assert_eq!(Ticks(0).elapsed_since(Ticks(u32::MAX)), Ticks(1));
+    assert_eq!(Ticks(7).elapsed_since(Ticks(u32::MAX - 2)), Ticks(10));
+    assert_eq!(Ticks(3).elapsed_since(Ticks(3)), Ticks(0));
+}
+
+#[test]
+fn milliseconds_to_ticks() {
+    assert_eq!(Milliseconds(1500).to_ticks(Hz(1000)), Ticks(1500));
+    assert_eq!(Milliseconds(1).to_ticks(Hz(32768)), Ticks(33));
+    // 32768 Hz doesn't divide evenly into milliseconds, so partial ticks
+    // round up.
+    assert_eq!(Milliseconds(1000).to_ticks(Hz(32768)), Ticks(32768));
+    assert_eq!(Milliseconds(3).to_ticks(Hz(32768)), Ticks(99));
+    assert_eq!(Milliseconds(0).to_ticks(Hz(32768)), Ticks(0));
+    assert_eq!(Milliseconds(7).to_ticks(Hz(16_000_000)), Ticks(112_000));
+    // Long durations don't overflow in the intermediate product.
+    assert_eq!(Milliseconds(200_000).to_ticks(Hz(32768)), Ticks(6_553_600));
+    assert_eq!(
+        Milliseconds(u32::MAX).to_ticks(Hz(16_000_000)),
+        Ticks(u32::MAX)
+    );
+    assert_eq!(Milliseconds(20), Milliseconds(20));
+}
+
+#[test]
+fn sampling_scheduler() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+
+    // A generous budget keeps the requested period.
+    let budget = PowerBudget {
+        sample_cost_uj: 10,
+        budget_uw: 1000,
+    };
+    assert_eq!(budget.min_interval(), Milliseconds(10));
+    let scheduler = SamplingScheduler::<fake::Syscalls>::new(Milliseconds(100), budget);
+    assert_eq!(scheduler.interval(), Milliseconds(100));
+
+    // A tight budget spaces the reads further apart.
+    let budget = PowerBudget {
+        sample_cost_uj: 10,
+        budget_uw: 50,
+    };
+    let scheduler = SamplingScheduler::<fake::Syscalls>::new(Milliseconds(100), budget);
+    assert_eq!(scheduler.interval(), Milliseconds(200));
+
+    assert_eq!(scheduler.next_sample_due(), Ok(Ticks(0)));
+    for expected in [0, 200, 400] {
+        assert_eq!(scheduler.sample(Alarm::get_ticks), Ok(Ok(Ticks(expected))));
+    }
+    assert_eq!(scheduler.next_sample_due(), Ok(Ticks(600)));
+
+    // A slightly late read keeps the schedule.
+    driver.advance(250);
+    assert_eq!(scheduler.sample(Alarm::get_ticks), Ok(Ok(Ticks(650))));
+    assert_eq!(scheduler.next_sample_due(), Ok(Ticks(800)));
+
+    // After missing a whole interval, the schedule restarts from the late read
+    // rather than catching up.
+    driver.advance(1000);
+    assert_eq!(scheduler.sample(Alarm::get_ticks), Ok(Ok(Ticks(1650))));
+    assert_eq!(scheduler.next_sample_due(), Ok(Ticks(1850)));
+    assert_eq!(scheduler.sample(Alarm::get_ticks), Ok(Ok(Ticks(1850))));
+
+    let no_budget = PowerBudget {
+        sample_cost_uj: 10,
+        budget_uw: 0,
+    };
+    assert_eq!(no_budget.min_interval(), Milliseconds(u32::MAX));
+}
@@ -0,0 +1,115 @@
+use crate::{AmbientLight, Lux, DEFAULT_DRIVER_NUM};
+use core::cell::Cell;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use libtock_platform::{
+    share, subscribe::OneId, DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall,
+};
+
+/// The upcall used by `AmbientLight::read_async`. It stores the reading and
+/// wakes the task waiting for it.
+#[derive(Default)]
+pub struct AsyncReading {
+    intensity: Cell<Option<u32>>,
+    waker: Cell<Option<Waker>>,
+}
+
+impl AsyncReading {
+    pub fn new() -> AsyncReading {
+        Default::default()
+    }
+}
+
+impl<const DRIVER_NUM: u32> Upcall<OneId<DRIVER_NUM, 0>> for AsyncReading {
+    fn upcall(&self, intensity: u32, _flags: u32, _arg2: u32) {
+        self.intensity.set(Some(intensity));
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<S: Syscalls, const DRIVER_NUM: u32> AmbientLight<S, DRIVER_NUM> {
+    /// Initiate a light intensity reading, returning a future that resolves
+    /// once the reading is available.
+    ///
+    /// The task is woken from the upcall, so the executor must yield to the
+    /// kernel while it has no tasks ready to run. Dropping the future before
+    /// it completes unregisters the upcall.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let reading = AsyncReading::new();
+    /// share::scope(|subscribe| {
+    ///     let intensity = executor.block_on(AmbientLight::read_async(&reading, subscribe)?);
+    /// });
+    /// ```
+    pub fn read_async<'share>(
+        reading: &'share AsyncReading,
+        subscribe: share::Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+    ) -> Result<ReadIntensity<'share, S, DRIVER_NUM>, ErrorCode> {
+        reading.intensity.set(None);
+        S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, reading)?;
+        let future = ReadIntensity {
+            reading,
+            done: false,
+            _syscalls: PhantomData,
+        };
+        Self::read_intensity()?;
+        Ok(future)
+    }
+}
+
+/// A pending light intensity reading, created by `AmbientLight::read_async`.
+///
+/// It borrows the `AsyncReading` the upcall writes to, which uses `Cell`s, so
+/// it is neither `Send` nor `Sync`.
+pub struct ReadIntensity<'share, S: Syscalls, const DRIVER_NUM: u32 = DEFAULT_DRIVER_NUM> {
+    reading: &'share AsyncReading,
+    done: bool,
+    _syscalls: PhantomData<S>,
+}
+
+impl<'share, S: Syscalls, const DRIVER_NUM: u32> ReadIntensity<'share, S, DRIVER_NUM> {
+    /// Consumes the future without unregistering the upcall, so the reading
+    /// still lands in the `AsyncReading` once it completes.
+    ///
+    /// The enclosing `share::scope` still unregisters the upcall when it ends,
+    /// so leaking the future cannot leave the kernel with a dangling pointer.
+    pub fn leak(self) {
+        core::mem::forget(self);
+    }
+}
+
+// ReadIntensity does not rely on being pinned; the only non-Unpin type it may
+// contain is the PhantomData<S>.
+impl<'share, S: Syscalls, const DRIVER_NUM: u32> Unpin for ReadIntensity<'share, S, DRIVER_NUM> {}
+
+impl<'share, S: Syscalls, const DRIVER_NUM: u32> Future for ReadIntensity<'share, S, DRIVER_NUM> {
+    type Output = Lux;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Lux> {
+        match self.reading.intensity.take() {
+            Some(intensity) => {
+                self.done = true;
+                S::unsubscribe(DRIVER_NUM, 0);
+                Poll::Ready(Lux(intensity))
+            }
+            None => {
+                self.reading.waker.set(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<'share, S: Syscalls, const DRIVER_NUM: u32> Drop for ReadIntensity<'share, S, DRIVER_NUM> {
+    fn drop(&mut self) {
+        if !self.done {
+            S::unsubscribe(DRIVER_NUM, 0);
+            self.reading.waker.set(None);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::IntensityListener;
+use core::cell::Cell;
+use libtock_platform::{subscribe::OneId, Upcall};
+
+/// What `Decimated` does with the readings it does not deliver.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Decimation {
+    /// Skipped readings are dropped; every `n`th reading is delivered as is.
+    Drop,
+    /// Every `n`th delivery is the average of the last `n` readings, rounded
+    /// down.
+    Average,
+}
+
+/// A listener that only passes every `n`th reading on, created by
+/// `IntensityListener::decimate`. Useful with `AmbientLight::start_continuous`
+/// when the sensor's slowest continuous rate is faster than the app needs.
+///
+/// # Example
+/// ```ignore
+/// let listener = IntensityListener(|intensity| {
+///     // called once per second, with the average of the last 10 readings
+/// })
+/// .decimate(10, Decimation::Average);
+/// share::scope(|subscribe| {
+///     let _continuous = AmbientLight::start_continuous(10, &listener, subscribe)?;
+///     loop {
+///         TockSyscalls::yield_wait();
+///     }
+/// });
+/// ```
+pub struct Decimated<F: Fn(u32)> {
+    listener: IntensityListener<F>,
+    n: u32,
+    mode: Decimation,
+    count: Cell<u32>,
+    sum: Cell<u64>,
+}
+
+impl<F: Fn(u32)> IntensityListener<F> {
+    /// Wraps this listener so it is only called for every `n`th reading. An
+    /// `n` of 0 is treated as 1.
+    pub fn decimate(self, n: u32, mode: Decimation) -> Decimated<F> {
+        Decimated {
+            listener: self,
+            n: n.max(1),
+            mode,
+            count: Cell::new(0),
+            sum: Cell::new(0),
+        }
+    }
+}
+
+impl<F: Fn(u32)> Decimated<F> {
+    /// Discards the readings counted towards the next delivery, so it happens
+    /// `n` readings from now.
+    pub fn reset(&self) {
+        self.count.set(0);
+        self.sum.set(0);
+    }
+}
+
+impl<F: Fn(u32), const DRIVER_NUM: u32> Upcall<OneId<DRIVER_NUM, 0>> for Decimated<F> {
+    fn upcall(&self, intensity: u32, _arg1: u32, _arg2: u32) {
+        let count = self.count.get() + 1;
+        let sum = self.sum.get() + intensity as u64;
+        if count < self.n {
+            self.count.set(count);
+            self.sum.set(sum);
+            return;
+        }
+        self.reset();
+        (self.listener.0)(match self.mode {
+            Decimation::Drop => intensity,
+            Decimation::Average => (sum / count as u64) as u32,
+        });
+    }
+}
@@ -1,22 +1,83 @@
 #![no_std]
 
 use core::cell::Cell;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use libtock_alarm::{Alarm, Hz, Milliseconds, Ticks};
 use libtock_platform::{
-    share, subscribe::OneId, DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall,
+    return_variant, share, subscribe::OneId, syscall_class, Calibration, CommandReturn,
+    DefaultConfig, ErrorCode, Percent, RawCount, Register, ReturnVariant, Subscribe, Syscalls,
+    Upcall,
 };
 
-pub struct AmbientLight<S: Syscalls>(S);
+mod async_read;
+mod decimate;
+mod light_controller;
+mod rate_limit;
+mod schedule;
+mod significant_change;
+mod watch;
 
-impl<S: Syscalls> AmbientLight<S> {
+pub use async_read::{AsyncReading, ReadIntensity};
+pub use decimate::{Decimated, Decimation};
+pub use light_controller::LightController;
+pub use rate_limit::RateLimitedReader;
+pub use schedule::{PendingPolicy, ScheduledReads};
+pub use significant_change::SignificantChange;
+pub use watch::{LightLevel, ThresholdWatch, WatchMode};
+
+/// The function signature of an upcall registered through
+/// `AmbientLight::subscribe_raw`.
+pub type RawUpcall = unsafe extern "C" fn(u32, u32, u32, Register);
+
+/// Returns the difference between two intensities, in lux, regardless of
+/// which one is larger. Delta-based logic should use this rather than
+/// subtracting, as `u32` subtraction overflows when the light dims.
+pub fn lux_abs_diff(a: u32, b: u32) -> u32 {
+    a.abs_diff(b)
+}
+
+/// The ambient light driver.
+///
+/// `DRIVER_NUM` defaults to the standard ambient light driver number, and
+/// should only be overridden for boards that register the driver under a
+/// different number. Using a number that belongs to another driver results in
+/// commands being sent to that driver, which may interpret them in unexpected
+/// ways.
+///
+/// `AmbientLight` holds no data: it only names the driver, and every method
+/// makes its system calls on the calling thread. It is therefore `Send` and
+/// `Sync` whenever `S` is.
+///
+/// # Malformed kernel data
+/// Values from the kernel that break the driver's contract, such as a
+/// resolution outside 1 to 32 bits, fail a `debug_assert!` so they are noticed
+/// during development. Release builds never panic on them: they are handled
+/// as documented at each conversion, e.g. by returning `BadRVal`. Unknown
+/// reading flag bits are not malformed; they are reserved for future flags
+/// and ignored.
+pub struct AmbientLight<S: Syscalls, const DRIVER_NUM: u32 = DEFAULT_DRIVER_NUM>(S);
+
+impl<S: Syscalls, const DRIVER_NUM: u32> AmbientLight<S, DRIVER_NUM> {
     /// Returns Ok() if the driver was present.This does not necessarily mean
     /// that the driver is working.
     pub fn exists() -> Result<(), ErrorCode> {
-        S::command(DRIVER_NUM, EXISTS, 0, 0).to_result()
+        Self::command_raw(EXISTS, 0, 0).to_result()
+    }
+
+    /// Re-runs the existence check, for boards where the driver may be loaded
+    /// after the application starts. Returns true if the driver is now
+    /// present. `AmbientLight` keeps no state of its own, so once this returns
+    /// true every other method is usable; a `Capabilities` probed earlier
+    /// must be probed again to notice the driver.
+    pub fn reprobe() -> bool {
+        Self::exists().is_ok()
     }
 
     /// Initiate a light intensity reading.
     pub fn read_intensity() -> Result<(), ErrorCode> {
-        S::command(DRIVER_NUM, READ_INTENSITY, 0, 0).to_result()
+        Self::command_raw(READ_INTENSITY, 0, 0).to_result()
     }
 
     /// Register an events listener
@@ -32,6 +93,110 @@ impl<S: Syscalls> AmbientLight<S> {
         S::unsubscribe(DRIVER_NUM, 0)
     }
 
+    /// Register an events listener and run `f` while it stays registered,
+    /// failing with `Already` while a listener registered through another
+    /// `register_exclusive` is still active. This keeps two parts of an app
+    /// from silently replacing each other's listener. The listener is
+    /// unregistered when `f` returns.
+    ///
+    /// `register_exclusive` opens the `share::scope` itself, so a rejected
+    /// call never holds a `Subscribe` handle whose cleanup would unregister the
+    /// active listener.
+    ///
+    /// ```ignore
+    /// let listener = IntensityListener(|intensity| { /* ... */ });
+    /// AmbientLight::register_exclusive(&listener, || {
+    ///     // yield while the listener is registered
+    /// })?;
+    /// ```
+    ///
+    /// Exclusivity is tracked per driver number, for up to
+    /// `EXCLUSIVE_DRIVERS` drivers at once; registering a listener for one
+    /// more driver returns `NoMem`. Listeners registered with
+    /// `register_listener` are not tracked.
+    pub fn register_exclusive<F: Fn(u32), G: FnOnce() -> R, R>(
+        listener: &IntensityListener<F>,
+        f: G,
+    ) -> Result<R, ErrorCode> {
+        let _claim = ExclusiveClaim::take(DRIVER_NUM)?;
+        share::scope(|subscribe| {
+            Self::register_listener(listener, subscribe)?;
+            Ok(f())
+        })
+    }
+
+    /// Register an events listener, unless `subscription` shows a listener was
+    /// already registered through it. Useful in retry loops, where registering
+    /// again would be redundant.
+    ///
+    /// `subscription` should be created inside the same `share::scope` as
+    /// `subscribe`, as the listener is unregistered when the scope ends.
+    pub fn ensure_subscribed<'share, F: Fn(u32)>(
+        subscription: &Subscription,
+        listener: &'share IntensityListener<F>,
+        subscribe: share::Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+    ) -> Result<(), ErrorCode> {
+        if subscription.is_active() {
+            return Ok(());
+        }
+        Self::register_listener(listener, subscribe)?;
+        subscription.0.set(true);
+        Ok(())
+    }
+
+    /// Unregister the events listener registered through `subscription`, so
+    /// that the next `ensure_subscribed` registers a listener again.
+    pub fn ensure_unsubscribed(subscription: &Subscription) {
+        if subscription.0.replace(false) {
+            Self::unregister_listener();
+        }
+    }
+
+    /// Sends an arbitrary command to the driver and returns the kernel's
+    /// response undecoded. The other commands of this API are built on top of
+    /// this, so it can be used for driver commands the API does not support
+    /// yet.
+    pub fn command_raw(command_id: u32, argument0: u32, argument1: u32) -> CommandReturn {
+        S::command(DRIVER_NUM, command_id, argument0, argument1)
+    }
+
+    /// Registers `upcall` directly with the kernel for the given subscribe
+    /// number, bypassing `share::scope`. The kernel calls it with the three
+    /// upcall arguments and `userdata`. The upcall must be removed with
+    /// `S::unsubscribe(DRIVER_NUM, subscribe_num)` once it is no longer
+    /// needed.
+    ///
+    /// This is a deliberately separate escape hatch: the safe API subscribes
+    /// through `Syscalls::subscribe`, which ties registrations to a
+    /// `share::scope`, and is not built on top of this.
+    ///
+    /// # Safety
+    /// - Whatever `userdata` refers to must stay valid until the upcall is
+    ///   unsubscribed or replaced; nothing removes it automatically.
+    /// - `upcall` runs from within `yield` system calls, so it may run while
+    ///   the code that yielded is holding references to the same data. It
+    ///   must only access `userdata` in ways that are sound under that
+    ///   re-entrancy, e.g. through `Cell`s.
+    /// - `upcall` must not unwind.
+    pub unsafe fn subscribe_raw(
+        subscribe_num: u32,
+        upcall: RawUpcall,
+        userdata: Register,
+    ) -> Result<(), ErrorCode> {
+        // syscall4 can be used to call Subscribe. The caller is responsible
+        // for userdata outliving the registration.
+        let [r0, r1, _, _] = S::syscall4::<{ syscall_class::SUBSCRIBE }>([
+            DRIVER_NUM.into(),
+            subscribe_num.into(),
+            Register::from(upcall as *const ()),
+            userdata,
+        ]);
+        if ReturnVariant::from(r0.as_u32()) == return_variant::FAILURE_2_U32 {
+            return Err(ErrorCode::try_from(r1.as_u32()).unwrap_or(ErrorCode::BadRVal));
+        }
+        Ok(())
+    }
+
     /// Initiate a synchronous light intensity measurement.
     /// Returns Ok(intensity_value) if the operation was successful
     /// intensity_value is returned in lux
@@ -41,7 +206,7 @@ impl<S: Syscalls> AmbientLight<S> {
             intensity_cell.set(Some(intensity_val));
         });
 
-        share::scope(|subscribe| {
+        let result = share::scope(|subscribe| {
             Self::register_listener(&listener, subscribe)?;
             Self::read_intensity()?;
             while intensity_cell.get() == None {
@@ -52,10 +217,784 @@ impl<S: Syscalls> AmbientLight<S> {
                 None => Err(ErrorCode::Busy),
                 Some(intensity_val) => Ok(intensity_val),
             }
+        });
+        log_error::<S, _>("read_intensity_sync", result)
+    }
+
+    /// Like `read_intensity_sync`, but returns the reading as reported by the
+    /// driver rather than as lux, for use with a `Calibration`.
+    pub fn read_raw() -> Result<RawCount, ErrorCode> {
+        Self::read_intensity_sync().map(RawCount::new)
+    }
+
+    /// Like `read_intensity_sync`, but passes the reading through
+    /// `calibration` before returning it.
+    pub fn read_intensity_calibrated_sync<C: Calibration>(
+        calibration: &C,
+    ) -> Result<i32, ErrorCode> {
+        Self::read_raw().map(|raw| calibration.calibrate(raw))
+    }
+
+    /// Initiate a synchronous light intensity measurement, returning it as a
+    /// percentage of the sensor's full scale. The full scale is
+    /// derived from `resolution_bits`.
+    ///
+    /// The percentage is linear in the reading, whereas perceived brightness
+    /// is roughly logarithmic, so most indoor scenes read as a few percent.
+    /// For brightness bars, use `Lux::log_percent` on a reading instead.
+    pub fn read_percent() -> Result<Percent, ErrorCode> {
+        let resolution_bits = Self::resolution_bits()?;
+        Ok(Lux(Self::read_intensity_sync()?).percent(resolution_bits))
+    }
+
+    /// Initiate a synchronous light intensity measurement, returning the
+    /// reading along with the quality flags reported by the driver. Drivers
+    /// that do not report flags produce `ReadingFlags::default()`.
+    ///
+    /// If the driver reports its maximum measurable intensity (see
+    /// `max_lux`), readings at or above it are clamped to it and flagged as
+    /// saturated, even if the driver did not flag them.
+    pub fn read_intensity_with_flags_sync() -> Result<(Lux, ReadingFlags), ErrorCode> {
+        let max_lux = match Self::max_lux() {
+            Ok(max_lux) => Some(max_lux),
+            Err(ErrorCode::NoSupport) => None,
+            Err(error) => return Err(error),
+        };
+        let reading: Cell<Option<(u32, u32)>> = Cell::new(None);
+
+        let (intensity, mut flags) = share::scope(|subscribe| {
+            S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, &reading)?;
+            Self::read_intensity()?;
+            loop {
+                S::yield_wait();
+                if let Some((intensity, flags)) = reading.get() {
+                    return Ok((Lux(intensity), ReadingFlags::from(flags)));
+                }
+            }
+        })?;
+        match max_lux {
+            Some(max_lux) if intensity >= max_lux => {
+                flags.saturated = true;
+                Ok((intensity.clamp_to(max_lux), flags))
+            }
+            _ => Ok((intensity, flags)),
+        }
+    }
+
+    /// Returns the highest intensity the sensor can measure in its current
+    /// configuration. Returns `NoSupport` if the driver does not report it.
+    pub fn max_lux() -> Result<Lux, ErrorCode> {
+        Self::command_raw(MAX_LUX, 0, 0).to_result().map(Lux)
+    }
+
+    /// Read the sensor's separate light channels. Drivers for single-channel
+    /// sensors report `NoSupport`, in which case a regular intensity reading is
+    /// returned as the visible channel, with an infrared channel of 0.
+    pub fn read_channels() -> Result<LightChannels, ErrorCode> {
+        match Self::command_raw(READ_CHANNELS, 0, 0).to_result::<(u32, u32), ErrorCode>() {
+            Ok((visible, ir)) => Ok(LightChannels { visible, ir }),
+            Err(ErrorCode::NoSupport) => Ok(LightChannels {
+                visible: Self::read_intensity_sync()?,
+                ir: 0,
+            }),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Initiate a synchronous light intensity measurement, returning the
+    /// reading along with the alarm's counter value when it arrived.
+    ///
+    /// The timestamp is taken when the app observed the reading, which is
+    /// after the sensor finished integrating it. Use it as a consistent time
+    /// base for logging, not as the exact time of the measurement.
+    pub fn read_timestamped() -> Result<(Lux, Ticks), ErrorCode> {
+        let intensity = Self::read_intensity_sync()?;
+        let now = Alarm::<S>::get_ticks()?;
+        Ok((Lux(intensity), now))
+    }
+
+    /// Perform a synchronous reading and write it to `console` as a line such
+    /// as `lux=450`. Returns the reading. A failed console write is reported
+    /// as `Fail`.
+    #[cfg(feature = "console")]
+    pub fn log_reading(console: &mut libtock_console::ConsoleWriter<S>) -> Result<Lux, ErrorCode> {
+        use core::fmt::Write;
+        let lux = Lux(Self::read_intensity_sync()?);
+        writeln!(console, "lux={}", lux).map_err(|_| ErrorCode::Fail)?;
+        Ok(lux)
+    }
+
+    /// Perform `N` synchronous light intensity measurements and return them
+    /// in order, for statistics over a fixed window of samples. If a
+    /// measurement fails, its error is returned and the earlier samples are
+    /// discarded.
+    pub fn collect_samples<const N: usize>() -> Result<[Lux; N], ErrorCode> {
+        let mut samples = [Lux(0); N];
+        for sample in &mut samples {
+            *sample = Lux(Self::read_intensity_sync()?);
+        }
+        Ok(samples)
+    }
+
+    /// Perform `n` synchronous light intensity measurements and return their
+    /// average. Returns `Invalid` if `n` is 0.
+    pub fn measure_many(n: u8) -> Result<Lux, ErrorCode> {
+        if n == 0 {
+            return Err(ErrorCode::Invalid);
+        }
+        let mut sum: u64 = 0;
+        for _ in 0..n {
+            sum += Self::read_intensity_sync()? as u64;
+        }
+        Ok(Lux((sum / n as u64) as u32))
+    }
+
+    /// Perform synchronous light intensity measurements, passing each one to
+    /// `f`, until `f` returns `ControlFlow::Break`. Returns the value `f`
+    /// broke with, or the error of the first measurement that fails.
+    ///
+    /// ```ignore
+    /// // Wait for the room to get dark, and report how bright it was before.
+    /// let mut brightest = Lux(0);
+    /// let brightest = AmbientLight::read_until(|lux| {
+    ///     brightest = brightest.max(lux);
+    ///     match lux < Lux(10) {
+    ///         true => ControlFlow::Break(brightest),
+    ///         false => ControlFlow::Continue(()),
+    ///     }
+    /// })?;
+    /// ```
+    pub fn read_until<R, F: FnMut(Lux) -> ControlFlow<R>>(mut f: F) -> Result<R, ErrorCode> {
+        loop {
+            if let ControlFlow::Break(result) = f(Lux(Self::read_intensity_sync()?)) {
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Put the sensor in its low-power standby mode. Readings fail with `Off`
+    /// until the sensor is powered up again.
+    pub fn power_down() -> Result<(), ErrorCode> {
+        Self::command_raw(POWER_DOWN, 0, 0).to_result()
+    }
+
+    /// Wake the sensor from standby.
+    pub fn power_up() -> Result<(), ErrorCode> {
+        Self::command_raw(POWER_UP, 0, 0).to_result()
+    }
+
+    /// Returns whether the sensor is powered up, i.e. not in standby.
+    pub fn is_powered() -> Result<bool, ErrorCode> {
+        Self::command_raw(IS_POWERED, 0, 0)
+            .to_result::<u32, _>()
+            .map(|powered| powered != 0)
+    }
+
+    /// Wake the sensor from standby, returning a guard that powers it down
+    /// again when dropped. If the sensor was already powered up, the guard
+    /// leaves it powered up. Drivers that cannot report their power state are
+    /// assumed to have been in standby.
+    ///
+    /// ```ignore
+    /// {
+    ///     let _powered = AmbientLight::power_up_scoped()?;
+    ///     let intensity = AmbientLight::read_intensity_sync()?;
+    /// } // The sensor is back in standby here.
+    /// ```
+    pub fn power_up_scoped() -> Result<PoweredUp<S, DRIVER_NUM>, ErrorCode> {
+        let was_powered = Self::is_powered().unwrap_or(false);
+        if !was_powered {
+            Self::power_up()?;
+        }
+        Ok(PoweredUp {
+            power_down: !was_powered,
+            _syscalls: PhantomData,
+        })
+    }
+
+    /// Start delivering readings to `listener` at `rate_hz` readings per
+    /// second, until the returned `Continuous` is dropped. `rate_hz` must be
+    /// one of `CONTINUOUS_RATES`, otherwise `Invalid` is returned.
+    ///
+    /// Returns `NoSupport` if the sensor has no continuous mode, in which case
+    /// apps can fall back to polling with `read_intensity_sync`.
+    ///
+    /// `listener` is usually an `IntensityListener`, or a `Decimated` one to
+    /// receive fewer readings than the sensor produces.
+    ///
+    /// ```ignore
+    /// let listener = IntensityListener(|intensity| {
+    ///     // make use of the intensity value
+    /// });
+    /// share::scope(|subscribe| {
+    ///     let _continuous = AmbientLight::start_continuous(10, &listener, subscribe)?;
+    ///     loop {
+    ///         TockSyscalls::yield_wait();
+    ///     }
+    /// });
+    /// ```
+    pub fn start_continuous<'share, U: Upcall<OneId<DRIVER_NUM, 0>>>(
+        rate_hz: u32,
+        listener: &'share U,
+        subscribe: share::Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+    ) -> Result<Continuous<S, DRIVER_NUM>, ErrorCode> {
+        if !CONTINUOUS_RATES.contains(&rate_hz) {
+            return Err(ErrorCode::Invalid);
+        }
+        S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, listener)?;
+        Self::command_raw(SET_SAMPLE_RATE, rate_hz, 0).to_result::<(), ErrorCode>()?;
+        Self::command_raw(START_CONTINUOUS, 0, 0).to_result::<(), ErrorCode>()?;
+        Ok(Continuous(PhantomData))
+    }
+
+    /// Stop delivering readings started by `start_continuous`.
+    pub fn stop_continuous() -> Result<(), ErrorCode> {
+        Self::command_raw(STOP_CONTINUOUS, 0, 0).to_result()
+    }
+
+    /// Returns the resolution of the sensor's raw readings, in bits. The
+    /// resolution may depend on the sensor's configuration, such as its
+    /// integration time. Drivers that do not report their resolution are
+    /// assumed to use `DEFAULT_RESOLUTION_BITS`.
+    pub fn resolution_bits() -> Result<u8, ErrorCode> {
+        match Self::command_raw(RESOLUTION, 0, 0).to_result::<u32, ErrorCode>() {
+            Ok(bits @ 1..=32) => Ok(bits as u8),
+            Ok(bits) => {
+                debug_assert!(false, "invalid resolution of {} bits", bits);
+                Err(ErrorCode::BadRVal)
+            }
+            Err(ErrorCode::NoSupport) => Ok(DEFAULT_RESOLUTION_BITS),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns the sensor's integration time. Longer integration times give
+    /// more precise readings in low light, but take longer.
+    pub fn integration_time() -> Result<Milliseconds, ErrorCode> {
+        Self::command_raw(INTEGRATION_TIME, 0, 0)
+            .to_result()
+            .map(Milliseconds)
+    }
+
+    /// Sets the sensor's integration time. Returns `Invalid` for a time of 0.
+    pub fn set_integration_time(time: Milliseconds) -> Result<(), ErrorCode> {
+        Self::command_raw(SET_INTEGRATION_TIME, time.0, 0).to_result()
+    }
+
+    /// Takes one synchronous reading with the integration time set to `time`,
+    /// then restores the previous integration time, whether or not the
+    /// reading succeeded. An error while restoring it is not reported.
+    pub fn read_with_integration(time: Milliseconds) -> Result<Lux, ErrorCode> {
+        let previous = Self::integration_time()?;
+        Self::set_integration_time(time)?;
+        let _restore = RestoreIntegrationTime::<S, DRIVER_NUM>(previous, PhantomData);
+        Self::read_intensity_sync().map(Lux)
+    }
+
+    /// Watch for the light intensity crossing `threshold`, using the driver's
+    /// threshold interrupts if it has them and polling otherwise. No system
+    /// calls are made until `ThresholdWatch::wait` is called.
+    pub fn watch<F: Fn(u32)>(threshold: u32, callback: F) -> ThresholdWatch<S, F, DRIVER_NUM> {
+        ThresholdWatch::new(threshold, callback)
+    }
+
+    /// Read the sensor every `interval` from the app's event loop, calling
+    /// `callback` with each reading. No system calls are made until
+    /// `ScheduledReads::start` is called.
+    pub fn schedule_reads<F: Fn(Lux)>(
+        interval: Milliseconds,
+        callback: F,
+    ) -> ScheduledReads<S, F, DRIVER_NUM> {
+        ScheduledReads::new(interval, callback)
+    }
+
+    /// Restore the sensor's configuration to its defaults: the default range,
+    /// powered up, and continuous mode stopped. Returns `NoSupport` if the
+    /// driver cannot reset the sensor.
+    ///
+    /// Guards such as `Continuous` and `PoweredUp` still issue their commands
+    /// when dropped after a reset. An `AutoRange` should be reset through
+    /// `AutoRange::reset_config` instead, so that it forgets its selected range.
+    pub fn reset_config() -> Result<(), ErrorCode> {
+        Self::command_raw(RESET, 0, 0).to_result()
+    }
+
+    /// Select the sensitivity range of the sensor.
+    pub fn set_range(range: Range) -> Result<(), ErrorCode> {
+        Self::command_raw(SET_RANGE, range as u32, 0).to_result()
+    }
+
+    /// Enable auto-ranging. The sensor is switched to `Range::Normal`, and the
+    /// returned `AutoRange` adjusts the range on each reading so that values
+    /// stay between `low` and `high` lux.
+    pub fn enable_autorange(low: u32, high: u32) -> Result<AutoRange<S, DRIVER_NUM>, ErrorCode> {
+        if low >= high {
+            return Err(ErrorCode::Invalid);
+        }
+        Self::set_range(Range::Normal)?;
+        Ok(AutoRange {
+            low,
+            high,
+            range: Range::Normal,
+            candidate: None,
+            _syscalls: PhantomData,
+        })
+    }
+}
+
+/// Tracks whether a listener was registered by
+/// `AmbientLight::ensure_subscribed`.
+#[derive(Default)]
+pub struct Subscription(Cell<bool>);
+
+impl Subscription {
+    pub fn new() -> Subscription {
+        Default::default()
+    }
+
+    /// Returns `true` if a listener is registered through this subscription.
+    pub fn is_active(&self) -> bool {
+        self.0.get()
+    }
+}
+
+/// A light intensity, in lux.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Lux(pub u32);
+
+/// Displays the number of lux, without a unit.
+impl core::fmt::Display for Lux {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A light intensity, in thousandths of a lux, for dim light where whole lux
+/// lose detail.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Millilux(pub u32);
+
+impl Millilux {
+    /// Returns an adapter that displays the intensity in lux with
+    /// `precision` decimal places (at most 3), rounded to nearest, followed by
+    /// ` lx`, e.g. `4.50 lx`.
+    pub fn display_with(self, precision: u8) -> LuxDisplay {
+        LuxDisplay {
+            millilux: self.0,
+            precision: precision.min(3),
+        }
+    }
+}
+
+/// Saturates at `u32::MAX` millilux.
+impl From<Lux> for Millilux {
+    fn from(lux: Lux) -> Millilux {
+        Millilux(lux.0.saturating_mul(1000))
+    }
+}
+
+/// Displays an intensity with a fixed number of decimal places. Created by
+/// `Lux::display_with` and `Millilux::display_with`.
+#[derive(Copy, Clone, Debug)]
+pub struct LuxDisplay {
+    millilux: u32,
+    precision: u8,
+}
+
+impl core::fmt::Display for LuxDisplay {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let scale = 10u64.pow(3 - self.precision as u32);
+        let units = (self.millilux as u64 + scale / 2) / scale;
+        let divisor = 10u64.pow(self.precision as u32);
+        match self.precision {
+            0 => write!(f, "{} lx", units),
+            precision => write!(
+                f,
+                "{}.{:0width$} lx",
+                units / divisor,
+                units % divisor,
+                width = precision as usize
+            ),
+        }
+    }
+}
+
+impl Lux {
+    /// Returns an adapter that displays the intensity followed by ` lx`, with
+    /// `precision` decimal places. See `Millilux::display_with`.
+    pub fn display_with(self, precision: u8) -> LuxDisplay {
+        Millilux::from(self).display_with(precision)
+    }
+
+    /// Limits the intensity to at most `max`, such as the maximum intensity a
+    /// sensor can measure.
+    pub fn clamp_to(self, max: Lux) -> Lux {
+        self.min(max)
+    }
+
+    /// Converts a raw sensor count to lux, given the resolution reported by
+    /// `AmbientLight::resolution_bits` and the intensity corresponding to the
+    /// maximum count.
+    pub fn from_counts(counts: u32, resolution_bits: u8, full_scale: Lux) -> Lux {
+        let max_count = u64::MAX >> (64 - resolution_bits.clamp(1, 32) as u32);
+        let counts = (counts as u64).min(max_count);
+        Lux((counts * full_scale.0 as u64 / max_count) as u32)
+    }
+
+    /// Converts a reading to a percentage of the sensor's full scale at the
+    /// given resolution, rounding down.
+    pub fn percent(self, resolution_bits: u8) -> Percent {
+        Percent::new(Lux::from_counts(self.0, resolution_bits, Lux(100)).0 as u8)
+    }
+
+    /// Like `percent`, but on a logarithmic scale: each doubling of the
+    /// reading adds the same amount, so half of the percentage range covers
+    /// readings up to the square root of the full scale. This is closer to
+    /// how bright a reading looks than `percent`.
+    pub fn log_percent(self, resolution_bits: u8) -> Percent {
+        let bits = resolution_bits.clamp(1, 32) as u64;
+        let counts = (self.0 as u64).min(u64::MAX >> (64 - bits)) + 1;
+        // log2(counts) in units of 1/256, interpolating linearly between
+        // powers of two.
+        let whole = 63 - counts.leading_zeros() as u64;
+        let log2 = (whole << 8) + ((counts << 8) >> whole) - 256;
+        Percent::new((log2 * 100 / (bits << 8)) as u8)
+    }
+
+    /// The exposure value for a camera at the given ISO speed, from the
+    /// incident-light meter equation `EV = log2(lux * iso / 250)`. At ISO 100,
+    /// 2.5 lux is EV 0 and each doubling of the light adds 1.
+    ///
+    /// Darkness (0 lux) has no exposure value; it returns
+    /// `f32::NEG_INFINITY`, as does an ISO speed of 0.
+    #[cfg(feature = "f32")]
+    pub fn to_exposure_value(self, iso: u32) -> f32 {
+        if self.0 == 0 || iso == 0 {
+            return f32::NEG_INFINITY;
+        }
+        log2_f32(self.0 as f32 * iso as f32 / 250.0)
+    }
+}
+
+// log2(x) for positive, normal x, accurate to about 1e-5. `core` has no
+// floating-point logarithm, so x is split into its exponent and a mantissa m
+// in [1, 2), and ln(m) is computed with the series
+// 2 * (t + t^3/3 + t^5/5 + ...) where t = (m - 1) / (m + 1) < 1/3.
+#[cfg(feature = "f32")]
+fn log2_f32(x: f32) -> f32 {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127;
+    let mantissa = f32::from_bits((bits & 0x007f_ffff) | 0x3f80_0000);
+    let t = (mantissa - 1.0) / (mantissa + 1.0);
+    let t2 = t * t;
+    let ln = 2.0 * t * (1.0 + t2 * (1.0 / 3.0 + t2 * (1.0 / 5.0 + t2 * (1.0 / 7.0))));
+    exponent as f32 + ln * core::f32::consts::LOG2_E
+}
+
+/// A rate of change of the light intensity, in lux per second. Positive when
+/// the light is getting brighter.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub struct LightRate(pub i32);
+
+impl LightRate {
+    /// The rate of change between two readings returned by
+    /// `AmbientLight::read_timestamped`, using the alarm frequency returned by
+    /// `Alarm::get_frequency`. The alarm counter may have wrapped around
+    /// between the readings, as long as less than a full period passed.
+    ///
+    /// Returns `None` if both readings have the same timestamp, or if the
+    /// frequency is 0, as no rate can be computed. Rates beyond the range of
+    /// `i32` saturate.
+    pub fn between(earlier: (Lux, Ticks), later: (Lux, Ticks), frequency: Hz) -> Option<LightRate> {
+        let elapsed = later.1.elapsed_since(earlier.1).0;
+        if elapsed == 0 || frequency.0 == 0 {
+            return None;
+        }
+        let (Lux(from), Lux(to)) = (earlier.0, later.0);
+        // The change fits in a u32 and the frequency in another, so their
+        // product cannot overflow a u64.
+        let rate = |change: u32| change as u64 * frequency.0 as u64 / elapsed as u64;
+        Some(match to.checked_sub(from) {
+            Some(increase) => LightRate(rate(increase).min(i32::MAX as u64) as i32),
+            None => LightRate(-(rate(from - to).min(i32::MAX as u64) as i32)),
         })
     }
 }
 
+/// Raw counts from a sensor with separate light channels, such as the
+/// TSL2561.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct LightChannels {
+    /// The channel sensitive to visible light. On sensors such as the TSL2561
+    /// this is a broadband channel, which also responds to infrared light.
+    pub visible: u32,
+    /// The infrared channel.
+    pub ir: u32,
+}
+
+/// Computes the intensity from a pair of channel counts using the TSL2561's
+/// piecewise channel-ratio formula. The formula assumes the sensor's nominal
+/// gain and integration time; readings taken with other settings need to be
+/// scaled first. Light with a high infrared ratio (above 1.3) yields 0 lux.
+pub fn compute_lux(channels: LightChannels) -> Lux {
+    let (ch0, ch1) = (channels.visible as u64, channels.ir as u64);
+    // Upper bounds of the ratio ch1 / ch0 (in thousandths), and the channel
+    // coefficients (in units of 1e-5) for ratios up to that bound.
+    const SEGMENTS: [(u64, u64, u64); 7] = [
+        (125, 3040, 2720),
+        (250, 3250, 4400),
+        (375, 3510, 5440),
+        (500, 3810, 6240),
+        (610, 2240, 3100),
+        (800, 1280, 1530),
+        (1300, 146, 112),
+    ];
+    let segment = SEGMENTS
+        .iter()
+        .find(|(ratio, _, _)| ch1 * 1000 <= ratio * ch0);
+    match segment {
+        Some(&(_, c0, c1)) => Lux(((c0 * ch0).saturating_sub(c1 * ch1) / 100_000) as u32),
+        None => Lux(0),
+    }
+}
+
+/// The resolution assumed for drivers that do not report one.
+pub const DEFAULT_RESOLUTION_BITS: u8 = 16;
+
+/// Quality flags the driver reports alongside a reading.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReadingFlags {
+    /// The sensor was saturated, so the actual intensity may be higher.
+    pub saturated: bool,
+    /// The sensor did not have new data ready, so the reading repeats an
+    /// earlier measurement.
+    pub stale: bool,
+}
+
+impl From<u32> for ReadingFlags {
+    fn from(flags: u32) -> ReadingFlags {
+        ReadingFlags {
+            saturated: flags & FLAG_SATURATED != 0,
+            stale: flags & FLAG_STALE != 0,
+        }
+    }
+}
+
+/// The sensitivity ranges of the sensor. More sensitive ranges use a higher
+/// gain and integration time, and saturate at lower light levels.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Range {
+    Bright = 0,
+    Normal = 1,
+    Dim = 2,
+}
+
+impl Range {
+    fn less_sensitive(self) -> Option<Range> {
+        match self {
+            Range::Bright => None,
+            Range::Normal => Some(Range::Bright),
+            Range::Dim => Some(Range::Normal),
+        }
+    }
+
+    fn more_sensitive(self) -> Option<Range> {
+        match self {
+            Range::Bright => Some(Range::Normal),
+            Range::Normal => Some(Range::Dim),
+            Range::Dim => None,
+        }
+    }
+}
+
+/// Keeps ambient light readings within a usable range by switching the
+/// sensor's range when readings approach its limits. Created by
+/// `AmbientLight::enable_autorange`.
+///
+/// To avoid oscillating between two ranges, the range is only changed after
+/// `AUTORANGE_SAMPLES` consecutive readings requested the same change.
+pub struct AutoRange<S: Syscalls, const DRIVER_NUM: u32 = DEFAULT_DRIVER_NUM> {
+    low: u32,
+    high: u32,
+    range: Range,
+    // The range the readings are asking for, and how many consecutive readings
+    // asked for it.
+    candidate: Option<(Range, u8)>,
+    _syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls, const DRIVER_NUM: u32> AutoRange<S, DRIVER_NUM> {
+    /// Returns the currently selected range.
+    pub fn current_range(&self) -> Range {
+        self.range
+    }
+
+    /// Restore the sensor's configuration to its defaults, as
+    /// `AmbientLight::reset_config` does, and start auto-ranging over from
+    /// `Range::Normal`.
+    pub fn reset_config(&mut self) -> Result<(), ErrorCode> {
+        AmbientLight::<S, DRIVER_NUM>::reset_config()?;
+        AmbientLight::<S, DRIVER_NUM>::set_range(Range::Normal)?;
+        self.range = Range::Normal;
+        self.candidate = None;
+        Ok(())
+    }
+
+    /// Perform a synchronous reading, then adjust the range if the reading was
+    /// outside of the `low..high` window. The returned value was measured
+    /// using the range that was selected before the call.
+    pub fn read_intensity_sync(&mut self) -> Result<u32, ErrorCode> {
+        let intensity = AmbientLight::<S, DRIVER_NUM>::read_intensity_sync()?;
+        let wanted = if intensity >= self.high {
+            self.range.less_sensitive()
+        } else if intensity <= self.low {
+            self.range.more_sensitive()
+        } else {
+            None
+        };
+        self.candidate = match (wanted, self.candidate) {
+            (None, _) => None,
+            (Some(wanted), Some((range, count))) if range == wanted => Some((range, count + 1)),
+            (Some(wanted), _) => Some((wanted, 1)),
+        };
+        if let Some((range, AUTORANGE_SAMPLES)) = self.candidate {
+            AmbientLight::<S, DRIVER_NUM>::set_range(range)?;
+            self.range = range;
+            self.candidate = None;
+        }
+        Ok(intensity)
+    }
+}
+
+/// The sample rates, in Hz, supported by `AmbientLight::start_continuous`.
+pub const CONTINUOUS_RATES: [u32; 4] = [1, 2, 5, 10];
+
+/// Keeps continuous mode running while it exists. Created by
+/// `AmbientLight::start_continuous`.
+///
+/// Neither `Send` nor `Sync`: dropping it stops continuous mode with a system
+/// call, which belongs on the thread that started it. Tock processes are
+/// single-threaded, and the unit test fake kernel is thread-local.
+pub struct Continuous<S: Syscalls, const DRIVER_NUM: u32 = DEFAULT_DRIVER_NUM>(
+    PhantomData<(S, *const ())>,
+);
+
+impl<S: Syscalls, const DRIVER_NUM: u32> Drop for Continuous<S, DRIVER_NUM> {
+    fn drop(&mut self) {
+        let _ = log_error::<S, _>(
+            "stop_continuous",
+            AmbientLight::<S, DRIVER_NUM>::stop_continuous(),
+        );
+    }
+}
+
+/// The number of ambient light drivers that can have a listener registered by
+/// `AmbientLight::register_exclusive` at the same time.
+pub const EXCLUSIVE_DRIVERS: usize = 4;
+
+// The driver numbers with an active listener registered by
+// `AmbientLight::register_exclusive`, so that drivers with different numbers
+// do not block each other.
+static EXCLUSIVE_ACTIVE: [ExclusiveSlot; EXCLUSIVE_DRIVERS] = [
+    ExclusiveSlot::new(),
+    ExclusiveSlot::new(),
+    ExclusiveSlot::new(),
+    ExclusiveSlot::new(),
+];
+
+// An entry of `EXCLUSIVE_ACTIVE`: the driver number is only meaningful while
+// `used` is set. Tock processes are single-threaded, so the slots need no
+// atomic read-modify-write (which some targets lack).
+struct ExclusiveSlot {
+    used: AtomicBool,
+    driver_num: AtomicU32,
+}
+
+impl ExclusiveSlot {
+    const fn new() -> ExclusiveSlot {
+        ExclusiveSlot {
+            used: AtomicBool::new(false),
+            driver_num: AtomicU32::new(0),
+        }
+    }
+}
+
+// Marks a driver as having an exclusive listener until dropped.
+struct ExclusiveClaim(&'static ExclusiveSlot);
+
+impl ExclusiveClaim {
+    fn take(driver_num: u32) -> Result<ExclusiveClaim, ErrorCode> {
+        if EXCLUSIVE_ACTIVE.iter().any(|slot| {
+            slot.used.load(Ordering::Relaxed)
+                && slot.driver_num.load(Ordering::Relaxed) == driver_num
+        }) {
+            return Err(ErrorCode::Already);
+        }
+        let slot = EXCLUSIVE_ACTIVE
+            .iter()
+            .find(|slot| !slot.used.load(Ordering::Relaxed))
+            .ok_or(ErrorCode::NoMem)?;
+        slot.driver_num.store(driver_num, Ordering::Relaxed);
+        slot.used.store(true, Ordering::Relaxed);
+        Ok(ExclusiveClaim(slot))
+    }
+}
+
+impl Drop for ExclusiveClaim {
+    fn drop(&mut self) {
+        self.0.used.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Keeps the sensor powered up while it exists. Created by
+/// `AmbientLight::power_up_scoped`.
+///
+/// Neither `Send` nor `Sync`, for the same reason as `Continuous`.
+pub struct PoweredUp<S: Syscalls, const DRIVER_NUM: u32 = DEFAULT_DRIVER_NUM> {
+    // Whether `power_up_scoped` powered the sensor up, and so should power it
+    // down again.
+    power_down: bool,
+    _syscalls: PhantomData<(S, *const ())>,
+}
+
+impl<S: Syscalls, const DRIVER_NUM: u32> Drop for PoweredUp<S, DRIVER_NUM> {
+    fn drop(&mut self) {
+        if self.power_down {
+            let _ = log_error::<S, _>("power_down", AmbientLight::<S, DRIVER_NUM>::power_down());
+        }
+    }
+}
+
+// Restores the integration time changed by
+// `AmbientLight::read_with_integration` when dropped.
+struct RestoreIntegrationTime<S: Syscalls, const DRIVER_NUM: u32>(Milliseconds, PhantomData<S>);
+
+impl<S: Syscalls, const DRIVER_NUM: u32> Drop for RestoreIntegrationTime<S, DRIVER_NUM> {
+    fn drop(&mut self) {
+        let _ = log_error::<S, _>(
+            "set_integration_time",
+            AmbientLight::<S, DRIVER_NUM>::set_integration_time(self.0),
+        );
+    }
+}
+
+// Writes the error in `result`, if any, to the console when the `console_log`
+// feature is enabled, for on-device diagnostics. Without the feature it only
+// returns `result`. Used where an error would otherwise go unnoticed, such as
+// in destructors.
+fn log_error<S: Syscalls, T>(
+    operation: &str,
+    result: Result<T, ErrorCode>,
+) -> Result<T, ErrorCode> {
+    #[cfg(feature = "console_log")]
+    if let Err(error) = &result {
+        libtock_console::log!(S, "ambient light: {} failed: {:?}", operation, error);
+    }
+    #[cfg(not(feature = "console_log"))]
+    let _ = operation;
+    result
+}
+
 /// A wrapper around a closure to be registered and called when
 /// a luminance reading is done.
 ///
@@ -66,7 +1005,7 @@ impl<S: Syscalls> AmbientLight<S> {
 /// ```
 pub struct IntensityListener<F: Fn(u32)>(pub F);
 
-impl<F: Fn(u32)> Upcall<OneId<DRIVER_NUM, 0>> for IntensityListener<F> {
+impl<F: Fn(u32), const DRIVER_NUM: u32> Upcall<OneId<DRIVER_NUM, 0>> for IntensityListener<F> {
     fn upcall(&self, intensity: u32, _arg1: u32, _arg2: u32) {
         self.0(intensity)
     }
@@ -79,9 +1018,31 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 0x60002;
+const DEFAULT_DRIVER_NUM: u32 = libtock_platform::driver_numbers::AMBIENT_LIGHT;
 
 // Command IDs
 
 const EXISTS: u32 = 0;
 const READ_INTENSITY: u32 = 1;
+const SET_RANGE: u32 = 2;
+const POWER_DOWN: u32 = 3;
+const POWER_UP: u32 = 4;
+const SET_SAMPLE_RATE: u32 = 5;
+const START_CONTINUOUS: u32 = 6;
+const STOP_CONTINUOUS: u32 = 7;
+const RESOLUTION: u32 = 8;
+const RESET: u32 = 9;
+const READ_CHANNELS: u32 = 10;
+const WATCH_THRESHOLD: u32 = 11;
+const MAX_LUX: u32 = 12;
+const INTEGRATION_TIME: u32 = 13;
+const SET_INTEGRATION_TIME: u32 = 14;
+const IS_POWERED: u32 = 15;
+
+// Reading flags, passed as the second upcall argument
+const FLAG_SATURATED: u32 = 1 << 0;
+const FLAG_STALE: u32 = 1 << 1;
+
+// Number of consecutive out-of-range readings required before `AutoRange`
+// switches ranges.
+const AUTORANGE_SAMPLES: u8 = 2;
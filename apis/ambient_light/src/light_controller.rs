@@ -0,0 +1,77 @@
+use crate::{AmbientLight, DEFAULT_DRIVER_NUM};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use libtock_leds::Leds;
+use libtock_platform::{ErrorCode, Syscalls};
+
+/// Turns an LED on when the light intensity drops below a threshold, and off
+/// when it rises back above it.
+///
+/// # Example
+/// ```ignore
+/// use libtock::ambient_light::LightController;
+///
+/// // Light LED 0 when there are fewer than 50 lux.
+/// let controller = LightController::new(0, 50);
+/// let error = controller.run();
+/// ```
+pub struct LightController<S: Syscalls, const DRIVER_NUM: u32 = DEFAULT_DRIVER_NUM> {
+    led: u32,
+    threshold: Cell<u32>,
+    // Whether the LED is currently on, or None before the first reading.
+    led_on: Cell<Option<bool>>,
+    _syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls, const DRIVER_NUM: u32> LightController<S, DRIVER_NUM> {
+    pub fn new(led: u32, threshold: u32) -> Self {
+        LightController {
+            led,
+            threshold: Cell::new(threshold),
+            led_on: Cell::new(None),
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Returns the current threshold, in lux.
+    pub fn threshold(&self) -> u32 {
+        self.threshold.get()
+    }
+
+    /// Changes the threshold. Takes effect with the next reading.
+    pub fn set_threshold(&self, threshold: u32) {
+        self.threshold.set(threshold);
+    }
+
+    /// Updates the LED for a new intensity reading. The LED driver is only
+    /// called when the LED has to change state.
+    pub fn update(&self, intensity: u32) -> Result<(), ErrorCode> {
+        let on = intensity < self.threshold.get();
+        if self.led_on.get() == Some(on) {
+            return Ok(());
+        }
+        match on {
+            true => Leds::<S>::on(self.led)?,
+            false => Leds::<S>::off(self.led)?,
+        }
+        self.led_on.set(Some(on));
+        Ok(())
+    }
+
+    /// Takes a single reading and updates the LED. Returns the reading.
+    pub fn step(&self) -> Result<u32, ErrorCode> {
+        let intensity = AmbientLight::<S, DRIVER_NUM>::read_intensity_sync()?;
+        self.update(intensity)?;
+        Ok(intensity)
+    }
+
+    /// Repeatedly takes readings and updates the LED. Only returns if an
+    /// error occurs, in which case the error is returned.
+    pub fn run(&self) -> ErrorCode {
+        loop {
+            if let Err(error) = self.step() {
+                return error;
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{AmbientLight, DEFAULT_DRIVER_NUM};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use libtock_alarm::{Alarm, Convert, Milliseconds, Ticks};
+use libtock_platform::{ErrorCode, Syscalls};
+
+/// Performs blocking light intensity readings, reusing the last reading if a
+/// new one is requested within a minimum interval of it.
+///
+/// Reading the sensor faster than its integration time wastes energy without
+/// producing new information. With a minimum read interval configured, a read
+/// that comes sooner than the interval after the last measurement returns the
+/// last measured value instead of triggering a new measurement. The tradeoff is
+/// staleness: a reused value may be up to the interval old, so a sudden change
+/// in light is only noticed once the interval has passed. The interval is off
+/// (0) by default, in which case every read triggers a measurement.
+///
+/// # Example
+/// ```ignore
+/// let reader = RateLimitedReader::new();
+/// reader.set_min_read_interval(Milliseconds(100));
+/// loop {
+///     let intensity = reader.read_intensity_sync()?;
+///     // make use of the intensity value
+/// }
+/// ```
+pub struct RateLimitedReader<S: Syscalls, const DRIVER_NUM: u32 = DEFAULT_DRIVER_NUM> {
+    min_read_interval: Cell<Milliseconds>,
+    // The last measured intensity, and the time it was measured.
+    last: Cell<Option<(u32, Ticks)>>,
+    _syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls, const DRIVER_NUM: u32> RateLimitedReader<S, DRIVER_NUM> {
+    pub fn new() -> Self {
+        RateLimitedReader {
+            min_read_interval: Cell::new(Milliseconds(0)),
+            last: Cell::new(None),
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Sets the minimum time between measurements. Uses the alarm driver to
+    /// timestamp measurements. `Milliseconds(0)` turns rate limiting off.
+    pub fn set_min_read_interval(&self, min_read_interval: Milliseconds) {
+        self.min_read_interval.set(min_read_interval);
+    }
+
+    /// Returns a light intensity reading, in lux. Triggers a new measurement
+    /// unless the last one happened less than the minimum read interval ago.
+    pub fn read_intensity_sync(&self) -> Result<u32, ErrorCode> {
+        let min_read_interval = self.min_read_interval.get();
+        if min_read_interval.0 == 0 {
+            return AmbientLight::<S, DRIVER_NUM>::read_intensity_sync();
+        }
+        let now = Alarm::<S>::get_ticks()?;
+        if let Some((intensity, measured_at)) = self.last.get() {
+            let min_ticks = min_read_interval.to_ticks(Alarm::<S>::get_frequency()?);
+            if now.elapsed_since(measured_at).0 < min_ticks.0 {
+                return Ok(intensity);
+            }
+        }
+        let intensity = AmbientLight::<S, DRIVER_NUM>::read_intensity_sync()?;
+        self.last.set(Some((intensity, now)));
+        Ok(intensity)
+    }
+}
+
+impl<S: Syscalls, const DRIVER_NUM: u32> Default for RateLimitedReader<S, DRIVER_NUM> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
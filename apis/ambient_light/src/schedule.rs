@@ -0,0 +1,173 @@
+use crate::{AmbientLight, Lux, DEFAULT_DRIVER_NUM};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use libtock_alarm::{Alarm, Milliseconds};
+use libtock_platform::{
+    share, subscribe::OneId, DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall,
+};
+
+/// What `ScheduledReads` does when the next read is due while the previous
+/// one is still pending.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PendingPolicy {
+    /// Drop the read that is due. Keeps at most one read per interval.
+    Skip,
+    /// Start one more read as soon as the pending one completes. Ticks that
+    /// arrive while a read is already queued are dropped.
+    Queue,
+}
+
+/// Reads the sensor every interval and delivers each reading to a closure,
+/// from the app's event loop. Created by `AmbientLight::schedule_reads`.
+///
+/// `start` subscribes to the alarm and the ambient light driver and sets the
+/// first alarm. The reads then run from upcalls while the app yields, until
+/// `cancel` is called, the `ScheduledReads` is dropped or the `share::scope`
+/// ends. By default, a read that is due while the previous one is still
+/// pending is skipped; see `on_pending`.
+///
+/// # Example
+/// ```ignore
+/// let reads = AmbientLight::schedule_reads(Milliseconds(5000), |lux| {
+///     // log the reading
+/// });
+/// type AlarmSubscribe<'a> = Subscribe<'a, TockSyscalls, { alarm::DRIVER_NUM }, 0>;
+/// type LightSubscribe<'a> = Subscribe<'a, TockSyscalls, 0x60002, 0>;
+/// share::scope::<(AlarmSubscribe, LightSubscribe), _, _>(|handle| {
+///     let (alarm, light) = handle.split();
+///     reads.start(alarm, light)?;
+///     loop {
+///         TockSyscalls::yield_wait();
+///     }
+/// })
+/// ```
+pub struct ScheduledReads<S: Syscalls, F: Fn(Lux), const DRIVER_NUM: u32 = DEFAULT_DRIVER_NUM> {
+    interval: Milliseconds,
+    policy: PendingPolicy,
+    running: Cell<bool>,
+    reading: ReadingHandler<S, F, DRIVER_NUM>,
+}
+
+// Receives the readings started by a `ScheduledReads`. It is a separate type
+// because one type cannot implement `Upcall` for both the alarm and the ambient
+// light driver while the latter's driver number is generic.
+struct ReadingHandler<S: Syscalls, F: Fn(Lux), const DRIVER_NUM: u32> {
+    callback: F,
+    pending: Cell<bool>,
+    queued: Cell<bool>,
+    _syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls, F: Fn(Lux), const DRIVER_NUM: u32> ScheduledReads<S, F, DRIVER_NUM> {
+    pub(crate) fn new(interval: Milliseconds, callback: F) -> Self {
+        ScheduledReads {
+            interval,
+            policy: PendingPolicy::Skip,
+            running: Cell::new(false),
+            reading: ReadingHandler {
+                callback,
+                pending: Cell::new(false),
+                queued: Cell::new(false),
+                _syscalls: PhantomData,
+            },
+        }
+    }
+
+    /// Sets what happens when a read is due while the previous one is still
+    /// pending.
+    pub fn on_pending(mut self, policy: PendingPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns `true` between `start` and `cancel`.
+    pub fn is_running(&self) -> bool {
+        self.running.get()
+    }
+
+    /// Subscribes to both drivers and sets the alarm for the first read, one
+    /// interval from now. Returns `Already` if the reads are already running.
+    pub fn start<'share>(
+        &'share self,
+        alarm: share::Handle<Subscribe<'share, S, { libtock_alarm::DRIVER_NUM }, 0>>,
+        light: share::Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+    ) -> Result<(), ErrorCode> {
+        if self.running.get() {
+            return Err(ErrorCode::Already);
+        }
+        S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(light, &self.reading)?;
+        S::subscribe::<_, _, DefaultConfig, { libtock_alarm::DRIVER_NUM }, 0>(alarm, self)?;
+        self.running.set(true);
+        if let Err(error) = Alarm::<S>::set_relative(self.interval) {
+            self.cancel();
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Stops the reads. A read that is already pending is not delivered.
+    pub fn cancel(&self) {
+        self.running.set(false);
+        self.reading.pending.set(false);
+        self.reading.queued.set(false);
+        // The fake alarm, and possibly other alarm drivers, cannot be stopped.
+        // Ignoring the error is fine, as a tick after cancel does nothing.
+        let _ = Alarm::<S>::stop();
+        S::unsubscribe(libtock_alarm::DRIVER_NUM, 0);
+        S::unsubscribe(DRIVER_NUM, 0);
+    }
+}
+
+impl<S: Syscalls, F: Fn(Lux), const DRIVER_NUM: u32> Drop for ScheduledReads<S, F, DRIVER_NUM> {
+    fn drop(&mut self) {
+        if self.running.get() {
+            self.cancel();
+        }
+    }
+}
+
+impl<S: Syscalls, F: Fn(Lux), const DRIVER_NUM: u32> Upcall<OneId<{ libtock_alarm::DRIVER_NUM }, 0>>
+    for ScheduledReads<S, F, DRIVER_NUM>
+{
+    fn upcall(&self, _now: u32, _alarm_ref: u32, _arg2: u32) {
+        // Upcalls already queued when the reads were cancelled may still
+        // arrive.
+        if !self.running.get() {
+            return;
+        }
+        if self.reading.pending.get() {
+            if self.policy == PendingPolicy::Queue {
+                self.reading.queued.set(true);
+            }
+        } else {
+            self.reading.start();
+        }
+        // There is no one to report an error to. If the alarm cannot be set,
+        // the reads stop.
+        if Alarm::<S>::set_relative(self.interval).is_err() {
+            self.running.set(false);
+        }
+    }
+}
+
+impl<S: Syscalls, F: Fn(Lux), const DRIVER_NUM: u32> ReadingHandler<S, F, DRIVER_NUM> {
+    fn start(&self) {
+        let started = AmbientLight::<S, DRIVER_NUM>::read_intensity().is_ok();
+        self.pending.set(started);
+    }
+}
+
+impl<S: Syscalls, F: Fn(Lux), const DRIVER_NUM: u32> Upcall<OneId<DRIVER_NUM, 0>>
+    for ReadingHandler<S, F, DRIVER_NUM>
+{
+    fn upcall(&self, intensity: u32, _flags: u32, _arg2: u32) {
+        if !self.pending.get() {
+            return;
+        }
+        (self.callback)(Lux(intensity));
+        match self.queued.replace(false) {
+            true => self.start(),
+            false => self.pending.set(false),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use core::cell::Cell;
+use core::marker::PhantomData;
+use libtock_alarm::{Alarm, Convert, Milliseconds, Ticks};
+use libtock_platform::{ErrorCode, Syscalls};
+
+/// Filters light intensity readings, passing on only those that differ
+/// significantly from the last reading passed on.
+///
+/// A reading is significant if it differs by at least `min_delta` lux from the
+/// last significant reading. If a minimum interval is configured, it must also
+/// arrive at least that long after the last significant reading; a large
+/// change within the interval is dropped rather than delayed, and is only
+/// reported if it is still present in a reading after the interval has passed.
+/// The first reading is always significant.
+///
+/// # Example
+/// ```ignore
+/// let filter = SignificantChange::new(50).min_interval(Milliseconds(1000));
+/// loop {
+///     let intensity = AmbientLight::read_intensity_sync()?;
+///     filter.update(intensity, |intensity| {
+///         // react to the change
+///     })?;
+/// }
+/// ```
+pub struct SignificantChange<S: Syscalls> {
+    min_delta: u32,
+    min_interval: Milliseconds,
+    // The last significant reading, and the time it was received.
+    last: Cell<Option<(u32, Ticks)>>,
+    _syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls> SignificantChange<S> {
+    pub fn new(min_delta: u32) -> Self {
+        SignificantChange {
+            min_delta,
+            min_interval: Milliseconds(0),
+            last: Cell::new(None),
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Sets the minimum time between significant readings. Uses the alarm
+    /// driver to timestamp readings.
+    pub fn min_interval(mut self, min_interval: Milliseconds) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Calls `callback` with `intensity` if it is significant. Returns whether
+    /// the callback was called.
+    pub fn update<F: FnOnce(u32)>(&self, intensity: u32, callback: F) -> Result<bool, ErrorCode> {
+        let now = match self.min_interval {
+            Milliseconds(0) => Ticks(0),
+            _ => Alarm::<S>::get_ticks()?,
+        };
+        if let Some((last_intensity, last_time)) = self.last.get() {
+            if crate::lux_abs_diff(intensity, last_intensity) < self.min_delta {
+                return Ok(false);
+            }
+            if self.min_interval.0 != 0 {
+                let min_ticks = self.min_interval.to_ticks(Alarm::<S>::get_frequency()?);
+                if now.elapsed_since(last_time).0 < min_ticks.0 {
+                    return Ok(false);
+                }
+            }
+        }
+        self.last.set(Some((intensity, now)));
+        callback(intensity);
+        Ok(true)
+    }
+}
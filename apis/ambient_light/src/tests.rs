@@ -1,8 +1,11 @@
 use core::cell::Cell;
-use libtock_platform::{share, ErrorCode, Syscalls, YieldNoWaitReturn};
-use libtock_unittest::fake;
+use libtock_platform::{share, ErrorCode, Percent, Syscalls, YieldNoWaitReturn};
+use libtock_unittest::{executor, fake, TestLock};
 
-use crate::IntensityListener;
+use crate::{
+    compute_lux, lux_abs_diff, AsyncReading, IntensityListener, LightChannels, LightController,
+    Lux, Range, RateLimitedReader, ReadingFlags, SignificantChange, Subscription, WatchMode,
+};
 
 type AmbientLight = super::AmbientLight<fake::Syscalls>;
 
@@ -21,6 +24,23 @@ fn driver_check() {
     assert_eq!(AmbientLight::exists(), Ok(()));
 }
 
+#[test]
+fn reprobe() {
+    let kernel = fake::Kernel::new();
+    assert!(!AmbientLight::reprobe());
+    assert_eq!(
+        AmbientLight::read_intensity_sync(),
+        Err(ErrorCode::NoDevice)
+    );
+
+    // The driver is loaded after the application started.
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+    assert!(AmbientLight::reprobe());
+    driver.set_value_sync(100);
+    assert_eq!(AmbientLight::read_intensity_sync(), Ok(100));
+}
+
 #[test]
 fn read_temperature() {
     let kernel = fake::Kernel::new();
@@ -74,3 +94,1526 @@ fn read_temperature_sync() {
     driver.set_value_sync(1000);
     assert_eq!(AmbientLight::read_intensity_sync(), Ok(1000));
 }
+
+// A blocking read sleeps in a single yield-wait until the reading arrives,
+// rather than spinning on yield-no-wait.
+#[test]
+fn read_intensity_sync_sleeps() {
+    use libtock_unittest::SyscallLogEntry;
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    driver.set_value_sync(1000);
+    assert_eq!(AmbientLight::read_intensity_sync(), Ok(1000));
+    let log = kernel.take_syscall_log();
+    let count = |expected: SyscallLogEntry| log.iter().filter(|&entry| *entry == expected).count();
+    assert_eq!(count(SyscallLogEntry::YieldWait), 1);
+    assert_eq!(count(SyscallLogEntry::YieldNoWait), 0);
+}
+
+#[test]
+fn read_with_integration() {
+    extern crate std;
+    use libtock_alarm::Milliseconds;
+    use libtock_unittest::SyscallLogEntry;
+    use std::vec::Vec;
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+    let commands = |log: Vec<SyscallLogEntry>| -> Vec<(u32, u32)> {
+        log.into_iter()
+            .filter_map(|entry| match entry {
+                SyscallLogEntry::Command {
+                    command_id,
+                    argument0,
+                    ..
+                } => Some((command_id, argument0)),
+                _ => None,
+            })
+            .collect()
+    };
+
+    assert_eq!(AmbientLight::integration_time(), Ok(Milliseconds(100)));
+    kernel.take_syscall_log();
+    driver.set_value_sync(1200);
+    assert_eq!(
+        AmbientLight::read_with_integration(Milliseconds(400)),
+        Ok(Lux(1200))
+    );
+    assert_eq!(
+        commands(kernel.take_syscall_log()),
+        [
+            (crate::INTEGRATION_TIME, 0),
+            (crate::SET_INTEGRATION_TIME, 400),
+            (crate::READ_INTENSITY, 0),
+            (crate::SET_INTEGRATION_TIME, 100),
+        ]
+    );
+    assert_eq!(driver.get_integration_time(), 100);
+
+    // The integration time is restored when the reading fails.
+    assert_eq!(AmbientLight::power_down(), Ok(()));
+    kernel.take_syscall_log();
+    assert_eq!(
+        AmbientLight::read_with_integration(Milliseconds(50)),
+        Err(ErrorCode::Off)
+    );
+    assert_eq!(
+        commands(kernel.take_syscall_log()),
+        [
+            (crate::INTEGRATION_TIME, 0),
+            (crate::SET_INTEGRATION_TIME, 50),
+            (crate::READ_INTENSITY, 0),
+            (crate::SET_INTEGRATION_TIME, 100),
+        ]
+    );
+    assert_eq!(driver.get_integration_time(), 100);
+
+    // Nothing needs restoring if the integration time could not be set.
+    assert_eq!(
+        AmbientLight::read_with_integration(Milliseconds(0)),
+        Err(ErrorCode::Invalid)
+    );
+    assert_eq!(
+        commands(kernel.take_syscall_log()),
+        [
+            (crate::INTEGRATION_TIME, 0),
+            (crate::SET_INTEGRATION_TIME, 0)
+        ]
+    );
+}
+
+#[test]
+fn autorange_saturation() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(
+        AmbientLight::enable_autorange(100, 100).err(),
+        Some(ErrorCode::Invalid)
+    );
+    let mut autorange = AmbientLight::enable_autorange(100, 60000).ok().unwrap();
+    assert_eq!(autorange.current_range(), Range::Normal);
+    assert_eq!(driver.get_range(), Range::Normal as u32);
+
+    // A single saturated reading does not change the range.
+    driver.set_value_sync(65000);
+    assert_eq!(autorange.read_intensity_sync(), Ok(65000));
+    assert_eq!(autorange.current_range(), Range::Normal);
+
+    // A reading within the window resets the count.
+    driver.set_value_sync(1000);
+    assert_eq!(autorange.read_intensity_sync(), Ok(1000));
+    driver.set_value_sync(65000);
+    assert_eq!(autorange.read_intensity_sync(), Ok(65000));
+    assert_eq!(autorange.current_range(), Range::Normal);
+
+    driver.set_value_sync(65000);
+    assert_eq!(autorange.read_intensity_sync(), Ok(65000));
+    assert_eq!(autorange.current_range(), Range::Bright);
+    assert_eq!(driver.get_range(), Range::Bright as u32);
+
+    // There is no less sensitive range than Bright.
+    for _ in 0..3 {
+        driver.set_value_sync(65000);
+        assert_eq!(autorange.read_intensity_sync(), Ok(65000));
+    }
+    assert_eq!(autorange.current_range(), Range::Bright);
+}
+
+#[test]
+fn autorange_underexposure() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let mut autorange = AmbientLight::enable_autorange(100, 60000).ok().unwrap();
+    for _ in 0..2 {
+        driver.set_value_sync(5);
+        assert_eq!(autorange.read_intensity_sync(), Ok(5));
+    }
+    assert_eq!(autorange.current_range(), Range::Dim);
+    assert_eq!(driver.get_range(), Range::Dim as u32);
+
+    // Alternating requests never reach the required sample count.
+    driver.set_value_sync(5);
+    assert_eq!(autorange.read_intensity_sync(), Ok(5));
+    driver.set_value_sync(65000);
+    assert_eq!(autorange.read_intensity_sync(), Ok(65000));
+    driver.set_value_sync(5);
+    assert_eq!(autorange.read_intensity_sync(), Ok(5));
+    assert_eq!(autorange.current_range(), Range::Dim);
+}
+
+#[test]
+fn max_lux() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(AmbientLight::max_lux(), Err(ErrorCode::NoSupport));
+    driver.set_max_lux(40000);
+    assert_eq!(AmbientLight::max_lux(), Ok(Lux(40000)));
+
+    // Just below the ceiling the reading is trusted.
+    driver.set_value_sync(39999);
+    assert_eq!(
+        AmbientLight::read_intensity_with_flags_sync(),
+        Ok((Lux(39999), ReadingFlags::default()))
+    );
+    let saturated = ReadingFlags {
+        saturated: true,
+        stale: false,
+    };
+    driver.set_value_sync(40000);
+    assert_eq!(
+        AmbientLight::read_intensity_with_flags_sync(),
+        Ok((Lux(40000), saturated))
+    );
+    driver.set_value_sync(65535);
+    assert_eq!(
+        AmbientLight::read_intensity_with_flags_sync(),
+        Ok((Lux(40000), saturated))
+    );
+
+    assert_eq!(Lux(100).clamp_to(Lux(50)), Lux(50));
+    assert_eq!(Lux(50).clamp_to(Lux(50)), Lux(50));
+    assert_eq!(Lux(49).clamp_to(Lux(50)), Lux(49));
+}
+
+#[test]
+fn read_intensity_with_flags_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    driver.set_value_sync(300);
+    assert_eq!(
+        AmbientLight::read_intensity_with_flags_sync(),
+        Ok((Lux(300), ReadingFlags::default()))
+    );
+
+    driver.set_saturated(true);
+    driver.set_value_sync(65535);
+    assert_eq!(
+        AmbientLight::read_intensity_with_flags_sync(),
+        Ok((
+            Lux(65535),
+            ReadingFlags {
+                saturated: true,
+                stale: false
+            }
+        ))
+    );
+
+    driver.set_saturated(false);
+    driver.set_stale(true);
+    driver.set_value_sync(400);
+    assert_eq!(
+        AmbientLight::read_intensity_with_flags_sync(),
+        Ok((
+            Lux(400),
+            ReadingFlags {
+                saturated: false,
+                stale: true
+            }
+        ))
+    );
+}
+
+#[test]
+fn light_controller() {
+    use libtock_unittest::SyscallLogEntry;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    let leds = fake::Leds::<2>::new();
+    kernel.add_driver(&driver);
+    kernel.add_driver(&leds);
+
+    let controller = LightController::<fake::Syscalls>::new(1, 50);
+    assert_eq!(controller.threshold(), 50);
+
+    // Feed a ramp going down then up, and record the LED state.
+    let ramp = [100, 60, 40, 10, 30, 70, 90];
+    let expected_led_states = [false, false, true, true, true, false, false];
+    for (intensity, led_state) in ramp.into_iter().zip(expected_led_states) {
+        driver.set_value_sync(intensity);
+        assert_eq!(controller.step(), Ok(intensity));
+        assert_eq!(leds.get_led(1), Some(led_state));
+    }
+
+    // The LED is only commanded when its state changes: off (first reading),
+    // on, then off.
+    let led_commands = kernel
+        .take_syscall_log()
+        .into_iter()
+        .filter_map(|entry| match entry {
+            SyscallLogEntry::Command {
+                driver_id: 2,
+                command_id,
+                ..
+            } => Some(command_id),
+            _ => None,
+        });
+    assert!(led_commands.eq([2, 1, 2]));
+
+    controller.set_threshold(200);
+    assert_eq!(controller.threshold(), 200);
+    driver.set_value_sync(90);
+    assert_eq!(controller.step(), Ok(90));
+    assert_eq!(leds.get_led(1), Some(true));
+
+    // Errors stop the event loop.
+    let controller = LightController::<fake::Syscalls>::new(5, 50);
+    driver.set_value_sync(10);
+    assert_eq!(controller.run(), ErrorCode::Invalid);
+}
+
+#[test]
+fn custom_driver_number() {
+    use libtock_unittest::SyscallLogEntry;
+    type CustomAmbientLight = super::AmbientLight<fake::Syscalls, 0x90002>;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new_with_driver_num(0x90002);
+    kernel.add_driver(&driver);
+
+    assert_eq!(AmbientLight::exists(), Err(ErrorCode::NoDevice));
+    kernel.take_syscall_log();
+    assert_eq!(CustomAmbientLight::exists(), Ok(()));
+
+    driver.set_value_sync(250);
+    assert_eq!(CustomAmbientLight::read_intensity_sync(), Ok(250));
+
+    let listener = IntensityListener(|_| {});
+    share::scope(|subscribe| {
+        assert_eq!(
+            CustomAmbientLight::register_listener(&listener, subscribe),
+            Ok(())
+        );
+    });
+
+    // Every driver call made through CustomAmbientLight goes to 0x90002.
+    let mut commands = 0;
+    let mut subscribes = 0;
+    for entry in kernel.take_syscall_log() {
+        match entry {
+            SyscallLogEntry::Command { driver_id, .. } => {
+                assert_eq!(driver_id, 0x90002);
+                commands += 1;
+            }
+            SyscallLogEntry::Subscribe { driver_num, .. } => {
+                assert_eq!(driver_num, 0x90002);
+                subscribes += 1;
+            }
+            _ => {}
+        }
+    }
+    assert!(commands > 0);
+    assert!(subscribes > 0);
+}
+
+#[test]
+fn measure_many() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(AmbientLight::measure_many(0), Err(ErrorCode::Invalid));
+
+    driver.set_value_sync(100);
+    driver.set_value_sync(200);
+    driver.set_value_sync(600);
+    assert_eq!(AmbientLight::measure_many(3), Ok(Lux(300)));
+
+    // The sum of the readings does not overflow.
+    for _ in 0..4 {
+        driver.set_value_sync(u32::MAX);
+    }
+    assert_eq!(AmbientLight::measure_many(4), Ok(Lux(u32::MAX)));
+}
+
+#[test]
+fn collect_samples() {
+    use crate::{DEFAULT_DRIVER_NUM as DRIVER_NUM, READ_INTENSITY};
+    use libtock_unittest::command_return;
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    for value in [100, 50, 400, 7] {
+        driver.set_value_sync(value);
+    }
+    assert_eq!(
+        AmbientLight::collect_samples::<4>(),
+        Ok([Lux(100), Lux(50), Lux(400), Lux(7)])
+    );
+    assert_eq!(AmbientLight::collect_samples::<0>(), Ok([]));
+
+    // The second measurement fails.
+    driver.set_value_sync(100);
+    kernel
+        .expect()
+        .subscribe(DRIVER_NUM, 0)
+        .command(DRIVER_NUM, READ_INTENSITY)
+        .yield_wait()
+        .subscribe(DRIVER_NUM, 0)
+        .subscribe(DRIVER_NUM, 0)
+        .command(DRIVER_NUM, READ_INTENSITY)
+        .returns(command_return::failure(ErrorCode::Off));
+    assert_eq!(AmbientLight::collect_samples::<3>(), Err(ErrorCode::Off));
+}
+
+#[test]
+fn read_until() {
+    use core::ops::ControlFlow;
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    for value in [300, 200, 5, 400] {
+        driver.set_value_sync(value);
+    }
+    let mut count = 0;
+    let result = AmbientLight::read_until(|lux| {
+        count += 1;
+        match lux < Lux(10) {
+            true => ControlFlow::Break((count, lux)),
+            false => ControlFlow::Continue(()),
+        }
+    });
+    assert_eq!(result, Ok((3, Lux(5))));
+    // No reading was taken after the break.
+    assert_eq!(AmbientLight::read_intensity_sync(), Ok(400));
+
+    assert_eq!(AmbientLight::power_down(), Ok(()));
+    assert_eq!(
+        AmbientLight::read_until(|_| ControlFlow::Break(())),
+        Err(ErrorCode::Off)
+    );
+}
+
+#[test]
+fn read_async() {
+    use core::task::Poll;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+    let reading = AsyncReading::new();
+
+    driver.set_value_sync(100);
+    share::scope(|subscribe| {
+        let future = AmbientLight::read_async(&reading, subscribe).ok().unwrap();
+        assert_eq!(executor::block_on(future), Lux(100));
+    });
+
+    share::scope(|subscribe| {
+        let mut future = AmbientLight::read_async(&reading, subscribe).ok().unwrap();
+        executor::take_woken();
+        assert_eq!(executor::poll(&mut future), Poll::Pending);
+        driver.set_value(200);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert!(executor::take_woken());
+        assert_eq!(executor::poll(&mut future), Poll::Ready(Lux(200)));
+    });
+
+    // Dropping the future cancels the reading.
+    share::scope(|subscribe| {
+        let mut future = AmbientLight::read_async(&reading, subscribe).ok().unwrap();
+        assert_eq!(executor::poll(&mut future), Poll::Pending);
+        core::mem::drop(future);
+        driver.set_value(300);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+}
+
+#[test]
+fn read_async_leak() {
+    use core::task::Poll;
+    use libtock_unittest::SyscallLogEntry;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+    let reading = AsyncReading::new();
+
+    share::scope(|subscribe| {
+        let mut future = AmbientLight::read_async(&reading, subscribe).ok().unwrap();
+        assert_eq!(executor::poll(&mut future), Poll::Pending);
+        kernel.take_syscall_log();
+
+        // Leaking the future issues no null subscribe, so the reading is still
+        // delivered.
+        future.leak();
+        assert_eq!(kernel.take_syscall_log(), []);
+        assert!(kernel.has_subscription(crate::DEFAULT_DRIVER_NUM, 0));
+        driver.set_value(300);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        kernel.take_syscall_log();
+    });
+
+    // The scope still unsubscribes when it ends.
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Subscribe {
+            driver_num: crate::DEFAULT_DRIVER_NUM,
+            subscribe_num: 0,
+        }]
+    );
+    assert!(!kernel.has_subscription(crate::DEFAULT_DRIVER_NUM, 0));
+}
+
+#[test]
+fn power() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(AmbientLight::power_down(), Ok(()));
+    assert!(!driver.is_powered());
+    assert_eq!(AmbientLight::read_intensity(), Err(ErrorCode::Off));
+    assert_eq!(AmbientLight::read_intensity_sync(), Err(ErrorCode::Off));
+
+    assert_eq!(AmbientLight::power_up(), Ok(()));
+    assert!(driver.is_powered());
+    driver.set_value_sync(100);
+    assert_eq!(AmbientLight::read_intensity_sync(), Ok(100));
+
+    assert_eq!(AmbientLight::power_down(), Ok(()));
+    {
+        let _powered = AmbientLight::power_up_scoped().ok().unwrap();
+        assert!(driver.is_powered());
+        driver.set_value_sync(200);
+        assert_eq!(AmbientLight::read_intensity_sync(), Ok(200));
+    }
+    assert!(!driver.is_powered());
+    assert_eq!(AmbientLight::is_powered(), Ok(false));
+}
+
+#[test]
+fn power_up_scoped_already_powered() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    // A guard taken while the sensor is already powered up leaves it powered
+    // up when dropped.
+    assert_eq!(AmbientLight::is_powered(), Ok(true));
+    {
+        let _powered = AmbientLight::power_up_scoped().ok().unwrap();
+        assert!(driver.is_powered());
+    }
+    assert!(driver.is_powered());
+    driver.set_value_sync(100);
+    assert_eq!(AmbientLight::read_intensity_sync(), Ok(100));
+}
+
+#[test]
+fn continuous() {
+    use libtock_unittest::SyscallLogEntry;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let readings: Cell<u32> = Cell::new(0);
+    let listener = IntensityListener(|intensity| {
+        assert_eq!(intensity, 100 + readings.get());
+        readings.set(readings.get() + 1);
+    });
+    share::scope(|subscribe| {
+        assert_eq!(
+            AmbientLight::start_continuous(3, &listener, subscribe).err(),
+            Some(ErrorCode::Invalid)
+        );
+    });
+    assert!(!driver.is_continuous());
+
+    kernel.take_syscall_log();
+    share::scope(|subscribe| {
+        let continuous = AmbientLight::start_continuous(10, &listener, subscribe)
+            .ok()
+            .unwrap();
+        assert!(driver.is_continuous());
+        assert_eq!(driver.get_sample_rate(), 10);
+        assert!(kernel
+            .take_syscall_log()
+            .contains(&SyscallLogEntry::Command {
+                driver_id: crate::DEFAULT_DRIVER_NUM,
+                command_id: crate::SET_SAMPLE_RATE,
+                argument0: 10,
+                argument1: 0,
+            }));
+
+        for intensity in 100..103 {
+            driver.set_value(intensity);
+            assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        }
+        assert_eq!(readings.get(), 3);
+
+        // Dropping the guard stops continuous mode.
+        core::mem::drop(continuous);
+        assert!(!driver.is_continuous());
+        driver.set_value(200);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+}
+
+#[test]
+fn ensure_subscribed() {
+    use libtock_unittest::SyscallLogEntry;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let count_subscribes = || {
+        kernel
+            .take_syscall_log()
+            .into_iter()
+            .filter(|entry| matches!(entry, SyscallLogEntry::Subscribe { .. }))
+            .count()
+    };
+
+    let listener = IntensityListener(|_| {});
+    share::scope(|subscribe| {
+        let subscription = Subscription::new();
+        assert!(!subscription.is_active());
+        assert_eq!(
+            AmbientLight::ensure_subscribed(&subscription, &listener, subscribe),
+            Ok(())
+        );
+        assert_eq!(
+            AmbientLight::ensure_subscribed(&subscription, &listener, subscribe),
+            Ok(())
+        );
+        assert!(subscription.is_active());
+        assert_eq!(count_subscribes(), 1);
+
+        AmbientLight::ensure_unsubscribed(&subscription);
+        assert!(!subscription.is_active());
+        assert_eq!(count_subscribes(), 1);
+        AmbientLight::ensure_unsubscribed(&subscription);
+        assert_eq!(count_subscribes(), 0);
+
+        assert_eq!(
+            AmbientLight::ensure_subscribed(&subscription, &listener, subscribe),
+            Ok(())
+        );
+        assert_eq!(count_subscribes(), 1);
+    });
+}
+
+#[cfg(feature = "f32")]
+#[test]
+fn exposure_value() {
+    // (lux, ISO, EV) reference points of the incident-light meter equation.
+    let references = [
+        (2, 125, 0.0),
+        (40, 100, 4.0),
+        (160, 400, 8.0),
+        (10240, 100, 12.0),
+        (81920, 100, 15.0),
+        (100_000, 100, 15.287712),
+        (1, 100, -1.321928),
+    ];
+    for (lux, iso, ev) in references {
+        let actual = Lux(lux).to_exposure_value(iso);
+        assert!(
+            (actual - ev).abs() < 1e-4,
+            "{} lux at ISO {}: expected EV {}, got {}",
+            lux,
+            iso,
+            ev,
+            actual
+        );
+    }
+    assert_eq!(Lux(0).to_exposure_value(100), f32::NEG_INFINITY);
+    assert_eq!(Lux(100).to_exposure_value(0), f32::NEG_INFINITY);
+}
+
+#[test]
+fn read_raw() {
+    use libtock_platform::{IdentityCalibration, LinearCalibration, RawCount};
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    driver.set_value_sync(450);
+    let raw = AmbientLight::read_raw().unwrap();
+    assert_eq!(raw, RawCount::new(450));
+    // A raw count only becomes lux through an explicit conversion.
+    assert_eq!(Lux(u32::from(raw)), Lux(450));
+
+    let calibration = LinearCalibration {
+        scale: 2,
+        offset: -100,
+    };
+    driver.set_value_sync(450);
+    assert_eq!(
+        AmbientLight::read_intensity_calibrated_sync(&calibration),
+        Ok(800)
+    );
+    // Readings above i32::MAX are not mistaken for negative ones.
+    driver.set_value_sync(u32::MAX);
+    assert_eq!(
+        AmbientLight::read_intensity_calibrated_sync(&IdentityCalibration),
+        Ok(i32::MAX)
+    );
+    driver.set_value_sync(i32::MAX as u32 + 1);
+    assert_eq!(
+        AmbientLight::read_intensity_calibrated_sync(&calibration),
+        Ok(i32::MAX)
+    );
+}
+
+#[test]
+fn read_percent() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    driver.set_resolution_bits(12);
+    driver.set_value_sync(2048);
+    assert_eq!(AmbientLight::read_percent(), Ok(Percent::new(50)));
+    driver.set_value_sync(4095);
+    assert_eq!(AmbientLight::read_percent(), Ok(Percent::new(100)));
+    // Readings beyond the full scale are clamped.
+    driver.set_value_sync(10_000);
+    assert_eq!(AmbientLight::read_percent(), Ok(Percent::new(100)));
+
+    assert_eq!(Lux(0).percent(16).get(), 0);
+    assert_eq!(Lux(655).percent(16).get(), 0);
+    assert_eq!(Lux(656).percent(16).get(), 1);
+    assert_eq!(Lux(u32::MAX).percent(32).get(), 100);
+
+    assert_eq!(Lux(0).log_percent(16).get(), 0);
+    assert_eq!(Lux(255).log_percent(16).get(), 50);
+    assert_eq!(Lux(65535).log_percent(16).get(), 100);
+    assert_eq!(Lux(u32::MAX).log_percent(16).get(), 100);
+    assert_eq!(Lux(u32::MAX).log_percent(32).get(), 100);
+}
+
+#[test]
+fn resolution_bits() {
+    extern crate std;
+    use libtock_unittest::{command_return, ExpectedSyscall};
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    driver.set_resolution_bits(16);
+    assert_eq!(AmbientLight::resolution_bits(), Ok(16));
+    let bits = AmbientLight::resolution_bits().unwrap();
+    assert_eq!(Lux::from_counts(65535, bits, Lux(1000)), Lux(1000));
+    assert_eq!(Lux::from_counts(4095, bits, Lux(1000)), Lux(62));
+
+    // The same count is a larger fraction of the full scale at a lower
+    // resolution.
+    driver.set_resolution_bits(12);
+    let bits = AmbientLight::resolution_bits().unwrap();
+    assert_eq!(Lux::from_counts(4095, bits, Lux(1000)), Lux(1000));
+    assert_eq!(Lux::from_counts(65535, bits, Lux(1000)), Lux(1000));
+
+    // A resolution the driver cannot have fails a debug assertion, and is
+    // rejected in release builds.
+    driver.set_resolution_bits(33);
+    let result = std::panic::catch_unwind(AmbientLight::resolution_bits);
+    assert_eq!(result.is_err(), cfg!(debug_assertions));
+    if let Ok(result) = result {
+        assert_eq!(result, Err(ErrorCode::BadRVal));
+    }
+
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DEFAULT_DRIVER_NUM,
+        command_id: crate::RESOLUTION,
+        argument0: 0,
+        argument1: 0,
+        override_return: Some(command_return::failure(ErrorCode::NoSupport)),
+    });
+    assert_eq!(
+        AmbientLight::resolution_bits(),
+        Ok(crate::DEFAULT_RESOLUTION_BITS)
+    );
+}
+
+// Tests that malformed data from the kernel is handled without panicking.
+#[test]
+fn malformed_kernel_data() {
+    // Unknown flag bits are reserved for future flags, and ignored.
+    assert_eq!(
+        ReadingFlags::from(u32::MAX & !0b11),
+        ReadingFlags::default()
+    );
+    assert_eq!(Lux::from_counts(u32::MAX, 0, Lux(u32::MAX)), Lux(u32::MAX));
+    assert_eq!(Lux::from_counts(u32::MAX, 255, Lux(100)), Lux(100));
+}
+
+#[test]
+fn significant_change() {
+    use libtock_alarm::Milliseconds;
+
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&alarm);
+
+    let reported: Cell<Option<u32>> = Cell::new(None);
+    let report = |intensity| reported.set(Some(intensity));
+    let filter = SignificantChange::<fake::Syscalls>::new(50).min_interval(Milliseconds(500));
+
+    assert_eq!(filter.update(100, report), Ok(true));
+    assert_eq!(reported.take(), Some(100));
+
+    // A large change within the interval is suppressed.
+    alarm.advance(100);
+    assert_eq!(filter.update(400, report), Ok(false));
+    assert_eq!(reported.take(), None);
+
+    // After the interval, small changes are still suppressed.
+    alarm.advance(500);
+    assert_eq!(filter.update(120, report), Ok(false));
+    assert_eq!(filter.update(400, report), Ok(true));
+    assert_eq!(reported.take(), Some(400));
+
+    // Without an interval, only the delta matters and the alarm is not used.
+    kernel.take_syscall_log();
+    let filter = SignificantChange::<fake::Syscalls>::new(50);
+    assert_eq!(filter.update(100, report), Ok(true));
+    assert_eq!(filter.update(60, report), Ok(false));
+    assert_eq!(filter.update(40, report), Ok(true));
+    assert_eq!(reported.take(), Some(40));
+    // Dimming to zero is measured against the last reported intensity.
+    assert_eq!(filter.update(0, report), Ok(false));
+    assert_eq!(filter.update(90, report), Ok(true));
+    assert_eq!(reported.take(), Some(90));
+    assert_eq!(kernel.take_syscall_log(), []);
+}
+
+#[test]
+fn watch_interrupt() {
+    use libtock_unittest::SyscallLogEntry;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let crossings = Cell::new([0; 2]);
+    let count = Cell::new(0);
+    let watch = AmbientLight::watch(500, |intensity| {
+        let mut values = crossings.get();
+        values[count.get()] = intensity;
+        crossings.set(values);
+        count.set(count.get() + 1);
+    });
+    assert_eq!(watch.active_mode(), WatchMode::Interrupt);
+    kernel.assert_no_syscalls();
+
+    // The first reading finds the intensity below the threshold, so the
+    // interrupt is armed for a rising crossing.
+    driver.set_value_sync(100);
+    driver.set_value_sync(200);
+    driver.set_value_sync(600);
+    assert_eq!(watch.wait(), Ok(()));
+    assert_eq!((count.get(), crossings.get()[0]), (1, 600));
+    assert!(kernel
+        .take_syscall_log()
+        .contains(&SyscallLogEntry::Command {
+            driver_id: crate::DEFAULT_DRIVER_NUM,
+            command_id: crate::WATCH_THRESHOLD,
+            argument0: 500,
+            argument1: 1,
+        }));
+
+    driver.set_value_sync(550);
+    driver.set_value_sync(499);
+    assert_eq!(watch.wait(), Ok(()));
+    assert_eq!((count.get(), crossings.get()[1]), (2, 499));
+    assert_eq!(watch.active_mode(), WatchMode::Interrupt);
+}
+
+#[test]
+fn watch_logical_state() {
+    use crate::LightLevel;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let watch = AmbientLight::watch(500, |_| {});
+    driver.set_value_sync(499);
+    assert_eq!(watch.current_logical_state(), Ok(LightLevel::Dark));
+    // The state is only read from the sensor once.
+    kernel.take_syscall_log();
+    assert_eq!(watch.current_logical_state(), Ok(LightLevel::Dark));
+    kernel.assert_no_syscalls();
+
+    // The seeded state is the one the first wait starts from.
+    driver.set_value_sync(500);
+    assert_eq!(watch.wait(), Ok(()));
+    assert_eq!(watch.current_logical_state(), Ok(LightLevel::Light));
+
+    let watch = AmbientLight::watch(500, |_| {});
+    driver.set_value_sync(500);
+    assert_eq!(watch.current_logical_state(), Ok(LightLevel::Light));
+}
+
+#[test]
+fn watch_polling() {
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&alarm);
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+    driver.set_threshold_supported(false);
+
+    let crossings = Cell::new([0; 2]);
+    let count = Cell::new(0);
+    let watch = AmbientLight::watch(500, |intensity| {
+        let mut values = crossings.get();
+        values[count.get()] = intensity;
+        crossings.set(values);
+        count.set(count.get() + 1);
+    })
+    .poll_interval(libtock_alarm::Milliseconds(100));
+    // Configuring the watch does not touch the kernel; only wait does.
+    kernel.assert_no_syscalls();
+
+    driver.set_value_sync(100);
+    driver.set_value_sync(200);
+    driver.set_value_sync(600);
+    assert_eq!(watch.wait(), Ok(()));
+    assert_eq!(watch.active_mode(), WatchMode::Polling);
+    assert_eq!((count.get(), crossings.get()[0]), (1, 600));
+    // The sensor was read twice after the first reading, 100 ms apart.
+    assert_eq!(
+        libtock_alarm::Alarm::<fake::Syscalls>::get_ticks(),
+        Ok(libtock_alarm::Ticks(200))
+    );
+
+    driver.set_value_sync(550);
+    driver.set_value_sync(499);
+    assert_eq!(watch.wait(), Ok(()));
+    assert_eq!((count.get(), crossings.get()[1]), (2, 499));
+}
+
+// The share list used by the `ScheduledReads` tests.
+type ScheduleSubscribes<'share> = (
+    libtock_platform::Subscribe<'share, fake::Syscalls, { libtock_alarm::DRIVER_NUM }, 0>,
+    libtock_platform::Subscribe<'share, fake::Syscalls, { crate::DEFAULT_DRIVER_NUM }, 0>,
+);
+
+#[test]
+fn schedule_reads() {
+    extern crate std;
+    use libtock_alarm::{Milliseconds, Ticks};
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&alarm);
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let readings = core::cell::RefCell::new(std::vec::Vec::new());
+    let reads =
+        AmbientLight::schedule_reads(Milliseconds(100), |lux| readings.borrow_mut().push(lux.0));
+    kernel.assert_no_syscalls();
+    for value in [10, 20, 30] {
+        driver.set_value_sync(value);
+    }
+    share::scope::<ScheduleSubscribes, _, _>(|handle| {
+        let (alarm_subscribe, light_subscribe) = handle.split();
+        assert_eq!(reads.start(alarm_subscribe, light_subscribe), Ok(()));
+        assert!(reads.is_running());
+        // Each interval delivers an alarm upcall, which starts a read, then
+        // the reading.
+        for _ in 0..6 {
+            assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        }
+        assert_eq!(*readings.borrow(), [10, 20, 30]);
+        // The fake alarm fires right away, advancing the clock one interval
+        // each time it is set.
+        assert_eq!(
+            libtock_alarm::Alarm::<fake::Syscalls>::get_ticks(),
+            Ok(Ticks(400))
+        );
+
+        reads.cancel();
+        assert!(!reads.is_running());
+        // The alarm upcall queued before the cancel is ignored.
+        driver.set_value_sync(40);
+        fake::Syscalls::yield_no_wait();
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+        assert_eq!(*readings.borrow(), [10, 20, 30]);
+    });
+}
+
+#[test]
+fn schedule_reads_pending() {
+    use crate::PendingPolicy;
+    use libtock_alarm::Milliseconds;
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&alarm);
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    for policy in [PendingPolicy::Skip, PendingPolicy::Queue] {
+        let count = Cell::new(0);
+        let reads = AmbientLight::schedule_reads(Milliseconds(100), |_| count.set(count.get() + 1))
+            .on_pending(policy);
+        share::scope::<ScheduleSubscribes, _, _>(|handle| {
+            let (alarm_subscribe, light_subscribe) = handle.split();
+            assert_eq!(reads.start(alarm_subscribe, light_subscribe), Ok(()));
+            // The first tick starts a read, which the sensor does not
+            // complete before the next two ticks.
+            for _ in 0..3 {
+                fake::Syscalls::yield_no_wait();
+            }
+            assert!(driver.is_busy());
+            driver.set_value(7);
+            // The next alarm upcall is ahead of the reading in the queue.
+            fake::Syscalls::yield_no_wait();
+            fake::Syscalls::yield_no_wait();
+            assert_eq!(count.get(), 1);
+            // A queued read starts as soon as the pending one completes.
+            assert_eq!(driver.is_busy(), policy == PendingPolicy::Queue);
+            reads.cancel();
+        });
+        // Let the driver finish the queued read before the next policy.
+        driver.set_value(0);
+        while fake::Syscalls::yield_no_wait() == YieldNoWaitReturn::Upcall {}
+        assert_eq!(count.get(), 1);
+    }
+}
+
+#[test]
+fn rate_limited_reads() {
+    use libtock_alarm::Milliseconds;
+    use libtock_unittest::SyscallLogEntry;
+
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&alarm);
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+    let measurements = || {
+        kernel
+            .take_syscall_log()
+            .into_iter()
+            .filter(|entry| {
+                matches!(
+                    entry,
+                    SyscallLogEntry::Command {
+                        driver_id: crate::DEFAULT_DRIVER_NUM,
+                        command_id: crate::READ_INTENSITY,
+                        ..
+                    }
+                )
+            })
+            .count()
+    };
+
+    // Rate limiting is off by default.
+    let reader = RateLimitedReader::<fake::Syscalls>::new();
+    driver.set_value_sync(100);
+    driver.set_value_sync(200);
+    assert_eq!(reader.read_intensity_sync(), Ok(100));
+    assert_eq!(reader.read_intensity_sync(), Ok(200));
+    assert_eq!(measurements(), 2);
+
+    // A rapid second read reuses the last value.
+    reader.set_min_read_interval(Milliseconds(100));
+    driver.set_value_sync(300);
+    assert_eq!(reader.read_intensity_sync(), Ok(300));
+    alarm.advance(50);
+    assert_eq!(reader.read_intensity_sync(), Ok(300));
+    assert_eq!(measurements(), 1);
+
+    // Once the interval has passed, a new measurement is made.
+    alarm.advance(50);
+    driver.set_value_sync(400);
+    assert_eq!(reader.read_intensity_sync(), Ok(400));
+    assert_eq!(measurements(), 1);
+}
+
+#[test]
+fn continuous_not_supported() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+    driver.set_continuous_supported(false);
+
+    let listener = IntensityListener(|_| {});
+    share::scope(|subscribe| {
+        assert_eq!(
+            AmbientLight::start_continuous(10, &listener, subscribe).err(),
+            Some(ErrorCode::NoSupport)
+        );
+    });
+
+    // The app can fall back to polling.
+    driver.set_value_sync(100);
+    assert_eq!(AmbientLight::read_intensity_sync(), Ok(100));
+}
+
+#[test]
+fn reset_config() {
+    use libtock_unittest::{command_return, ExpectedSyscall};
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let mut autorange = AmbientLight::enable_autorange(100, 60000).ok().unwrap();
+    for _ in 0..2 {
+        driver.set_value_sync(65000);
+        assert_eq!(autorange.read_intensity_sync(), Ok(65000));
+    }
+    assert_eq!(autorange.current_range(), Range::Bright);
+    let num = crate::DEFAULT_DRIVER_NUM;
+    assert!(fake::Syscalls::command(num, crate::SET_SAMPLE_RATE, 5, 0).is_success());
+    assert!(fake::Syscalls::command(num, crate::START_CONTINUOUS, 0, 0).is_success());
+    assert!(driver.is_continuous());
+    assert_eq!(AmbientLight::power_down(), Ok(()));
+
+    assert_eq!(autorange.reset_config(), Ok(()));
+    assert_eq!(autorange.current_range(), Range::Normal);
+    assert_eq!(driver.get_range(), Range::Normal as u32);
+    assert!(driver.is_powered());
+    assert!(!driver.is_continuous());
+    assert_eq!(driver.get_sample_rate(), 0);
+
+    // Drivers that cannot reset the sensor report NoSupport, and the local
+    // state is kept.
+    for _ in 0..2 {
+        driver.set_value_sync(65000);
+        assert_eq!(autorange.read_intensity_sync(), Ok(65000));
+    }
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DEFAULT_DRIVER_NUM,
+        command_id: crate::RESET,
+        argument0: 0,
+        argument1: 0,
+        override_return: Some(command_return::failure(ErrorCode::NoSupport)),
+    });
+    assert_eq!(autorange.reset_config(), Err(ErrorCode::NoSupport));
+    assert_eq!(autorange.current_range(), Range::Bright);
+}
+
+#[test]
+fn read_timestamped() {
+    use libtock_alarm::Ticks;
+
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&alarm);
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    alarm.advance(1234);
+    driver.set_value_sync(100);
+    assert_eq!(
+        AmbientLight::read_timestamped(),
+        Ok((Lux(100), Ticks(1234)))
+    );
+
+    assert_eq!(AmbientLight::power_down(), Ok(()));
+    assert_eq!(AmbientLight::read_timestamped(), Err(ErrorCode::Off));
+}
+
+#[test]
+fn light_rate() {
+    use crate::LightRate;
+    use libtock_alarm::{Alarm, Hz, Ticks};
+
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&alarm);
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    // A light is turned on: 100 lux to 600 lux in half a second.
+    alarm.advance(1000);
+    driver.set_value_sync(100);
+    let earlier = AmbientLight::read_timestamped().ok().unwrap();
+    alarm.advance(500);
+    driver.set_value_sync(600);
+    let later = AmbientLight::read_timestamped().ok().unwrap();
+    let frequency = Alarm::<fake::Syscalls>::get_frequency().ok().unwrap();
+    assert_eq!(
+        LightRate::between(earlier, later, frequency),
+        Some(LightRate(1000))
+    );
+    assert_eq!(
+        LightRate::between(later, (Lux(300), Ticks(4500)), frequency),
+        Some(LightRate(-100))
+    );
+
+    // The counter wrapped around between the readings.
+    assert_eq!(
+        LightRate::between(
+            (Lux(0), Ticks(u32::MAX - 99)),
+            (Lux(50), Ticks(100)),
+            Hz(1000)
+        ),
+        Some(LightRate(250))
+    );
+    assert_eq!(
+        LightRate::between((Lux(0), Ticks(0)), (Lux(u32::MAX), Ticks(1)), Hz(u32::MAX)),
+        Some(LightRate(i32::MAX))
+    );
+    assert_eq!(
+        LightRate::between((Lux(u32::MAX), Ticks(0)), (Lux(0), Ticks(1)), Hz(u32::MAX)),
+        Some(LightRate(-i32::MAX))
+    );
+
+    // No time passed, or the frequency is unknown.
+    assert_eq!(LightRate::between(earlier, earlier, frequency), None);
+    assert_eq!(LightRate::between(earlier, later, Hz(0)), None);
+}
+
+#[test]
+fn read_channels() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    // A single-channel sensor falls back to an intensity reading.
+    driver.set_value_sync(150);
+    assert_eq!(
+        AmbientLight::read_channels(),
+        Ok(LightChannels {
+            visible: 150,
+            ir: 0
+        })
+    );
+
+    driver.set_channels(1000, 100);
+    assert_eq!(
+        AmbientLight::read_channels(),
+        Ok(LightChannels {
+            visible: 1000,
+            ir: 100
+        })
+    );
+}
+
+#[test]
+fn channel_ratio_lux() {
+    let lux = |visible, ir| compute_lux(LightChannels { visible, ir });
+    // 0.0304 * 1000 - 0.0272 * 100 = 27.68
+    assert_eq!(lux(1000, 100), Lux(27));
+    // 0.0381 * 1000 - 0.0624 * 450 = 10.02
+    assert_eq!(lux(1000, 450), Lux(10));
+    // 0.0128 * 1000 - 0.0153 * 700 = 2.09
+    assert_eq!(lux(1000, 700), Lux(2));
+    // Mostly infrared light, or no light at all.
+    assert_eq!(lux(1000, 1500), Lux(0));
+    assert_eq!(lux(0, 0), Lux(0));
+    assert_eq!(lux(0, 10), Lux(0));
+    // Extreme counts do not overflow.
+    assert_eq!(lux(u32::MAX, 0), Lux(130_567_005));
+}
+
+#[test]
+fn raw_subscribe_and_command() {
+    use libtock_platform::Register;
+
+    unsafe extern "C" fn upcall(lux: u32, _: u32, _: u32, data: Register) {
+        let reading: *const Cell<Option<u32>> = data.into();
+        (*reading).set(Some(lux));
+    }
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let reading: Cell<Option<u32>> = Cell::new(None);
+    let userdata: Register = (&reading as *const Cell<Option<u32>>).into();
+    // Safety: reading outlives the registration, which is removed below, and
+    // is only accessed through a Cell.
+    let result = unsafe { AmbientLight::subscribe_raw(0, upcall, userdata) };
+    assert_eq!(result, Ok(()));
+    assert!(kernel.has_subscription(crate::DEFAULT_DRIVER_NUM, 0));
+
+    let result: Result<(), ErrorCode> = AmbientLight::command_raw(1, 0, 0).to_result();
+    assert_eq!(result, Ok(()));
+    driver.set_value(321);
+    assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+    assert_eq!(reading.get(), Some(321));
+
+    fake::Syscalls::unsubscribe(crate::DEFAULT_DRIVER_NUM, 0);
+    assert!(!kernel.has_subscription(crate::DEFAULT_DRIVER_NUM, 0));
+
+    // Subscribing to a slot the driver does not have is rejected.
+    let result = unsafe { AmbientLight::subscribe_raw(7, upcall, userdata) };
+    assert_eq!(result, Err(ErrorCode::Invalid));
+}
+
+#[test]
+fn subscribe_raw_upcall_abi() {
+    use libtock_platform::Register;
+
+    type Received = Cell<Option<(u32, u32, u32, usize)>>;
+
+    // Records every argument the kernel passes, including userdata itself.
+    unsafe extern "C" fn upcall(arg0: u32, arg1: u32, arg2: u32, data: Register) {
+        let received: *const Received = data.into();
+        (*received).set(Some((arg0, arg1, arg2, data.into())));
+    }
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let received: Received = Cell::new(None);
+    let userdata: Register = (&received as *const Received).into();
+    // Safety: received outlives the registration, which is removed below, and
+    // is only accessed through a Cell.
+    assert_eq!(
+        unsafe { AmbientLight::subscribe_raw(0, upcall, userdata) },
+        Ok(())
+    );
+
+    // The upcall receives the reading, its flags, an unused third argument,
+    // and userdata unchanged.
+    driver.set_saturated(true);
+    assert_eq!(AmbientLight::read_intensity(), Ok(()));
+    driver.set_value(654);
+    assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+    assert_eq!(
+        received.get(),
+        Some((
+            654,
+            crate::FLAG_SATURATED,
+            0,
+            &received as *const Received as usize
+        ))
+    );
+
+    fake::Syscalls::unsubscribe(crate::DEFAULT_DRIVER_NUM, 0);
+    received.set(None);
+    assert_eq!(AmbientLight::read_intensity(), Ok(()));
+    driver.set_value(100);
+    assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    assert_eq!(received.get(), None);
+}
+
+#[test]
+fn abs_diff() {
+    // Rising, falling, and unchanged intensities, including zero.
+    assert_eq!(lux_abs_diff(100, 250), 150);
+    assert_eq!(lux_abs_diff(250, 100), 150);
+    assert_eq!(lux_abs_diff(0, 7), 7);
+    assert_eq!(lux_abs_diff(7, 0), 7);
+    assert_eq!(lux_abs_diff(0, 0), 0);
+    assert_eq!(lux_abs_diff(0, u32::MAX), u32::MAX);
+    assert_eq!(lux_abs_diff(u32::MAX, 0), u32::MAX);
+}
+
+#[cfg(feature = "console")]
+#[test]
+fn log_reading() {
+    use libtock_console::Console;
+
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    // No reading is written if the reading fails.
+    let mut writer = Console::<fake::Syscalls>::writer();
+    assert_eq!(
+        AmbientLight::log_reading(&mut writer),
+        Err(ErrorCode::NoDevice)
+    );
+    #[cfg(feature = "console_log")]
+    assert_eq!(
+        console.take_bytes(),
+        b"ambient light: read_intensity_sync failed: NODEVICE\n"
+    );
+    #[cfg(not(feature = "console_log"))]
+    assert_eq!(console.take_bytes(), b"");
+
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+    driver.set_value_sync(450);
+    assert_eq!(AmbientLight::log_reading(&mut writer), Ok(Lux(450)));
+    assert_eq!(console.take_bytes(), b"lux=450\n");
+}
+
+#[test]
+fn display_with() {
+    extern crate std;
+    use crate::Millilux;
+    use std::format;
+
+    assert_eq!(format!("{}", Millilux(4500).display_with(2)), "4.50 lx");
+    assert_eq!(format!("{}", Millilux(4500).display_with(1)), "4.5 lx");
+    assert_eq!(format!("{}", Millilux(4500).display_with(0)), "5 lx");
+    assert_eq!(format!("{}", Millilux(4567).display_with(3)), "4.567 lx");
+    assert_eq!(format!("{}", Millilux(4567).display_with(9)), "4.567 lx");
+    assert_eq!(format!("{}", Millilux(4567).display_with(2)), "4.57 lx");
+    assert_eq!(format!("{}", Millilux(5).display_with(2)), "0.01 lx");
+    assert_eq!(
+        format!("{}", Millilux(u32::MAX).display_with(1)),
+        "4294967.3 lx"
+    );
+    assert_eq!(format!("{}", Lux(450).display_with(2)), "450.00 lx");
+    assert_eq!(format!("{}", Lux(450).display_with(0)), "450 lx");
+}
+
+// The exclusive-listener slots are shared by the whole process, so tests that
+// use them must not run concurrently.
+static EXCLUSIVE_LOCK: TestLock = TestLock::new();
+
+#[test]
+fn register_exclusive() {
+    let _lock = EXCLUSIVE_LOCK.lock();
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let calls = Cell::new(0);
+    let listener = IntensityListener(|_| calls.set(calls.get() + 1));
+    let result = AmbientLight::register_exclusive(&listener, || {
+        assert!(kernel.has_subscription(crate::DEFAULT_DRIVER_NUM, 0));
+        assert_eq!(
+            AmbientLight::register_exclusive(&listener, || unreachable!()),
+            Err::<(), _>(ErrorCode::Already)
+        );
+        // The rejected call leaves the first listener registered.
+        assert!(kernel.has_subscription(crate::DEFAULT_DRIVER_NUM, 0));
+        driver.set_value_sync(100);
+        assert_eq!(AmbientLight::read_intensity(), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        calls.get()
+    });
+    assert_eq!(result, Ok(1));
+    assert!(!kernel.has_subscription(crate::DEFAULT_DRIVER_NUM, 0));
+
+    // The listener is released once the closure returns.
+    assert_eq!(
+        AmbientLight::register_exclusive(&listener, || {
+            kernel.has_subscription(crate::DEFAULT_DRIVER_NUM, 0)
+        }),
+        Ok(true)
+    );
+
+    // A failed registration releases it too.
+    kernel
+        .expect()
+        .subscribe(crate::DEFAULT_DRIVER_NUM, 0)
+        .fails_with(ErrorCode::Reserve);
+    assert_eq!(
+        AmbientLight::register_exclusive(&listener, || unreachable!()),
+        Err::<(), _>(ErrorCode::Reserve)
+    );
+    assert_eq!(AmbientLight::register_exclusive(&listener, || ()), Ok(()));
+}
+
+#[test]
+fn register_exclusive_per_driver() {
+    let _lock = EXCLUSIVE_LOCK.lock();
+    let kernel = fake::Kernel::new();
+    let default = fake::AmbientLight::new();
+    let other = fake::AmbientLight::new_with_driver_num(0x90002);
+    let zero = fake::AmbientLight::new_with_driver_num(0);
+    kernel.add_driver(&default);
+    kernel.add_driver(&other);
+    kernel.add_driver(&zero);
+
+    let listener = IntensityListener(|_| {});
+    let result = AmbientLight::register_exclusive(&listener, || {
+        super::AmbientLight::<fake::Syscalls, 0x90002>::register_exclusive(&listener, || {
+            // Driver number 0 is tracked like any other.
+            super::AmbientLight::<fake::Syscalls, 0>::register_exclusive(&listener, || {
+                assert!(kernel.has_subscription(crate::DEFAULT_DRIVER_NUM, 0));
+                assert!(kernel.has_subscription(0x90002, 0));
+                assert!(kernel.has_subscription(0, 0));
+            })
+        })
+    });
+    assert_eq!(result, Ok(Ok(Ok(()))));
+}
+
+#[test]
+fn marker_traits() {
+    use crate::{Continuous, PoweredUp, ReadIntensity};
+    use libtock_unittest::{assert_impl, assert_not_impl};
+
+    assert_impl!(AmbientLight: Send, Sync);
+    assert_impl!(Lux: Send, Sync);
+    assert_not_impl!(Continuous<fake::Syscalls>: Send, Sync);
+    assert_not_impl!(PoweredUp<fake::Syscalls>: Send, Sync);
+    assert_not_impl!(ReadIntensity<'static, fake::Syscalls>: Send, Sync);
+}
+
+#[test]
+fn decimated_continuous() {
+    use crate::Decimation;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let delivered: Cell<[u32; 2]> = Cell::new([0; 2]);
+    let deliveries: Cell<usize> = Cell::new(0);
+    let record = |intensity| {
+        let mut values = delivered.get();
+        values[deliveries.get()] = intensity;
+        delivered.set(values);
+        deliveries.set(deliveries.get() + 1);
+    };
+
+    // Dropping: only every third reading gets through, unchanged.
+    let listener = IntensityListener(record).decimate(3, Decimation::Drop);
+    share::scope(|subscribe| {
+        let _continuous = AmbientLight::start_continuous(10, &listener, subscribe)
+            .ok()
+            .unwrap();
+        for intensity in [10, 20, 30, 40, 50, 60, 70] {
+            driver.set_value(intensity);
+            assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        }
+    });
+    assert_eq!(deliveries.take(), 2);
+    assert_eq!(delivered.take(), [30, 60]);
+
+    // Averaging: each delivery is the mean of the readings since the last.
+    let listener = IntensityListener(record).decimate(2, Decimation::Average);
+    share::scope(|subscribe| {
+        let _continuous = AmbientLight::start_continuous(10, &listener, subscribe)
+            .ok()
+            .unwrap();
+        for intensity in [10, 21, u32::MAX, u32::MAX, 5] {
+            driver.set_value(intensity);
+            assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        }
+    });
+    assert_eq!(deliveries.take(), 2);
+    assert_eq!(delivered.take(), [15, u32::MAX]);
+}
+
+#[cfg(feature = "console")]
+#[test]
+fn log_errors() {
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(AmbientLight::power_down(), Ok(()));
+    assert_eq!(AmbientLight::read_intensity_sync(), Err(ErrorCode::Off));
+    #[cfg(feature = "console_log")]
+    assert_eq!(
+        console.take_bytes(),
+        b"ambient light: read_intensity_sync failed: OFF\n"
+    );
+    #[cfg(not(feature = "console_log"))]
+    assert_eq!(console.take_bytes(), b"");
+}
@@ -0,0 +1,153 @@
+use crate::{AmbientLight, IntensityListener, DEFAULT_DRIVER_NUM, WATCH_THRESHOLD};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use libtock_alarm::{Alarm, Milliseconds};
+use libtock_platform::{share, DefaultConfig, ErrorCode, Syscalls};
+
+/// How a `ThresholdWatch` detects threshold crossings.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WatchMode {
+    /// The driver raises an interrupt when the intensity crosses the
+    /// threshold.
+    Interrupt,
+    /// The driver has no threshold interrupts, so the sensor is read
+    /// periodically instead.
+    Polling,
+}
+
+/// Which side of a `ThresholdWatch`'s threshold the light intensity is on.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LightLevel {
+    /// Below the threshold.
+    Dark,
+    /// At or above the threshold.
+    Light,
+}
+
+/// Calls a closure each time the light intensity crosses a threshold, in
+/// either direction. Created by `AmbientLight::watch`.
+///
+/// Uses the driver's threshold interrupts where available. If the driver
+/// returns `NoSupport`, the watch switches to polling the sensor every poll
+/// interval (1 second by default) and comparing the readings against the
+/// threshold. The closure receives the first reading past the threshold in
+/// both modes. An intensity at or above the threshold counts as above it.
+///
+/// # Example
+/// ```ignore
+/// let watch = AmbientLight::watch(500, |intensity| {
+///     // react to the crossing
+/// })
+/// .poll_interval(Milliseconds(200));
+/// loop {
+///     watch.wait()?;
+/// }
+/// ```
+pub struct ThresholdWatch<S: Syscalls, F: Fn(u32), const DRIVER_NUM: u32 = DEFAULT_DRIVER_NUM> {
+    threshold: u32,
+    callback: F,
+    poll_interval: Milliseconds,
+    mode: Cell<WatchMode>,
+    // Whether the last reading was at or above the threshold.
+    above: Cell<Option<bool>>,
+    _syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls, F: Fn(u32), const DRIVER_NUM: u32> ThresholdWatch<S, F, DRIVER_NUM> {
+    pub(crate) fn new(threshold: u32, callback: F) -> Self {
+        ThresholdWatch {
+            threshold,
+            callback,
+            poll_interval: Milliseconds(1000),
+            mode: Cell::new(WatchMode::Interrupt),
+            above: Cell::new(None),
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Sets how often the sensor is read in polling mode. Uses the alarm
+    /// driver.
+    pub fn poll_interval(mut self, poll_interval: Milliseconds) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// The mode used to detect crossings. A new watch reports `Interrupt`
+    /// until the first `wait` finds that the driver does not support it.
+    pub fn active_mode(&self) -> WatchMode {
+        self.mode.get()
+    }
+
+    /// Returns which side of the threshold the intensity was on at the last
+    /// reading or crossing, without waiting for a crossing. Before the first
+    /// call to `wait`, this takes a reading to find out; that reading also
+    /// seeds the state `wait` starts from.
+    pub fn current_logical_state(&self) -> Result<LightLevel, ErrorCode> {
+        let above = match self.above.get() {
+            Some(above) => above,
+            None => {
+                let above = self.read_is_above()?;
+                self.above.set(Some(above));
+                above
+            }
+        };
+        Ok(match above {
+            true => LightLevel::Light,
+            false => LightLevel::Dark,
+        })
+    }
+
+    /// Blocks until the intensity crosses the threshold, then calls the
+    /// closure with the reading. The first call reads the sensor once to find
+    /// which side of the threshold the intensity starts on.
+    pub fn wait(&self) -> Result<(), ErrorCode> {
+        let above = self.current_logical_state()? == LightLevel::Light;
+        let intensity = match self.mode.get() {
+            WatchMode::Interrupt => match self.wait_interrupt(!above) {
+                Err(ErrorCode::NoSupport) => {
+                    self.mode.set(WatchMode::Polling);
+                    self.wait_polling(above)?
+                }
+                result => result?,
+            },
+            WatchMode::Polling => self.wait_polling(above)?,
+        };
+        self.above.set(Some(!above));
+        (self.callback)(intensity);
+        Ok(())
+    }
+
+    fn read_is_above(&self) -> Result<bool, ErrorCode> {
+        Ok(AmbientLight::<S, DRIVER_NUM>::read_intensity_sync()? >= self.threshold)
+    }
+
+    fn wait_interrupt(&self, rising: bool) -> Result<u32, ErrorCode> {
+        let crossing: Cell<Option<u32>> = Cell::new(None);
+        let listener = IntensityListener(|intensity| crossing.set(Some(intensity)));
+        share::scope(|subscribe| {
+            S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, &listener)?;
+            AmbientLight::<S, DRIVER_NUM>::command_raw(
+                WATCH_THRESHOLD,
+                self.threshold,
+                rising as u32,
+            )
+            .to_result::<(), ErrorCode>()?;
+            loop {
+                S::yield_wait();
+                if let Some(intensity) = crossing.get() {
+                    return Ok(intensity);
+                }
+            }
+        })
+    }
+
+    fn wait_polling(&self, above: bool) -> Result<u32, ErrorCode> {
+        loop {
+            Alarm::<S>::sleep_for(self.poll_interval)?;
+            let intensity = AmbientLight::<S, DRIVER_NUM>::read_intensity_sync()?;
+            if (intensity >= self.threshold) != above {
+                return Ok(intensity);
+            }
+        }
+    }
+}
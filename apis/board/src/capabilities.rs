@@ -0,0 +1,55 @@
+#[cfg(feature = "ambient_light")]
+use libtock_ambient_light::AmbientLight;
+#[cfg(feature = "button")]
+use libtock_buttons::Buttons;
+use libtock_leds::Leds;
+use libtock_platform::Syscalls;
+#[cfg(feature = "proximity")]
+use libtock_proximity::Proximity;
+#[cfg(feature = "temperature")]
+use libtock_temperature::Temperature;
+
+/// The drivers present on the board, probed once so the application can
+/// branch on them instead of handling `NoDevice` from every call. Drivers
+/// whose cargo feature is disabled are reported as absent.
+///
+/// # Example
+/// ```ignore
+/// let caps = libtock::capabilities::probe();
+/// if caps.has_ambient_light {
+///     // use the ambient light sensor
+/// }
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Capabilities {
+    pub has_ambient_light: bool,
+    pub has_buttons: bool,
+    pub has_leds: bool,
+    pub has_proximity: bool,
+    pub has_temperature: bool,
+}
+
+impl Capabilities {
+    /// Probes each driver with a single existence command.
+    pub fn probe_with<S: Syscalls>() -> Capabilities {
+        Capabilities {
+            #[cfg(feature = "ambient_light")]
+            has_ambient_light: AmbientLight::<S>::exists().is_ok(),
+            #[cfg(not(feature = "ambient_light"))]
+            has_ambient_light: false,
+            #[cfg(feature = "button")]
+            has_buttons: Buttons::<S>::count().is_ok(),
+            #[cfg(not(feature = "button"))]
+            has_buttons: false,
+            has_leds: Leds::<S>::count().is_ok(),
+            #[cfg(feature = "proximity")]
+            has_proximity: Proximity::<S>::exists().is_ok(),
+            #[cfg(not(feature = "proximity"))]
+            has_proximity: false,
+            #[cfg(feature = "temperature")]
+            has_temperature: Temperature::<S>::exists().is_ok(),
+            #[cfg(not(feature = "temperature"))]
+            has_temperature: false,
+        }
+    }
+}
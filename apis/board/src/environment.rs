@@ -0,0 +1,43 @@
+use libtock_ambient_light::{AmbientLight, Lux};
+use libtock_platform::{ErrorCode, Syscalls};
+use libtock_temperature::Temperature;
+
+/// A snapshot of the environmental sensors present on the board. Sensors
+/// whose driver is not present are `None`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Environment {
+    pub lux: Option<Lux>,
+    /// The temperature, in hundredths of a degree Celsius.
+    pub centi_celsius: Option<i32>,
+    /// The relative humidity, in percent. There is no humidity driver yet, so
+    /// this is always `None`.
+    pub humidity_pct: Option<u8>,
+}
+
+/// Reads every environmental sensor that is present. Missing drivers are
+/// reported as `None`, while a failed reading from a present driver fails the
+/// whole call.
+///
+/// # Example
+/// ```ignore
+/// let environment = libtock::environment::read_environment()?;
+/// if let Some(lux) = environment.lux {
+///     // make use of the light intensity
+/// }
+/// ```
+pub fn read_environment_with<S: Syscalls>() -> Result<Environment, ErrorCode> {
+    Ok(Environment {
+        lux: present(AmbientLight::<S>::read_intensity_sync())?.map(Lux),
+        centi_celsius: present(Temperature::<S>::read_temperature_sync())?,
+        humidity_pct: None,
+    })
+}
+
+// Maps a missing driver to `None`.
+fn present<T>(result: Result<T, ErrorCode>) -> Result<Option<T>, ErrorCode> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ErrorCode::NoDevice) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
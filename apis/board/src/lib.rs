@@ -0,0 +1,12 @@
+#![no_std]
+
+mod capabilities;
+#[cfg(all(feature = "ambient_light", feature = "temperature"))]
+mod environment;
+
+pub use capabilities::Capabilities;
+#[cfg(all(feature = "ambient_light", feature = "temperature"))]
+pub use environment::{read_environment_with, Environment};
+
+#[cfg(test)]
+mod tests;
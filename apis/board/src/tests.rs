@@ -0,0 +1,102 @@
+use libtock_unittest::fake;
+
+use crate::Capabilities;
+
+#[test]
+fn no_drivers() {
+    let _kernel = fake::Kernel::new();
+    assert_eq!(
+        Capabilities::probe_with::<fake::Syscalls>(),
+        Capabilities::default()
+    );
+}
+
+#[cfg(feature = "ambient_light")]
+#[test]
+fn capabilities_subset() {
+    let kernel = fake::Kernel::new();
+    let ambient_light = fake::AmbientLight::new();
+    kernel.add_driver(&ambient_light);
+    let leds = fake::Leds::<4>::new();
+    kernel.add_driver(&leds);
+
+    assert_eq!(
+        Capabilities::probe_with::<fake::Syscalls>(),
+        Capabilities {
+            has_ambient_light: true,
+            has_leds: true,
+            ..Capabilities::default()
+        }
+    );
+}
+
+// Builds under every combination of driver features: a driver whose feature
+// is disabled must be reported as absent even though the kernel has it. Run
+// with e.g. `--no-default-features --features button,proximity,temperature`
+// to check the configuration without ambient light.
+#[test]
+fn disabled_drivers() {
+    let kernel = fake::Kernel::new();
+    let ambient_light = fake::AmbientLight::new();
+    kernel.add_driver(&ambient_light);
+    let buttons = fake::Buttons::<2>::new();
+    kernel.add_driver(&buttons);
+    let leds = fake::Leds::<4>::new();
+    kernel.add_driver(&leds);
+    let proximity = fake::Proximity::new();
+    kernel.add_driver(&proximity);
+    let temperature = fake::Temperature::new();
+    kernel.add_driver(&temperature);
+
+    assert_eq!(
+        Capabilities::probe_with::<fake::Syscalls>(),
+        Capabilities {
+            has_ambient_light: cfg!(feature = "ambient_light"),
+            has_buttons: cfg!(feature = "button"),
+            has_leds: true,
+            has_proximity: cfg!(feature = "proximity"),
+            has_temperature: cfg!(feature = "temperature"),
+        }
+    );
+}
+
+#[cfg(all(feature = "ambient_light", feature = "temperature"))]
+#[test]
+fn environment_light_and_temperature() {
+    use crate::{read_environment_with, Environment};
+    use libtock_ambient_light::Lux;
+
+    let kernel = fake::Kernel::new();
+    let ambient_light = fake::AmbientLight::new();
+    ambient_light.set_value_sync(450);
+    kernel.add_driver(&ambient_light);
+    let temperature = fake::Temperature::new();
+    temperature.set_value_sync(2150);
+    kernel.add_driver(&temperature);
+
+    assert_eq!(
+        read_environment_with::<fake::Syscalls>(),
+        Ok(Environment {
+            lux: Some(Lux(450)),
+            centi_celsius: Some(2150),
+            humidity_pct: None,
+        })
+    );
+}
+
+#[cfg(all(feature = "ambient_light", feature = "temperature"))]
+#[test]
+fn environment_fixture() {
+    use crate::{read_environment_with, Environment};
+    use libtock_ambient_light::Lux;
+
+    let _kernel = fake::Kernel::with_environment(450, -250);
+    assert_eq!(
+        read_environment_with::<fake::Syscalls>(),
+        Ok(Environment {
+            lux: Some(Lux(450)),
+            centi_celsius: Some(-250),
+            humidity_pct: None,
+        })
+    );
+}
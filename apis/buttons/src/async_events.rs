@@ -0,0 +1,139 @@
+use crate::{ButtonEventQueue, ButtonState, Buttons, OverflowPolicy, DRIVER_NUM};
+use core::cell::Cell;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use libtock_platform::{
+    share::Handle, subscribe::OneId, DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall,
+};
+
+/// The upcall used by `Buttons::events`. It buffers up to `N` events until the
+/// stream consumes them, and wakes the task waiting for the next event.
+pub struct AsyncButtonEvents<const N: usize> {
+    queue: ButtonEventQueue<N>,
+    waker: Cell<Option<Waker>>,
+}
+
+impl<const N: usize> AsyncButtonEvents<N> {
+    /// `policy` selects which events are discarded when more than `N` events
+    /// arrive before the stream is polled.
+    pub fn new(policy: OverflowPolicy) -> AsyncButtonEvents<N> {
+        AsyncButtonEvents {
+            queue: ButtonEventQueue::new(policy),
+            waker: Cell::new(None),
+        }
+    }
+
+    /// The number of events discarded because the buffer was full.
+    pub fn dropped(&self) -> u32 {
+        self.queue.dropped()
+    }
+}
+
+impl<const N: usize> Upcall<OneId<DRIVER_NUM, 0>> for AsyncButtonEvents<N> {
+    fn upcall(&self, button_index: u32, state: u32, _arg2: u32) {
+        self.queue.push(button_index, state.into());
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<S: Syscalls> Buttons<S> {
+    /// Returns a stream of the events of the buttons with interrupts enabled.
+    ///
+    /// Events are buffered in `events` until the stream is polled. The kernel
+    /// is not slowed down when the buffer fills up; instead, events are
+    /// discarded according to the buffer's overflow policy and counted by
+    /// `AsyncButtonEvents::dropped`. Dropping the stream unregisters the
+    /// upcall.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let events = AsyncButtonEvents::<4>::new(OverflowPolicy::DropOldest);
+    /// share::scope(|subscribe| {
+    ///     let mut stream = Buttons::events(&events, subscribe)?;
+    ///     while let Some((button, state)) = stream.next_event().await {
+    ///         // make use of the button's state
+    ///     }
+    /// });
+    /// ```
+    pub fn events<'share, const N: usize>(
+        events: &'share AsyncButtonEvents<N>,
+        subscribe: Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+    ) -> Result<ButtonEvents<'share, S, N>, ErrorCode> {
+        S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, events)?;
+        Ok(ButtonEvents {
+            events,
+            _syscalls: PhantomData,
+        })
+    }
+}
+
+/// A stream of button events, created by `Buttons::events`.
+///
+/// `core` has no `Stream` trait, so `poll_next` has the same signature as
+/// `futures::Stream::poll_next` to make adapting it straightforward.
+///
+/// The stream never ends: `poll_next` only returns `Ready(Some(_))` or
+/// `Pending`.
+///
+/// The upcall fills the borrowed `AsyncButtonEvents` through `Cell`s while
+/// the process yields, so the stream is neither `Send` nor `Sync`.
+pub struct ButtonEvents<'share, S: Syscalls, const N: usize> {
+    events: &'share AsyncButtonEvents<N>,
+    _syscalls: PhantomData<S>,
+}
+
+impl<'share, S: Syscalls, const N: usize> ButtonEvents<'share, S, N> {
+    /// Polls for the next button event, registering the task to be woken when
+    /// one arrives.
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<(u32, ButtonState)>> {
+        match self.events.queue.pop() {
+            Some(event) => Poll::Ready(Some(event)),
+            None => {
+                self.events.waker.set(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Returns a future that resolves to the next button event.
+    pub fn next_event(&mut self) -> NextEvent<'_, 'share, S, N> {
+        NextEvent { stream: self }
+    }
+
+    /// Consumes the stream without unregistering the upcall, so events keep
+    /// being buffered in the `AsyncButtonEvents`.
+    ///
+    /// The enclosing `share::scope` still unregisters the upcall when it ends,
+    /// so leaking the stream cannot leave the kernel with a dangling pointer.
+    pub fn leak(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl<'share, S: Syscalls, const N: usize> Drop for ButtonEvents<'share, S, N> {
+    fn drop(&mut self) {
+        // The stream may be dropped well before the enclosing `share::scope`
+        // ends, so it unsubscribes now to stop further upcalls. The scope's
+        // `Subscribe` unsubscribes again when it ends; that second null
+        // subscribe is redundant but harmless.
+        S::unsubscribe(DRIVER_NUM, 0);
+        self.events.waker.set(None);
+    }
+}
+
+/// The future returned by `ButtonEvents::next_event`.
+pub struct NextEvent<'stream, 'share, S: Syscalls, const N: usize> {
+    stream: &'stream mut ButtonEvents<'share, S, N>,
+}
+
+impl<'stream, 'share, S: Syscalls, const N: usize> Future for NextEvent<'stream, 'share, S, N> {
+    type Output = Option<(u32, ButtonState)>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.stream.poll_next(cx)
+    }
+}
@@ -0,0 +1,135 @@
+use crate::ButtonState;
+use core::cell::Cell;
+
+/// What `ButtonEventQueue` does with an event pushed while it is full.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// Discard the oldest queued event to make room for the new one.
+    DropOldest,
+    /// Discard the new event.
+    DropNewest,
+}
+
+/// A fixed-capacity queue of button events, used to move event handling out
+/// of the listener and into the application's main loop.
+///
+/// ```ignore
+/// let queue = ButtonEventQueue::<8>::new(OverflowPolicy::DropOldest);
+/// let listener = ButtonListener(|button, state| queue.push(button, state));
+///
+/// share::scope(|subscribe| {
+///     Buttons::register_listener(&listener, subscribe)?;
+///     loop {
+///         TockSyscalls::yield_wait();
+///         while let Some((button, state)) = queue.pop() {
+///             // handle the event
+///         }
+///     }
+/// });
+/// ```
+///
+/// Events still queued when the queue is dropped are discarded, unless a
+/// handler is set with `on_drop_flush`.
+pub struct ButtonEventQueue<const N: usize, F: FnMut(u32, ButtonState) = fn(u32, ButtonState)> {
+    events: [Cell<Option<(u32, ButtonState)>>; N],
+    head: Cell<usize>,
+    len: Cell<usize>,
+    dropped: Cell<u32>,
+    policy: OverflowPolicy,
+    flush: Option<F>,
+}
+
+impl<const N: usize> ButtonEventQueue<N> {
+    pub fn new(policy: OverflowPolicy) -> ButtonEventQueue<N> {
+        ButtonEventQueue {
+            events: [(); N].map(|_| Cell::new(None)),
+            head: Cell::new(0),
+            len: Cell::new(0),
+            dropped: Cell::new(0),
+            policy,
+            flush: None,
+        }
+    }
+}
+
+impl<const N: usize, F: FnMut(u32, ButtonState)> ButtonEventQueue<N, F> {
+    /// Passes the events still queued when the queue is dropped to `handler`,
+    /// oldest first, so that they are not lost at shutdown. Events already in
+    /// the queue are kept, and a previously set handler is replaced.
+    ///
+    /// The handler runs in `Drop`, so it must not panic. If the queue is
+    /// dropped while unwinding from another panic, a panic in the handler
+    /// aborts the app.
+    pub fn on_drop_flush<G: FnMut(u32, ButtonState)>(self, handler: G) -> ButtonEventQueue<N, G> {
+        let len = self.len();
+        // Move the events over oldest first, which also leaves nothing for
+        // this queue's own handler to flush.
+        let events = [(); N].map(|_| Cell::new(self.pop()));
+        ButtonEventQueue {
+            events,
+            head: Cell::new(0),
+            len: Cell::new(len),
+            dropped: Cell::new(self.dropped()),
+            policy: self.policy,
+            flush: Some(handler),
+        }
+    }
+
+    /// Add an event to the back of the queue. If the queue is full, an event
+    /// is discarded according to the overflow policy and counted in
+    /// `dropped`.
+    pub fn push(&self, button: u32, state: ButtonState) {
+        if N == 0 {
+            self.dropped.set(self.dropped.get().saturating_add(1));
+            return;
+        }
+        if self.len.get() == N {
+            self.dropped.set(self.dropped.get().saturating_add(1));
+            match self.policy {
+                OverflowPolicy::DropNewest => return,
+                OverflowPolicy::DropOldest => {
+                    self.head.set((self.head.get() + 1) % N);
+                    self.len.set(N - 1);
+                }
+            }
+        }
+        let tail = (self.head.get() + self.len.get()) % N;
+        self.events[tail].set(Some((button, state)));
+        self.len.set(self.len.get() + 1);
+    }
+
+    /// Remove the event at the front of the queue.
+    pub fn pop(&self) -> Option<(u32, ButtonState)> {
+        if self.len.get() == 0 {
+            return None;
+        }
+        let event = self.events[self.head.get()].take();
+        self.head.set((self.head.get() + 1) % N);
+        self.len.set(self.len.get() - 1);
+        event
+    }
+
+    /// The number of queued events.
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len.get() == 0
+    }
+
+    /// The number of events discarded because the queue was full.
+    pub fn dropped(&self) -> u32 {
+        self.dropped.get()
+    }
+}
+
+impl<const N: usize, F: FnMut(u32, ButtonState)> Drop for ButtonEventQueue<N, F> {
+    fn drop(&mut self) {
+        if let Some(mut handler) = self.flush.take() {
+            while let Some((button, state)) = self.pop() {
+                handler(button, state);
+            }
+        }
+    }
+}
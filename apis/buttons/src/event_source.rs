@@ -0,0 +1,166 @@
+use crate::{ButtonListener, ButtonState, Buttons, DRIVER_NUM};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use libtock_alarm::{Alarm, Milliseconds};
+use libtock_platform::{share, ErrorCode, Syscalls};
+
+/// How a `ButtonEventSource` detects button events.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EventMode {
+    /// The driver delivers an upcall for each event.
+    Interrupt,
+    /// The driver's buttons can only be read, so they are read periodically
+    /// and compared with the previous reading instead.
+    Polling,
+}
+
+/// Calls a closure with the button number and new state of each button
+/// event. Created by `Buttons::with_callback`.
+///
+/// Uses button interrupts where the driver supports them, and otherwise reads
+/// buttons 0 to 31 every poll interval (100 ms by default). In polling mode,
+/// a press and release that both happen between two reads are missed.
+///
+/// In interrupt mode, the first `wait` enables interrupts for every button.
+/// They stay enabled between waits, and are disabled again when the source is
+/// dropped, unless `keep_enabled_on_drop` is set.
+///
+/// # Example
+/// ```ignore
+/// let source = Buttons::with_callback(|button, state| {
+///     // make use of the button's state
+/// });
+/// loop {
+///     source.wait()?;
+/// }
+/// ```
+pub struct ButtonEventSource<S: Syscalls, F: Fn(u32, ButtonState)> {
+    callback: F,
+    poll_interval: Milliseconds,
+    mode: Cell<Option<EventMode>>,
+    // The button states at the last read in polling mode.
+    last_mask: Cell<Option<u32>>,
+    // The number of buttons whose interrupts `wait` enabled.
+    enabled: Cell<u32>,
+    keep_enabled_on_drop: bool,
+    _syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls, F: Fn(u32, ButtonState)> ButtonEventSource<S, F> {
+    pub(crate) fn new(callback: F) -> Self {
+        ButtonEventSource {
+            callback,
+            poll_interval: Milliseconds(100),
+            mode: Cell::new(None),
+            last_mask: Cell::new(None),
+            enabled: Cell::new(0),
+            keep_enabled_on_drop: false,
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Sets how often the buttons are read in polling mode. Uses the alarm
+    /// driver.
+    pub fn poll_interval(mut self, poll_interval: Milliseconds) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Leaves button interrupts enabled when the source is dropped, so that
+    /// a listener registered afterwards keeps receiving events without
+    /// enabling them again. The upcall is still unregistered. Only use this
+    /// if such a listener exists: otherwise the kernel keeps handling button
+    /// interrupts that nothing receives.
+    pub fn keep_enabled_on_drop(mut self) -> Self {
+        self.keep_enabled_on_drop = true;
+        self
+    }
+
+    /// The mode used to detect events. The first call checks
+    /// `Buttons::supports_interrupts`, and in polling mode reads the buttons
+    /// that later readings are compared against; later calls return the
+    /// cached mode.
+    pub fn active_mode(&self) -> Result<EventMode, ErrorCode> {
+        if let Some(mode) = self.mode.get() {
+            return Ok(mode);
+        }
+        let mode = match Buttons::<S>::supports_interrupts() {
+            true => EventMode::Interrupt,
+            false => {
+                self.last_mask.set(Some(Buttons::<S>::read_mask()?));
+                EventMode::Polling
+            }
+        };
+        self.mode.set(Some(mode));
+        Ok(mode)
+    }
+
+    /// Blocks until a button event happens, then calls the closure for it. In
+    /// polling mode, the closure is called for each button that changed
+    /// between two reads.
+    pub fn wait(&self) -> Result<(), ErrorCode> {
+        match self.active_mode()? {
+            EventMode::Interrupt => self.wait_interrupt(),
+            EventMode::Polling => self.wait_polling(),
+        }
+    }
+
+    fn wait_interrupt(&self) -> Result<(), ErrorCode> {
+        let event: Cell<Option<(u32, ButtonState)>> = Cell::new(None);
+        let listener = ButtonListener(|button, state| event.set(Some((button, state))));
+        let (button, state) = share::scope(|subscribe| {
+            Buttons::<S>::register_listener(&listener, subscribe)?;
+            if self.enabled.get() == 0 {
+                let count = Buttons::<S>::count()? as u32;
+                Buttons::<S>::enable_interrupts_range(0..count)?;
+                self.enabled.set(count);
+            }
+            loop {
+                S::yield_wait();
+                if let Some(event) = event.get() {
+                    return Ok::<_, ErrorCode>(event);
+                }
+            }
+        })?;
+        (self.callback)(button, state);
+        Ok(())
+    }
+
+    fn wait_polling(&self) -> Result<(), ErrorCode> {
+        let last = match self.last_mask.get() {
+            Some(last) => last,
+            None => Buttons::<S>::read_mask()?,
+        };
+        loop {
+            Alarm::<S>::sleep_for(self.poll_interval)?;
+            let mask = Buttons::<S>::read_mask()?;
+            let changed = mask ^ last;
+            if changed == 0 {
+                continue;
+            }
+            self.last_mask.set(Some(mask));
+            for button in (0..u32::BITS).filter(|button| changed & 1 << button != 0) {
+                (self.callback)(button, (mask & 1 << button != 0).into());
+            }
+            return Ok(());
+        }
+    }
+}
+
+impl<S: Syscalls, F: Fn(u32, ButtonState)> Drop for ButtonEventSource<S, F> {
+    fn drop(&mut self) {
+        if self.enabled.get() == 0 {
+            return;
+        }
+        if !self.keep_enabled_on_drop {
+            for button in 0..self.enabled.get() {
+                let _ = Buttons::<S>::disable_interrupts(button);
+            }
+        }
+        // Install the null upcall, so that button events go nowhere until a
+        // successor registers its listener. The kernel never invokes the null
+        // upcall, and Subscribe discards upcalls still queued for the old one,
+        // so no stray event can reach the listener after this.
+        S::unsubscribe(DRIVER_NUM, 0);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{ButtonState, Buttons, DRIVER_NUM};
+use core::cell::Cell;
+use libtock_platform::{share, AllowRw, CommandId, DefaultConfig, ErrorCode, Subscribe, Syscalls};
+
+/// A button event recorded by the driver in the history buffer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ButtonEvent {
+    pub button: u32,
+    pub state: ButtonState,
+    /// The time of the event, in alarm ticks.
+    pub timestamp: u32,
+}
+
+impl<S: Syscalls> Buttons<S> {
+    /// Records button events into `buffer` until the driver flushes it, then
+    /// returns the number of events recorded. Use `decode_history` to read
+    /// them.
+    ///
+    /// Unlike a listener, which only sees the events that occurred before each
+    /// yield, the driver records every event, so fast sequences of presses are
+    /// not missed. Each event takes `HISTORY_EVENT_SIZE` bytes of `buffer`.
+    /// Recording is disabled and the buffer is un-shared before returning.
+    pub fn read_history(buffer: &mut [u8]) -> Result<usize, ErrorCode> {
+        let called: Cell<Option<(u32,)>> = Cell::new(None);
+        share::scope::<
+            (
+                AllowRw<_, DRIVER_NUM, { allow_rw::HISTORY }>,
+                Subscribe<_, DRIVER_NUM, { subscribe::HISTORY }>,
+            ),
+            _,
+            _,
+        >(|handle| {
+            let (allow_rw, subscribe) = handle.split();
+            S::allow_rw::<DefaultConfig, DRIVER_NUM, { allow_rw::HISTORY }>(allow_rw, buffer)?;
+            S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, { subscribe::HISTORY }>(
+                subscribe, &called,
+            )?;
+
+            S::command_id(BUTTONS_ENABLE_HISTORY, 0, 0).to_result()?;
+            let count = loop {
+                S::yield_wait();
+                if let Some((count,)) = called.get() {
+                    break count as usize;
+                }
+            };
+            S::command_id(BUTTONS_DISABLE_HISTORY, 0, 0).to_result::<(), ErrorCode>()?;
+            Ok(count)
+        })
+    }
+}
+
+/// Decodes the first `count` events recorded in a history buffer by
+/// `Buttons::read_history`. Values that are not a valid button state are
+/// decoded as pressed, as in `ButtonState::from`.
+pub fn decode_history(buffer: &[u8], count: usize) -> impl Iterator<Item = ButtonEvent> + '_ {
+    buffer
+        .chunks_exact(HISTORY_EVENT_SIZE)
+        .take(count)
+        .map(|event| {
+            let word =
+                |i: usize| u32::from_le_bytes([event[i], event[i + 1], event[i + 2], event[i + 3]]);
+            ButtonEvent {
+                button: word(0),
+                state: word(4).into(),
+                timestamp: word(8),
+            }
+        })
+}
+
+/// The number of bytes each event takes in the history buffer: the button
+/// number, its state and the timestamp, as little-endian u32s.
+pub const HISTORY_EVENT_SIZE: usize = 12;
+
+const BUTTONS_ENABLE_HISTORY: CommandId<DRIVER_NUM> = CommandId(4);
+const BUTTONS_DISABLE_HISTORY: CommandId<DRIVER_NUM> = CommandId(5);
+
+mod subscribe {
+    pub const HISTORY: u32 = 1;
+}
+
+mod allow_rw {
+    pub const HISTORY: u32 = 0;
+}
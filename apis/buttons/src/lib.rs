@@ -1,32 +1,94 @@
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::cell::Cell;
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+use core::ops::Range;
+use core::sync::atomic::{AtomicBool, Ordering};
 use libtock_platform::{
-    share::Handle, subscribe::OneId, DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall,
+    share, share::Handle, subscribe::OneId, CommandId, CommandReturn, DefaultConfig, ErrorCode,
+    Subscribe, Syscalls, Upcall,
 };
 
-/// The Buttons driver
-///
-/// # Example
-/// ```ignore
-/// use libtock::Buttons;
-///
-/// // Read button state
-/// Buttons::is_pressed(0);
-///
-/// // Register for events
-///
-/// let listener = ButtonListener(|button, state| {
-///     // make use of the button's state
-/// });
-///
-/// share::scope(|subscribe| {
-///     if let Ok(()) = Buttons::register_listener(&listener, subscribe) {
-///         // yield
-///     }
-/// });
-/// ```
+mod async_events;
+mod event_queue;
+mod event_source;
+mod history;
 
-pub struct Buttons<S: Syscalls>(S);
+pub use async_events::{AsyncButtonEvents, ButtonEvents, NextEvent};
+pub use event_queue::{ButtonEventQueue, OverflowPolicy};
+pub use event_source::{ButtonEventSource, EventMode};
+pub use history::{decode_history, ButtonEvent, HISTORY_EVENT_SIZE};
+pub use libtock_platform::subscribe::SubscribeReturn;
+
+libtock_platform::define_driver! {
+    /// The Buttons driver
+    ///
+    /// # Example
+    /// ```ignore
+    /// use libtock::Buttons;
+    ///
+    /// // Read button state
+    /// Buttons::is_pressed(0);
+    ///
+    /// // Register for events
+    ///
+    /// let listener = ButtonListener(|button, state| {
+    ///     // make use of the button's state
+    /// });
+    ///
+    /// share::scope(|subscribe| {
+    ///     if let Ok(()) = Buttons::register_listener(&listener, subscribe) {
+    ///         // yield
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// `Buttons` holds no data and makes its system calls on the calling
+    /// thread, so it is `Send` and `Sync` whenever `S` is.
+    ///
+    /// # Malformed kernel data
+    /// Values from the kernel that break the driver's contract, such as a
+    /// button state other than 0 or 1, fail a `debug_assert!` so they are
+    /// noticed during development. Release builds never panic on them:
+    /// they are handled as documented at each conversion, e.g. by returning
+    /// `BadRVal`.
+    pub struct Buttons;
+    driver_num: DRIVER_NUM;
+    listener: ButtonListener, Fn(u32, ButtonState);
+}
+
+/// Why `Buttons::check` found no buttons to use.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ButtonsError {
+    /// The driver is present, but has no buttons.
+    NoButtons,
+    /// The driver is missing, or the count command failed with this error.
+    NotSupported(ErrorCode),
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for ButtonsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ButtonsError::NoButtons => f.write_str("the buttons driver has no buttons"),
+            ButtonsError::NotSupported(_) => f.write_str("the buttons driver is not available"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ButtonsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ButtonsError::NoButtons => None,
+            ButtonsError::NotSupported(error) => Some(error),
+        }
+    }
+}
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum ButtonState {
@@ -34,8 +96,28 @@ pub enum ButtonState {
     Released,
 }
 
+impl ButtonState {
+    /// Decode a button state reported by the driver. Unlike `From<u32>`, which
+    /// treats any non-zero value as pressed, values other than 0 and 1 return
+    /// `BadRVal`.
+    pub fn from_raw(value: u32) -> Result<ButtonState, ErrorCode> {
+        match value {
+            0 => Ok(ButtonState::Released),
+            1 => Ok(ButtonState::Pressed),
+            _ => {
+                debug_assert!(false, "invalid button state {}", value);
+                Err(ErrorCode::BadRVal)
+            }
+        }
+    }
+}
+
+/// Converts a button state reported in an upcall. In release builds, any
+/// non-zero value is treated as pressed, so malformed values from the kernel
+/// never cause a panic.
 impl From<u32> for ButtonState {
     fn from(value: u32) -> ButtonState {
+        debug_assert!(value <= 1, "invalid button state {}", value);
         match value {
             0 => ButtonState::Released,
             _ => ButtonState::Pressed,
@@ -43,19 +125,76 @@ impl From<u32> for ButtonState {
     }
 }
 
+impl From<bool> for ButtonState {
+    fn from(pressed: bool) -> ButtonState {
+        match pressed {
+            true => ButtonState::Pressed,
+            false => ButtonState::Released,
+        }
+    }
+}
+
+impl From<ButtonState> for bool {
+    fn from(state: ButtonState) -> bool {
+        state == ButtonState::Pressed
+    }
+}
+
+impl From<ButtonState> for u8 {
+    fn from(state: ButtonState) -> u8 {
+        match state {
+            ButtonState::Pressed => 1,
+            ButtonState::Released => 0,
+        }
+    }
+}
+
 impl<S: Syscalls> Buttons<S> {
     /// Run a check against the buttons capsule to ensure it is present.
     ///
     /// Returns `Ok(number_of_buttons)` if the driver was present. This does not necessarily mean
-    /// that the driver is working.
-    pub fn count() -> Result<u32, ErrorCode> {
-        S::command(DRIVER_NUM, BUTTONS_COUNT, 0, 0).to_result()
+    /// that the driver is working. A count that does not fit in a `u16` cannot be a real number of
+    /// buttons, and returns `NoSupport`.
+    pub fn count() -> Result<u16, ErrorCode> {
+        let count: u32 = S::command_id(BUTTONS_COUNT, 0, 0).to_result()?;
+        u16::try_from(count).map_err(|_| ErrorCode::NoSupport)
+    }
+
+    /// Like `count`, but tells a driver without buttons apart from a missing
+    /// or failing driver: a count of 0 returns `NoButtons`, and errors from
+    /// `count` are returned as `NotSupported`.
+    pub fn check() -> Result<u16, ButtonsError> {
+        match Self::count() {
+            Ok(0) => Err(ButtonsError::NoButtons),
+            Ok(count) => Ok(count),
+            Err(error) => Err(ButtonsError::NotSupported(error)),
+        }
     }
 
     /// Read the state of a button
     pub fn read(button: u32) -> Result<ButtonState, ErrorCode> {
-        let button_state: u32 = S::command(DRIVER_NUM, BUTTONS_READ, button, 0).to_result()?;
-        Ok(button_state.into())
+        let result = S::command_id(BUTTONS_READ, button, 0)
+            .to_result()
+            .and_then(ButtonState::from_raw);
+        log_error::<S, _>("read", result)
+    }
+
+    /// Read the state of buttons 0 to 31 at once, with bit `n` set if button
+    /// `n` is pressed. Uses a single system call if the driver supports bulk
+    /// reads; otherwise (`NoSupport`) each button is read separately.
+    pub fn read_mask() -> Result<u32, ErrorCode> {
+        match S::command_id(BUTTONS_READ_MASK, 0, 0).to_result() {
+            Err(ErrorCode::NoSupport) => {}
+            result => return result,
+        }
+        let count = Self::count()?.min(u32::BITS as u16) as u32;
+        let mut mask = 0;
+        for button in 0..count {
+            if Self::read(button)? == ButtonState::Pressed {
+                mask |= 1 << button;
+            }
+        }
+        Ok(mask)
     }
 
     /// Returns `true` if a button is pressed
@@ -83,36 +222,258 @@ impl<S: Syscalls> Buttons<S> {
     }
 
     /// Enable events (interrupts) for a button
+    ///
+    /// Any success variant returned by the kernel is accepted, as some kernels
+    /// return a value along with the success.
     pub fn enable_interrupts(button: u32) -> Result<(), ErrorCode> {
-        S::command(DRIVER_NUM, BUTTONS_ENABLE_INTERRUPTS, button, 0).to_result()
+        success_or_error(S::command_id(BUTTONS_ENABLE_INTERRUPTS, button, 0))
+    }
+
+    /// Returns `false` if the driver cannot deliver button events, as on
+    /// boards whose buttons can only be read. Probes by enabling and then
+    /// disabling interrupts on button 0 and checking for `NoSupport`, so it
+    /// should be called before interrupts are enabled on that button.
+    pub fn supports_interrupts() -> bool {
+        match Self::enable_interrupts(0) {
+            Err(ErrorCode::NoSupport) => false,
+            Err(_) => true,
+            Ok(()) => {
+                let _ = Self::disable_interrupts(0);
+                true
+            }
+        }
+    }
+
+    /// Calls `callback` for each button event, using interrupts where the
+    /// driver supports them and polling otherwise. No system calls are made
+    /// until the returned `ButtonEventSource` is used.
+    pub fn with_callback<F: Fn(u32, ButtonState)>(callback: F) -> ButtonEventSource<S, F> {
+        ButtonEventSource::new(callback)
+    }
+
+    /// Enable events (interrupts) for a contiguous range of buttons
+    ///
+    /// If enabling any of the buttons fails, the buttons enabled by this call
+    /// are disabled again and the error is returned.
+    pub fn enable_interrupts_range(buttons: Range<u32>) -> Result<(), ErrorCode> {
+        for button in buttons.clone() {
+            if let Err(error) = Self::enable_interrupts(button) {
+                for enabled in buttons.start..button {
+                    let _ = Self::disable_interrupts(enabled);
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
     }
 
     /// Disable events (interrupts) for a button
+    ///
+    /// Like `enable_interrupts`, any success variant is accepted.
     pub fn disable_interrupts(button: u32) -> Result<(), ErrorCode> {
-        S::command(DRIVER_NUM, BUTTONS_DISABLE_INTERRUPTS, button, 0).to_result()
+        success_or_error(S::command_id(BUTTONS_DISABLE_INTERRUPTS, button, 0))
+    }
+
+    /// Returns whether events (interrupts) are enabled for a button
+    pub fn interrupts_enabled(button: u32) -> Result<bool, ErrorCode> {
+        let enabled: u32 = S::command_id(BUTTONS_INTERRUPTS_ENABLED, button, 0).to_result()?;
+        Ok(enabled != 0)
+    }
+
+    /// Disable events (interrupts) for every button requested through
+    /// `pending` since the last call, then clear the requests.
+    ///
+    /// All requested buttons are disabled even if some of them fail, and the
+    /// first error is returned.
+    pub fn apply_pending_disables(pending: &PendingDisables) -> Result<(), ErrorCode> {
+        let mut buttons = pending.0.replace(0);
+        let mut result = Ok(());
+        while buttons != 0 {
+            let button = buttons.trailing_zeros();
+            buttons &= !(1 << button);
+            if let Err(error) = Self::disable_interrupts(button) {
+                result = result.and(Err(error));
+            }
+        }
+        result
     }
 
-    /// Register an events listener
+    /// Register a separate handler for each button, replacing any registered
+    /// listener.
+    pub fn register_handlers<'share, const N: usize>(
+        handlers: &'share ButtonHandlers<'share, N>,
+        subscribe: Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+    ) -> Result<(), ErrorCode> {
+        S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, handlers)
+    }
+
+    /// Register an events listener and run `f` while it stays registered,
+    /// failing with `Already` while a listener registered through another
+    /// `register_exclusive` is still active. The listener is unregistered when
+    /// `f` returns. Listeners registered with `register_listener` are not
+    /// tracked.
     ///
-    /// There can be only one single listener registered at a time.
-    /// Each time this function is used, it will replace the
-    /// previously registered listener.
-    pub fn register_listener<'share, F: Fn(u32, ButtonState)>(
+    /// `register_exclusive` opens the `share::scope` itself, so a rejected
+    /// call never holds a `Subscribe` handle whose cleanup would unregister the
+    /// active listener.
+    pub fn register_exclusive<F: Fn(u32, ButtonState), G: FnOnce() -> R, R>(
+        listener: &ButtonListener<F>,
+        f: G,
+    ) -> Result<R, ErrorCode> {
+        let _claim = ExclusiveClaim::take()?;
+        share::scope(|subscribe| {
+            Self::register_listener(listener, subscribe)?;
+            Ok(f())
+        })
+    }
+
+    /// Like `register_listener`, but also records the registers returned by
+    /// the Subscribe system call in `diagnostics`, whether it succeeds or not.
+    pub fn register_listener_recorded<'share, F: Fn(u32, ButtonState)>(
         listener: &'share ButtonListener<F>,
         subscribe: Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+        diagnostics: &SubscribeDiagnostics,
     ) -> Result<(), ErrorCode> {
-        S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, listener)
+        let (result, registers) =
+            S::subscribe_raw::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, listener);
+        diagnostics.0.set(Some(registers));
+        result
     }
 
-    /// Unregister the events listener
+    /// Registers a listener stored in a `'static` slot, such as a `static mut`,
+    /// without a `share::scope`. The listener stays registered until
+    /// `unregister_listener` is called or another listener replaces it.
+    ///
+    /// Compared to `register_listener`, the listener's address is stable for
+    /// the life of the app, so the subscription does not need a scope that
+    /// outlives it, and can be made once at startup. The cost is that the
+    /// listener's type must be nameable in a `static`, which rules out
+    /// closures that capture state: use a function or a non-capturing closure
+    /// that keeps its state in other statics. Nothing unregisters the listener
+    /// automatically.
+    ///
+    /// ```ignore
+    /// fn on_button(button: u32, state: ButtonState) {
+    ///     // make use of the button's state
+    /// }
+    /// static mut LISTENER: ButtonListener<fn(u32, ButtonState)> = ButtonListener(on_button);
     ///
-    /// This function may be used even if there was no
-    /// previously registered listener.
-    pub fn unregister_listener() {
-        S::unsubscribe(DRIVER_NUM, 0)
+    /// Buttons::with_static_callback(unsafe { &mut LISTENER })?;
+    /// ```
+    pub fn with_static_callback<F: Fn(u32, ButtonState)>(
+        listener: &'static mut ButtonListener<F>,
+    ) -> Result<(), ErrorCode> {
+        let subscribe = Subscribe::<'static, S, DRIVER_NUM, 0>::default();
+        // Safety: `Handle::new` requires `subscribe` to be dropped, because its
+        // drop unsubscribes before a borrowed upcall becomes invalid. It is
+        // forgotten instead, which is sound because the listener is `'static`
+        // and so never becomes invalid.
+        let handle = unsafe { Handle::new(&subscribe) };
+        let result = S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(handle, listener);
+        core::mem::forget(subscribe);
+        result
+    }
+
+    /// Disable events (interrupts) for `button` while `f` runs, then restore
+    /// the button's prior state, e.g. to ignore presses during a long
+    /// operation.
+    ///
+    /// The prior state is read with `interrupts_enabled`. Drivers without that
+    /// query, which include the upstream Tock buttons driver, answer
+    /// `NoSupport`; the button is then assumed to have had interrupts enabled,
+    /// so on those kernels a disabled button comes back enabled.
+    ///
+    /// If disabling fails, `f` is not called and the error is returned. If `f`
+    /// returns but restoring the state fails, `f`'s result is discarded and
+    /// the error is returned. If `f` unwinds, the state is still restored and
+    /// errors are ignored.
+    pub fn with_suppressed<F: FnOnce() -> R, R>(button: u32, f: F) -> Result<R, ErrorCode> {
+        let enabled = match Self::interrupts_enabled(button) {
+            Err(ErrorCode::NoSupport) => true,
+            result => result?,
+        };
+        Self::disable_interrupts(button)?;
+        let mut suppressed = Suppressed::<S> {
+            button,
+            enabled,
+            restored: false,
+            _syscalls: PhantomData,
+        };
+        let result = f();
+        suppressed.restore().map(|()| result)
+    }
+}
+
+// Whether a listener registered by `Buttons::register_exclusive` is active.
+static EXCLUSIVE_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// Marks the driver as having an exclusive listener until dropped.
+struct ExclusiveClaim;
+
+impl ExclusiveClaim {
+    fn take() -> Result<ExclusiveClaim, ErrorCode> {
+        // Tock processes are single-threaded, so the flag needs no atomic
+        // read-modify-write (which some targets lack).
+        if EXCLUSIVE_ACTIVE.load(Ordering::Relaxed) {
+            return Err(ErrorCode::Already);
+        }
+        EXCLUSIVE_ACTIVE.store(true, Ordering::Relaxed);
+        Ok(ExclusiveClaim)
     }
 }
 
+impl Drop for ExclusiveClaim {
+    fn drop(&mut self) {
+        EXCLUSIVE_ACTIVE.store(false, Ordering::Relaxed);
+    }
+}
+
+// Restores a button's interrupts for `with_suppressed`: explicitly through
+// `restore` once its closure returns, or when dropped if the closure unwinds.
+struct Suppressed<S: Syscalls> {
+    button: u32,
+    // Whether interrupts were enabled before `with_suppressed` disabled them.
+    enabled: bool,
+    // Whether `restore` has already run, so `drop` has nothing left to do.
+    restored: bool,
+    _syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls> Suppressed<S> {
+    fn restore(&mut self) -> Result<(), ErrorCode> {
+        self.restored = true;
+        match self.enabled {
+            true => Buttons::<S>::enable_interrupts(self.button),
+            false => Ok(()),
+        }
+    }
+}
+
+impl<S: Syscalls> Drop for Suppressed<S> {
+    fn drop(&mut self) {
+        if !self.restored {
+            let _ = log_error::<S, _>("restore interrupts", self.restore());
+        }
+    }
+}
+
+// Writes the error in `result`, if any, to the console when the `console_log`
+// feature is enabled, for on-device diagnostics. Without the feature it only
+// returns `result`. Used where an error would otherwise go unnoticed, such as
+// in destructors.
+fn log_error<S: Syscalls, T>(
+    operation: &str,
+    result: Result<T, ErrorCode>,
+) -> Result<T, ErrorCode> {
+    #[cfg(feature = "console_log")]
+    if let Err(error) = &result {
+        libtock_console::log!(S, "buttons: {} failed: {:?}", operation, error);
+    }
+    #[cfg(not(feature = "console_log"))]
+    let _ = operation;
+    result
+}
+
 /// A wrapper around a closure to be registered and called when
 /// a button event occurs.
 ///
@@ -128,6 +489,110 @@ impl<F: Fn(u32, ButtonState)> Upcall<OneId<DRIVER_NUM, 0>> for ButtonListener<F>
         self.0(button_index, state.into())
     }
 }
+
+/// A listener with a separate handler for each of the first `N` buttons,
+/// usually with `N` equal to `Buttons::count`.
+///
+/// Events for buttons without a handler are ignored. The kernel should never
+/// report a button beyond `count`, but a misbehaving kernel cannot make the
+/// dispatch index out of bounds.
+///
+/// ```ignore
+/// let select = |state| { /* handle the first button */ };
+/// let back = |state| { /* handle the second button */ };
+/// let handlers = ButtonHandlers([&select, &back]);
+/// share::scope(|subscribe| {
+///     Buttons::register_handlers(&handlers, subscribe)?;
+///     // yield
+/// });
+/// ```
+pub struct ButtonHandlers<'a, const N: usize>(pub [&'a dyn Fn(ButtonState); N]);
+
+impl<const N: usize> Upcall<OneId<DRIVER_NUM, 0>> for ButtonHandlers<'_, N> {
+    fn upcall(&self, button_index: u32, state: u32, _arg2: u32) {
+        if let Some(handler) = usize::try_from(button_index)
+            .ok()
+            .and_then(|index| self.0.get(index))
+        {
+            handler(state.into())
+        }
+    }
+}
+/// Stores the registers returned by the most recent subscribe made through
+/// `Buttons::register_listener_recorded`, for diagnosing boards where
+/// subscribing misbehaves.
+#[derive(Default)]
+pub struct SubscribeDiagnostics(Cell<Option<SubscribeReturn>>);
+
+impl SubscribeDiagnostics {
+    pub fn new() -> SubscribeDiagnostics {
+        SubscribeDiagnostics(Cell::new(None))
+    }
+
+    /// The registers returned by the most recent subscribe, or `None` if no
+    /// subscribe has been recorded yet.
+    pub fn last_subscribe(&self) -> Option<SubscribeReturn> {
+        self.0.get()
+    }
+}
+
+/// Buttons whose events should be disabled once the current upcall returns.
+///
+/// Tock allows system calls from inside an upcall, so a listener may call
+/// `Buttons::disable_interrupts` directly. A listener that wants to stop
+/// receiving events (e.g. a one-shot button) can instead call `request`, and
+/// the application disables the buttons after yielding by calling
+/// `Buttons::apply_pending_disables`. This keeps the upcall free of system
+/// calls and leaves the application in control of when the disables happen,
+/// e.g. after it has handled the press.
+///
+/// ```ignore
+/// let pending = PendingDisables::new();
+/// let listener = ButtonListener(|button, _state| {
+///     let _ = pending.request(button);
+/// });
+///
+/// share::scope(|subscribe| {
+///     Buttons::register_listener(&listener, subscribe)?;
+///     loop {
+///         TockSyscalls::yield_wait();
+///         Buttons::apply_pending_disables(&pending)?;
+///     }
+/// });
+/// ```
+#[derive(Default)]
+pub struct PendingDisables(Cell<u32>);
+
+impl PendingDisables {
+    pub fn new() -> PendingDisables {
+        PendingDisables(Cell::new(0))
+    }
+
+    /// Request that events for `button` be disabled. Only buttons 0 to 31
+    /// can be requested; other buttons return `Invalid`.
+    pub fn request(&self, button: u32) -> Result<(), ErrorCode> {
+        if button >= u32::BITS {
+            return Err(ErrorCode::Invalid);
+        }
+        self.0.set(self.0.get() | 1 << button);
+        Ok(())
+    }
+
+    /// Returns `true` if there are requests not yet applied.
+    pub fn is_pending(&self) -> bool {
+        self.0.get() != 0
+    }
+}
+
+// Converts the return of a command that reports no data, accepting any success
+// variant.
+fn success_or_error(command_return: CommandReturn) -> Result<(), ErrorCode> {
+    match command_return.is_any_success() {
+        true => Ok(()),
+        false => command_return.to_result(),
+    }
+}
+
 #[cfg(test)]
 mod tests;
 
@@ -135,12 +600,14 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 3;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::BUTTONS;
 
 // Command IDs
-const BUTTONS_COUNT: u32 = 0;
+const BUTTONS_COUNT: CommandId<DRIVER_NUM> = CommandId(0);
 
-const BUTTONS_ENABLE_INTERRUPTS: u32 = 1;
-const BUTTONS_DISABLE_INTERRUPTS: u32 = 2;
+const BUTTONS_ENABLE_INTERRUPTS: CommandId<DRIVER_NUM> = CommandId(1);
+const BUTTONS_DISABLE_INTERRUPTS: CommandId<DRIVER_NUM> = CommandId(2);
 
-const BUTTONS_READ: u32 = 3;
+const BUTTONS_READ: CommandId<DRIVER_NUM> = CommandId(3);
+const BUTTONS_READ_MASK: CommandId<DRIVER_NUM> = CommandId(6);
+const BUTTONS_INTERRUPTS_ENABLED: CommandId<DRIVER_NUM> = CommandId(7);
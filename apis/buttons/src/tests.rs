@@ -1,9 +1,13 @@
 use core::cell::Cell;
 
 use libtock_platform::{share, ErrorCode, Syscalls, YieldNoWaitReturn};
-use libtock_unittest::fake;
+use libtock_unittest::{executor, fake, TestLock};
 
-use crate::{ButtonListener, ButtonState};
+use crate::{
+    decode_history, AsyncButtonEvents, ButtonEvent, ButtonEventQueue, ButtonHandlers,
+    ButtonListener, ButtonState, OverflowPolicy, PendingDisables, SubscribeDiagnostics,
+    HISTORY_EVENT_SIZE,
+};
 
 type Buttons = super::Buttons<fake::Syscalls>;
 
@@ -21,6 +25,67 @@ fn num_buttons() {
     assert_eq!(Buttons::count(), Ok(10));
 }
 
+#[test]
+fn check() {
+    use crate::ButtonsError;
+    use libtock_unittest::command_return;
+
+    let kernel = fake::Kernel::new();
+    assert_eq!(
+        Buttons::check(),
+        Err(ButtonsError::NotSupported(ErrorCode::NoDevice))
+    );
+
+    // The driver is present, but has no buttons.
+    let driver = fake::Buttons::<0>::new();
+    kernel.add_driver(&driver);
+    assert_eq!(Buttons::count(), Ok(0));
+    assert_eq!(Buttons::check(), Err(ButtonsError::NoButtons));
+
+    kernel
+        .expect()
+        .command(crate::DRIVER_NUM, crate::BUTTONS_COUNT.into())
+        .returns(command_return::failure(ErrorCode::Fail));
+    assert_eq!(
+        Buttons::check(),
+        Err(ButtonsError::NotSupported(ErrorCode::Fail))
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn buttons_error_std_error() {
+    use crate::ButtonsError;
+    use std::error::Error;
+    use std::string::ToString;
+
+    assert_eq!(
+        ButtonsError::NoButtons.to_string(),
+        "the buttons driver has no buttons"
+    );
+    assert!(ButtonsError::NoButtons.source().is_none());
+    let error = ButtonsError::NotSupported(ErrorCode::NoDevice);
+    assert_eq!(error.to_string(), "the buttons driver is not available");
+    assert_eq!(error.source().unwrap().to_string(), "NODEVICE");
+}
+
+#[test]
+fn num_buttons_too_large() {
+    use libtock_unittest::{command_return, ExpectedSyscall};
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DRIVER_NUM,
+        command_id: crate::BUTTONS_COUNT.into(),
+        argument0: 0,
+        argument1: 0,
+        override_return: Some(command_return::success_u32(0x1_0000)),
+    });
+    assert_eq!(Buttons::count(), Err(ErrorCode::NoSupport));
+}
+
 #[test]
 fn read() {
     let kernel = fake::Kernel::new();
@@ -36,6 +101,205 @@ fn read() {
     assert_eq!(Buttons::read(11), Err(ErrorCode::Invalid));
 }
 
+#[test]
+fn read_mask() {
+    use libtock_unittest::SyscallLogEntry;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(Buttons::read_mask(), Ok(0));
+    assert_eq!(driver.set_pressed(0, true), Ok(()));
+    assert_eq!(driver.set_pressed(3, true), Ok(()));
+    assert_eq!(driver.set_pressed(9, true), Ok(()));
+    kernel.take_syscall_log();
+    assert_eq!(Buttons::read_mask(), Ok(0b10_0000_1001));
+    assert_eq!(kernel.take_syscall_log().len(), 1);
+
+    // Without bulk reads, each button is read separately.
+    driver.set_read_mask_supported(false);
+    assert_eq!(Buttons::read_mask(), Ok(0b10_0000_1001));
+    let reads = kernel
+        .take_syscall_log()
+        .into_iter()
+        .filter(|entry| {
+            matches!(
+                entry,
+                SyscallLogEntry::Command {
+                    command_id,
+                    ..
+                } if *command_id == crate::BUTTONS_READ.into()
+            )
+        })
+        .count();
+    assert_eq!(reads, 10);
+}
+
+#[test]
+fn supports_interrupts() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<2>::new();
+    kernel.add_driver(&driver);
+
+    assert!(Buttons::supports_interrupts());
+    // The probe leaves button 0's interrupts disabled.
+    assert!(!driver.get_button_state(0).unwrap().interrupt_enabled);
+
+    driver.set_interrupts_supported(false);
+    assert!(!Buttons::supports_interrupts());
+}
+
+#[test]
+fn with_callback_interrupts() {
+    use crate::EventMode;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<2>::new();
+    kernel.add_driver(&driver);
+
+    let event = Cell::new(None);
+    let source = Buttons::with_callback(|button, state| event.set(Some((button, state))));
+    kernel.assert_no_syscalls();
+    assert_eq!(source.active_mode(), Ok(EventMode::Interrupt));
+
+    driver.set_pressed_on_enable(1, true);
+    assert_eq!(source.wait(), Ok(()));
+    assert_eq!(event.get(), Some((1, ButtonState::Pressed)));
+    // Interrupts stay enabled until the source is dropped.
+    assert!(driver.get_button_state(0).unwrap().interrupt_enabled);
+    drop(source);
+    assert!(!driver.get_button_state(0).unwrap().interrupt_enabled);
+    assert!(!driver.get_button_state(1).unwrap().interrupt_enabled);
+}
+
+#[test]
+fn keep_enabled_on_drop() {
+    use libtock_unittest::SyscallLogEntry;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<2>::new();
+    kernel.add_driver(&driver);
+
+    let source = Buttons::with_callback(|_, _| {}).keep_enabled_on_drop();
+    driver.set_pressed_on_enable(1, true);
+    assert_eq!(source.wait(), Ok(()));
+    kernel.take_syscall_log();
+    drop(source);
+    // The upcall is unregistered, but no disable command is issued.
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Subscribe {
+            driver_num: crate::DRIVER_NUM,
+            subscribe_num: 0,
+        }]
+    );
+    assert!(driver.get_button_state(0).unwrap().interrupt_enabled);
+    assert!(driver.get_button_state(1).unwrap().interrupt_enabled);
+}
+
+#[test]
+fn stray_upcall_after_drop() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<2>::new();
+    kernel.add_driver(&driver);
+
+    // An event queued before the listener is unregistered is discarded along
+    // with it, rather than delivered to a listener that no longer exists.
+    let calls = Cell::new(0);
+    let listener = ButtonListener(|_, _| calls.set(calls.get() + 1));
+    assert_eq!(Buttons::enable_interrupts(0), Ok(()));
+    share::scope(|subscribe| {
+        assert_eq!(Buttons::register_listener(&listener, subscribe), Ok(()));
+        assert_eq!(driver.set_pressed(0, true), Ok(()));
+        Buttons::unregister_listener();
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+    assert_eq!(calls.get(), 0);
+
+    // With interrupts left enabled, events after the drop reach the null
+    // upcall, which the kernel does not invoke.
+    let source = Buttons::with_callback(|_, _| calls.set(calls.get() + 1)).keep_enabled_on_drop();
+    driver.set_pressed_on_enable(1, true);
+    assert_eq!(source.wait(), Ok(()));
+    assert_eq!(calls.get(), 1);
+    drop(source);
+    assert_eq!(driver.set_pressed(1, false), Ok(()));
+    assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn with_callback_polling() {
+    use crate::EventMode;
+    use libtock_alarm::{Alarm, Milliseconds, Ticks};
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&alarm);
+    let driver = fake::Buttons::<2>::new();
+    kernel.add_driver(&driver);
+    driver.set_interrupts_supported(false);
+
+    let events = Cell::new([None; 2]);
+    let source = Buttons::with_callback(|button, state| {
+        let mut values = events.get();
+        values[button as usize] = Some(state);
+        events.set(values);
+    })
+    .poll_interval(Milliseconds(50));
+    // Finding the mode also reads the initial button states.
+    assert_eq!(source.active_mode(), Ok(EventMode::Polling));
+
+    assert_eq!(driver.set_pressed(0, true), Ok(()));
+    assert_eq!(driver.set_pressed(1, true), Ok(()));
+    assert_eq!(source.wait(), Ok(()));
+    assert_eq!(
+        events.get(),
+        [Some(ButtonState::Pressed), Some(ButtonState::Pressed)]
+    );
+    assert_eq!(Alarm::<fake::Syscalls>::get_ticks(), Ok(Ticks(50)));
+
+    events.set([None; 2]);
+    assert_eq!(driver.set_pressed(1, false), Ok(()));
+    assert_eq!(source.wait(), Ok(()));
+    assert_eq!(events.get(), [None, Some(ButtonState::Released)]);
+}
+
+// Tests that the read command's return is decoded into a state, the returned
+// error, or BadRVal for a value that is not a button state.
+#[test]
+fn read_command_return() {
+    use libtock_unittest::{command_return, ExpectedSyscall};
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DRIVER_NUM,
+        command_id: crate::BUTTONS_READ.into(),
+        argument0: 0,
+        argument1: 0,
+        override_return: Some(command_return::failure(ErrorCode::Fail)),
+    });
+    assert_eq!(Buttons::read(0), Err(ErrorCode::Fail));
+
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DRIVER_NUM,
+        command_id: crate::BUTTONS_READ.into(),
+        argument0: 0,
+        argument1: 0,
+        override_return: Some(command_return::success_u32(2)),
+    });
+    if let Some(result) = malformed(|| Buttons::read(0)) {
+        assert_eq!(result, Err(ErrorCode::BadRVal));
+    }
+
+    assert_eq!(ButtonState::from_raw(0), Ok(ButtonState::Released));
+    assert_eq!(ButtonState::from_raw(1), Ok(ButtonState::Pressed));
+    if let Some(result) = malformed(|| ButtonState::from_raw(u32::MAX)) {
+        assert_eq!(result, Err(ErrorCode::BadRVal));
+    }
+}
+
 #[test]
 fn interrupts() {
     let kernel = fake::Kernel::new();
@@ -113,3 +377,763 @@ fn subscribe() {
     });
     assert!(!pressed_interrupt_count.get());
 }
+
+// Tests that the command return of the interrupt commands is decoded into
+// success for any success variant, the returned error, or BadRVal for an
+// unexpected failure variant.
+#[test]
+fn interrupts_command_return() {
+    use libtock_unittest::{command_return, ExpectedSyscall};
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DRIVER_NUM,
+        command_id: crate::BUTTONS_ENABLE_INTERRUPTS.into(),
+        argument0: 0,
+        argument1: 0,
+        override_return: None,
+    });
+    assert_eq!(Buttons::enable_interrupts(0), Ok(()));
+
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DRIVER_NUM,
+        command_id: crate::BUTTONS_ENABLE_INTERRUPTS.into(),
+        argument0: 1,
+        argument1: 0,
+        override_return: Some(command_return::failure(ErrorCode::NoMem)),
+    });
+    assert_eq!(Buttons::enable_interrupts(1), Err(ErrorCode::NoMem));
+
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DRIVER_NUM,
+        command_id: crate::BUTTONS_ENABLE_INTERRUPTS.into(),
+        argument0: 2,
+        argument1: 0,
+        override_return: Some(command_return::success_u32(0)),
+    });
+    assert_eq!(Buttons::enable_interrupts(2), Ok(()));
+
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DRIVER_NUM,
+        command_id: crate::BUTTONS_DISABLE_INTERRUPTS.into(),
+        argument0: 0,
+        argument1: 0,
+        override_return: Some(command_return::success_2_u32(1, 2)),
+    });
+    assert_eq!(Buttons::disable_interrupts(0), Ok(()));
+
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DRIVER_NUM,
+        command_id: crate::BUTTONS_DISABLE_INTERRUPTS.into(),
+        argument0: 1,
+        argument1: 0,
+        override_return: Some(command_return::failure_u32(ErrorCode::Fail, 3)),
+    });
+    assert_eq!(Buttons::disable_interrupts(1), Err(ErrorCode::BadRVal));
+}
+
+#[test]
+fn interrupts_range() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(Buttons::enable_interrupts_range(0..3), Ok(()));
+    for button in 0..3 {
+        assert!(driver.get_button_state(button).unwrap().interrupt_enabled);
+        assert_eq!(Buttons::read(button), Ok(ButtonState::Released));
+    }
+    assert!(!driver.get_button_state(3).unwrap().interrupt_enabled);
+
+    // Buttons enabled before the failure are disabled again.
+    assert_eq!(
+        Buttons::enable_interrupts_range(8..12),
+        Err(ErrorCode::Invalid)
+    );
+    assert!(!driver.get_button_state(8).unwrap().interrupt_enabled);
+    assert!(!driver.get_button_state(9).unwrap().interrupt_enabled);
+
+    assert_eq!(Buttons::enable_interrupts_range(5..5), Ok(()));
+}
+
+#[test]
+fn button_state_conversions() {
+    assert!(bool::from(ButtonState::Pressed));
+    assert!(!bool::from(ButtonState::Released));
+    assert_eq!(u8::from(ButtonState::Pressed), 1);
+    assert_eq!(u8::from(ButtonState::Released), 0);
+
+    assert_eq!(ButtonState::from(true), ButtonState::Pressed);
+    assert_eq!(ButtonState::from(false), ButtonState::Released);
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        assert_eq!(ButtonState::from(bool::from(state)), state);
+        assert_eq!(ButtonState::from(u8::from(state) as u32), state);
+    }
+}
+
+#[test]
+fn one_shot() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    let pending = PendingDisables::new();
+    assert_eq!(pending.request(32), Err(ErrorCode::Invalid));
+    assert!(!pending.is_pending());
+
+    let presses: Cell<u32> = Cell::new(0);
+    let listener = ButtonListener(|button, _| {
+        assert_eq!(button, 2);
+        presses.set(presses.get() + 1);
+        assert_eq!(pending.request(button), Ok(()));
+    });
+    assert_eq!(Buttons::enable_interrupts(2), Ok(()));
+    assert_eq!(Buttons::enable_interrupts(3), Ok(()));
+    share::scope(|subscribe| {
+        assert_eq!(Buttons::register_listener(&listener, subscribe), Ok(()));
+        assert_eq!(driver.set_pressed(2, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+
+        // The disable is deferred until after the upcall returns.
+        assert!(pending.is_pending());
+        assert!(driver.get_button_state(2).unwrap().interrupt_enabled);
+        assert_eq!(Buttons::apply_pending_disables(&pending), Ok(()));
+        assert!(!pending.is_pending());
+        assert!(!driver.get_button_state(2).unwrap().interrupt_enabled);
+        assert!(driver.get_button_state(3).unwrap().interrupt_enabled);
+
+        assert_eq!(driver.set_pressed(2, false), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+    assert_eq!(presses.get(), 1);
+
+    // Failures are reported after all requested buttons are processed.
+    assert_eq!(pending.request(12), Ok(()));
+    assert_eq!(pending.request(3), Ok(()));
+    assert_eq!(
+        Buttons::apply_pending_disables(&pending),
+        Err(ErrorCode::Invalid)
+    );
+    assert!(!driver.get_button_state(3).unwrap().interrupt_enabled);
+}
+
+#[test]
+fn disable_from_listener() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    // A listener may disable its own button directly from the upcall.
+    let presses: Cell<u32> = Cell::new(0);
+    let listener = ButtonListener(|button, _| {
+        presses.set(presses.get() + 1);
+        assert_eq!(Buttons::disable_interrupts(button), Ok(()));
+    });
+    assert_eq!(Buttons::enable_interrupts(2), Ok(()));
+    share::scope(|subscribe| {
+        assert_eq!(Buttons::register_listener(&listener, subscribe), Ok(()));
+        assert_eq!(driver.set_pressed(2, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert!(!driver.get_button_state(2).unwrap().interrupt_enabled);
+
+        assert_eq!(driver.set_pressed(2, false), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+    assert_eq!(presses.get(), 1);
+}
+
+#[test]
+fn event_queue_order() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    let queue = ButtonEventQueue::<4>::new(OverflowPolicy::DropNewest);
+    assert!(queue.is_empty());
+    let listener = ButtonListener(|button, state| queue.push(button, state));
+    assert_eq!(Buttons::enable_interrupts(0), Ok(()));
+    assert_eq!(Buttons::enable_interrupts(1), Ok(()));
+    share::scope(|subscribe| {
+        assert_eq!(Buttons::register_listener(&listener, subscribe), Ok(()));
+        assert_eq!(driver.set_pressed(0, true), Ok(()));
+        assert_eq!(driver.set_pressed(1, true), Ok(()));
+        assert_eq!(driver.set_pressed(0, false), Ok(()));
+        while fake::Syscalls::yield_no_wait() == YieldNoWaitReturn::Upcall {}
+    });
+    assert_eq!(queue.len(), 3);
+    assert_eq!(queue.pop(), Some((0, ButtonState::Pressed)));
+    assert_eq!(queue.pop(), Some((1, ButtonState::Pressed)));
+    assert_eq!(queue.pop(), Some((0, ButtonState::Released)));
+    assert_eq!(queue.pop(), None);
+    assert_eq!(queue.dropped(), 0);
+}
+
+#[test]
+fn event_queue_overflow() {
+    let queue = ButtonEventQueue::<2>::new(OverflowPolicy::DropNewest);
+    for button in 0..5 {
+        queue.push(button, ButtonState::Pressed);
+    }
+    assert_eq!(queue.dropped(), 3);
+    assert_eq!(queue.pop(), Some((0, ButtonState::Pressed)));
+    assert_eq!(queue.pop(), Some((1, ButtonState::Pressed)));
+    assert_eq!(queue.pop(), None);
+
+    let queue = ButtonEventQueue::<2>::new(OverflowPolicy::DropOldest);
+    for button in 0..5 {
+        queue.push(button, ButtonState::Pressed);
+    }
+    assert_eq!(queue.dropped(), 3);
+    assert_eq!(queue.pop(), Some((3, ButtonState::Pressed)));
+    assert_eq!(queue.pop(), Some((4, ButtonState::Pressed)));
+    assert_eq!(queue.pop(), None);
+
+    let queue = ButtonEventQueue::<0>::new(OverflowPolicy::DropOldest);
+    queue.push(0, ButtonState::Pressed);
+    assert_eq!(queue.dropped(), 1);
+    assert_eq!(queue.pop(), None);
+}
+
+#[test]
+fn event_queue_drain() {
+    let queue = ButtonEventQueue::<3>::new(OverflowPolicy::DropOldest);
+    // Wrap the indices around the end of the buffer.
+    for round in 0..4 {
+        queue.push(round, ButtonState::Pressed);
+        queue.push(round, ButtonState::Released);
+        let mut drained = 0;
+        while let Some((button, _)) = queue.pop() {
+            assert_eq!(button, round);
+            drained += 1;
+        }
+        assert_eq!(drained, 2);
+        assert!(queue.is_empty());
+    }
+    assert_eq!(queue.dropped(), 0);
+}
+
+#[test]
+fn event_queue_drop_flush() {
+    extern crate std;
+    use std::vec::Vec;
+
+    let mut flushed = Vec::new();
+    let queue = ButtonEventQueue::<3>::new(OverflowPolicy::DropOldest);
+    queue.push(0, ButtonState::Pressed);
+    let queue = queue.on_drop_flush(|button, state| flushed.push((button, state)));
+    for button in 1..4 {
+        queue.push(button, ButtonState::Released);
+    }
+    assert_eq!(queue.pop(), Some((1, ButtonState::Released)));
+    queue.push(4, ButtonState::Pressed);
+    assert_eq!(queue.dropped(), 1);
+    drop(queue);
+    assert_eq!(
+        flushed,
+        [
+            (2, ButtonState::Released),
+            (3, ButtonState::Released),
+            (4, ButtonState::Pressed),
+        ]
+    );
+
+    // Without a handler, the remaining events are discarded.
+    let queue = ButtonEventQueue::<2>::new(OverflowPolicy::DropNewest);
+    queue.push(0, ButtonState::Pressed);
+    drop(queue);
+}
+
+#[test]
+fn with_static_callback() {
+    use core::sync::atomic::{AtomicU32, Ordering::Relaxed};
+
+    // The last event, as (button, pressed).
+    static LAST_EVENT: AtomicU32 = AtomicU32::new(u32::MAX);
+    fn record(button: u32, state: ButtonState) {
+        LAST_EVENT.store(
+            button << 1 | (state == ButtonState::Pressed) as u32,
+            Relaxed,
+        );
+    }
+    static mut LISTENER: ButtonListener<fn(u32, ButtonState)> = ButtonListener(record);
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(
+        Buttons::with_static_callback(unsafe { &mut LISTENER }),
+        Ok(())
+    );
+    assert_eq!(Buttons::enable_interrupts(3), Ok(()));
+    // The listener stays registered after the function returns, with no
+    // scope around it.
+    assert!(kernel.has_subscription(crate::DRIVER_NUM, 0));
+    assert_eq!(driver.set_pressed(3, true), Ok(()));
+    fake::Syscalls::yield_no_wait();
+    assert_eq!(LAST_EVENT.load(Relaxed), 3 << 1 | 1);
+
+    Buttons::unregister_listener();
+    assert!(!kernel.has_subscription(crate::DRIVER_NUM, 0));
+    assert_eq!(driver.set_pressed(3, false), Ok(()));
+    assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    assert_eq!(LAST_EVENT.load(Relaxed), 3 << 1 | 1);
+}
+
+#[test]
+fn events_stream() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    let events = AsyncButtonEvents::<4>::new(OverflowPolicy::DropNewest);
+    assert_eq!(Buttons::enable_interrupts(0), Ok(()));
+    assert_eq!(Buttons::enable_interrupts(1), Ok(()));
+    share::scope(|subscribe| {
+        let mut stream = Buttons::events(&events, subscribe).ok().unwrap();
+        assert_eq!(driver.set_pressed(1, true), Ok(()));
+        assert_eq!(
+            executor::block_on(stream.next_event()),
+            Some((1, ButtonState::Pressed))
+        );
+        assert_eq!(driver.set_pressed(0, true), Ok(()));
+        assert_eq!(driver.set_pressed(1, false), Ok(()));
+        assert_eq!(
+            executor::block_on(stream.next_event()),
+            Some((0, ButtonState::Pressed))
+        );
+        assert_eq!(
+            executor::block_on(stream.next_event()),
+            Some((1, ButtonState::Released))
+        );
+
+        // Dropping the stream unsubscribes.
+        core::mem::drop(stream);
+        assert_eq!(driver.set_pressed(0, false), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+    assert_eq!(events.dropped(), 0);
+}
+
+#[test]
+fn teardown_sequence() {
+    use libtock_unittest::SyscallLogEntry;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    let unsubscribe = SyscallLogEntry::Subscribe {
+        driver_num: crate::DRIVER_NUM,
+        subscribe_num: 0,
+    };
+    let events = AsyncButtonEvents::<4>::new(OverflowPolicy::DropNewest);
+    share::scope(|subscribe| {
+        let stream = Buttons::events(&events, subscribe).ok().unwrap();
+        assert_eq!(Buttons::enable_interrupts(0), Ok(()));
+        kernel.take_syscall_log();
+
+        // Dropping the stream clears the callback slot right away, and does
+        // not touch the buttons' interrupts.
+        core::mem::drop(stream);
+        assert_eq!(kernel.take_syscall_log(), [unsubscribe.clone()]);
+        assert!(!kernel.has_subscription(crate::DRIVER_NUM, 0));
+    });
+
+    // The end of the scope clears the slot once more; nothing else is issued.
+    assert_eq!(kernel.take_syscall_log(), [unsubscribe]);
+    assert!(!kernel.has_subscription(crate::DRIVER_NUM, 0));
+}
+
+#[test]
+fn events_leak() {
+    use libtock_unittest::SyscallLogEntry;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    let events = AsyncButtonEvents::<4>::new(OverflowPolicy::DropNewest);
+    assert_eq!(Buttons::enable_interrupts(0), Ok(()));
+    share::scope(|subscribe| {
+        let stream = Buttons::events(&events, subscribe).ok().unwrap();
+        kernel.take_syscall_log();
+
+        // Leaking the stream issues no null subscribe, so events are still
+        // delivered.
+        stream.leak();
+        assert_eq!(kernel.take_syscall_log(), []);
+        assert!(kernel.has_subscription(crate::DRIVER_NUM, 0));
+        assert_eq!(driver.set_pressed(0, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        kernel.take_syscall_log();
+    });
+
+    // The scope still unsubscribes when it ends.
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Subscribe {
+            driver_num: crate::DRIVER_NUM,
+            subscribe_num: 0,
+        }]
+    );
+    assert!(!kernel.has_subscription(crate::DRIVER_NUM, 0));
+}
+
+#[test]
+fn history() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    driver.push_history_event(0, true, 100);
+    driver.push_history_event(0, false, 101);
+    driver.push_history_event(3, true, 105);
+
+    // The buffer only has room for two events, so the third one is left for
+    // the next call.
+    let mut buffer = [0; 2 * HISTORY_EVENT_SIZE];
+    assert_eq!(Buttons::read_history(&mut buffer), Ok(2));
+    assert!(!driver.is_history_enabled());
+    assert!(decode_history(&buffer, 2).eq([
+        ButtonEvent {
+            button: 0,
+            state: ButtonState::Pressed,
+            timestamp: 100
+        },
+        ButtonEvent {
+            button: 0,
+            state: ButtonState::Released,
+            timestamp: 101
+        },
+    ]));
+
+    assert_eq!(Buttons::read_history(&mut buffer), Ok(1));
+    assert!(decode_history(&buffer, 1).eq([ButtonEvent {
+        button: 3,
+        state: ButtonState::Pressed,
+        timestamp: 105
+    }]));
+}
+
+// Runs `f`, which passes malformed kernel data to the crate. It must fail a
+// debug assertion in debug builds, and return normally in release builds, in
+// which case its output is returned for the caller to check.
+fn malformed<R>(f: impl FnOnce() -> R) -> Option<R> {
+    extern crate std;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    assert_eq!(result.is_err(), cfg!(debug_assertions));
+    result.ok()
+}
+
+// Tests that malformed data from the kernel fails a debug assertion, and is
+// handled without panicking in release builds.
+#[test]
+fn malformed_kernel_data() {
+    use libtock_platform::subscribe::OneId;
+    use libtock_platform::Upcall;
+
+    let state: Cell<Option<ButtonState>> = Cell::new(None);
+    let listener = ButtonListener(|_, button_state| state.set(Some(button_state)));
+    malformed(|| Upcall::<OneId<{ crate::DRIVER_NUM }, 0>>::upcall(&listener, 0, 7, 0));
+    #[cfg(not(debug_assertions))]
+    assert_eq!(state.get(), Some(ButtonState::Pressed));
+    malformed(|| {
+        Upcall::<OneId<{ crate::DRIVER_NUM }, 0>>::upcall(&listener, u32::MAX, u32::MAX, 0)
+    });
+    #[cfg(not(debug_assertions))]
+    assert_eq!(state.get(), Some(ButtonState::Pressed));
+
+    // Truncated history buffers and counts larger than the buffer only decode
+    // the complete events.
+    assert_eq!(decode_history(&[0; HISTORY_EVENT_SIZE - 1], 1).count(), 0);
+    assert_eq!(decode_history(&[0; 2 * HISTORY_EVENT_SIZE], 10).count(), 2);
+
+    // A zero-sized event queue drops everything.
+    let queue = ButtonEventQueue::<0>::new(OverflowPolicy::DropNewest);
+    queue.push(0, ButtonState::Pressed);
+    assert_eq!(queue.pop(), None);
+
+    let pending = PendingDisables::new();
+    assert_eq!(pending.request(u32::MAX), Err(ErrorCode::Invalid));
+}
+
+// The exclusive-listener flag is shared by the whole process, so tests that
+// use it must not run concurrently.
+static EXCLUSIVE_LOCK: TestLock = TestLock::new();
+
+#[test]
+fn register_exclusive() {
+    let _lock = EXCLUSIVE_LOCK.lock();
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<2>::new();
+    kernel.add_driver(&driver);
+
+    let calls = Cell::new(0);
+    let listener = ButtonListener(|_, _| calls.set(calls.get() + 1));
+    let result = Buttons::register_exclusive(&listener, || {
+        assert!(kernel.has_subscription(crate::DRIVER_NUM, 0));
+        assert_eq!(
+            Buttons::register_exclusive(&listener, || unreachable!()),
+            Err::<(), _>(ErrorCode::Already)
+        );
+        // The rejected call leaves the first listener registered.
+        assert!(kernel.has_subscription(crate::DRIVER_NUM, 0));
+        assert_eq!(Buttons::enable_interrupts(0), Ok(()));
+        assert_eq!(driver.set_pressed(0, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        calls.get()
+    });
+    assert_eq!(result, Ok(1));
+    assert!(!kernel.has_subscription(crate::DRIVER_NUM, 0));
+
+    // The listener is released once the closure returns.
+    assert_eq!(
+        Buttons::register_exclusive(&listener, || kernel.has_subscription(crate::DRIVER_NUM, 0)),
+        Ok(true)
+    );
+}
+
+#[test]
+fn marker_traits() {
+    use crate::ButtonEvents;
+    use libtock_unittest::{assert_impl, assert_not_impl};
+
+    assert_impl!(Buttons: Send, Sync);
+    assert_not_impl!(ButtonEvents<'static, fake::Syscalls, 4>: Send, Sync);
+}
+
+#[test]
+fn lifecycle_expectations() {
+    use libtock_unittest::command_return;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+    kernel
+        .expect()
+        .command(crate::DRIVER_NUM, crate::BUTTONS_ENABLE_INTERRUPTS.into())
+        .args(1, 0)
+        .subscribe(crate::DRIVER_NUM, 0)
+        .command(crate::DRIVER_NUM, crate::BUTTONS_READ.into())
+        .args(1, 0)
+        .returns(command_return::success_u32(1))
+        .command(crate::DRIVER_NUM, crate::BUTTONS_DISABLE_INTERRUPTS.into())
+        .args(1, 0)
+        .subscribe(crate::DRIVER_NUM, 0);
+
+    let listener = ButtonListener(|_, _| {});
+    assert_eq!(Buttons::enable_interrupts(1), Ok(()));
+    share::scope(|subscribe| {
+        assert_eq!(Buttons::register_listener(&listener, subscribe), Ok(()));
+        // The overridden return reports a press the fake driver never saw.
+        assert_eq!(Buttons::read(1), Ok(ButtonState::Pressed));
+        assert_eq!(Buttons::disable_interrupts(1), Ok(()));
+    });
+    assert_eq!(kernel.take_syscall_log().len(), 5);
+}
+
+#[test]
+fn subscribe_diagnostics() {
+    use crate::SubscribeReturn;
+    use libtock_platform::return_variant;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    let listener = ButtonListener(|_, _| {});
+    let diagnostics = SubscribeDiagnostics::new();
+    assert_eq!(diagnostics.last_subscribe(), None);
+    share::scope(|subscribe| {
+        assert_eq!(
+            Buttons::register_listener_recorded(&listener, subscribe, &diagnostics),
+            Ok(())
+        );
+    });
+    assert_eq!(
+        diagnostics.last_subscribe(),
+        Some(SubscribeReturn {
+            return_variant: return_variant::SUCCESS_2_U32,
+            r1: 0,
+            r2: 0,
+        })
+    );
+
+    // Replacing a registered upcall succeeds with the previous upcall in the
+    // returned registers, rather than zeros.
+    let calls = Cell::new(0);
+    let recorded = ButtonListener(|_, _| calls.set(calls.get() + 1));
+    share::scope(|subscribe| {
+        assert_eq!(Buttons::register_listener(&listener, subscribe), Ok(()));
+        assert_eq!(
+            Buttons::register_listener_recorded(&recorded, subscribe, &diagnostics),
+            Ok(())
+        );
+        let last = diagnostics.last_subscribe().unwrap();
+        assert_eq!(last.return_variant, return_variant::SUCCESS_2_U32);
+        assert_ne!(last.r1, 0);
+        assert_eq!(last.r2, &listener as *const _ as usize);
+
+        // The recorded listener receives events.
+        assert_eq!(Buttons::enable_interrupts(0), Ok(()));
+        assert_eq!(driver.set_pressed(0, true), Ok(()));
+        fake::Syscalls::yield_no_wait();
+        assert_eq!(calls.get(), 1);
+    });
+
+    kernel
+        .expect()
+        .subscribe(crate::DRIVER_NUM, 0)
+        .fails_with(ErrorCode::Reserve);
+    share::scope(|subscribe| {
+        assert_eq!(
+            Buttons::register_listener_recorded(&listener, subscribe, &diagnostics),
+            Err(ErrorCode::Reserve)
+        );
+    });
+    let last = diagnostics.last_subscribe().unwrap();
+    assert_eq!(last.return_variant, return_variant::FAILURE_2_U32);
+    assert_eq!(last.r1, ErrorCode::Reserve as usize);
+}
+
+#[test]
+fn with_suppressed() {
+    use libtock_unittest::SyscallLogEntry;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+    let command =
+        |command_id: libtock_platform::CommandId<{ crate::DRIVER_NUM }>| SyscallLogEntry::Command {
+            driver_id: crate::DRIVER_NUM,
+            command_id: command_id.into(),
+            argument0: 2,
+            argument1: 0,
+        };
+
+    assert_eq!(Buttons::enable_interrupts(2), Ok(()));
+    kernel.take_syscall_log();
+    let result = Buttons::with_suppressed(2, || {
+        assert!(!driver.get_button_state(2).unwrap().interrupt_enabled);
+        Err::<(), _>("long operation failed")
+    });
+    assert_eq!(result, Ok(Err("long operation failed")));
+    assert!(driver.get_button_state(2).unwrap().interrupt_enabled);
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [
+            command(crate::BUTTONS_INTERRUPTS_ENABLED),
+            command(crate::BUTTONS_DISABLE_INTERRUPTS),
+            command(crate::BUTTONS_ENABLE_INTERRUPTS)
+        ]
+    );
+
+    // A button whose interrupts were disabled stays disabled.
+    assert_eq!(Buttons::disable_interrupts(2), Ok(()));
+    assert_eq!(Buttons::with_suppressed(2, || 5), Ok(5));
+    assert!(!driver.get_button_state(2).unwrap().interrupt_enabled);
+    assert_eq!(Buttons::interrupts_enabled(2), Ok(false));
+
+    // A failed disable skips the closure.
+    assert_eq!(
+        Buttons::with_suppressed(11, || unreachable!()),
+        Err::<(), _>(ErrorCode::Invalid)
+    );
+}
+
+#[test]
+fn with_suppressed_restore() {
+    extern crate std;
+    use libtock_unittest::command_return;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+    let query = crate::BUTTONS_INTERRUPTS_ENABLED.into();
+    let disable = crate::BUTTONS_DISABLE_INTERRUPTS.into();
+    let enable = crate::BUTTONS_ENABLE_INTERRUPTS.into();
+
+    // An error restoring the state after the closure returns is reported.
+    assert_eq!(Buttons::enable_interrupts(2), Ok(()));
+    kernel
+        .expect()
+        .command(crate::DRIVER_NUM, query)
+        .args(2, 0)
+        .command(crate::DRIVER_NUM, disable)
+        .args(2, 0)
+        .command(crate::DRIVER_NUM, enable)
+        .args(2, 0)
+        .returns(command_return::failure(ErrorCode::Busy));
+    assert_eq!(Buttons::with_suppressed(2, || 5), Err(ErrorCode::Busy));
+
+    // A driver without the query is assumed to have had interrupts enabled.
+    assert_eq!(Buttons::disable_interrupts(2), Ok(()));
+    kernel
+        .expect()
+        .command(crate::DRIVER_NUM, query)
+        .args(2, 0)
+        .returns(command_return::failure(ErrorCode::NoSupport));
+    assert_eq!(Buttons::with_suppressed(2, || 5), Ok(5));
+    assert!(driver.get_button_state(2).unwrap().interrupt_enabled);
+
+    // If the closure unwinds, the state is still restored.
+    let result = std::panic::catch_unwind(|| {
+        let _ = Buttons::with_suppressed(2, || panic!("long operation panicked"));
+    });
+    assert!(result.is_err());
+    assert!(driver.get_button_state(2).unwrap().interrupt_enabled);
+}
+
+#[test]
+fn handlers() {
+    use libtock_platform::Upcall;
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    let first: Cell<Option<ButtonState>> = Cell::new(None);
+    let second: Cell<Option<ButtonState>> = Cell::new(None);
+    let on_first = |state| first.set(Some(state));
+    let on_second = |state| second.set(Some(state));
+    let handlers = ButtonHandlers([&on_first, &on_second]);
+    share::scope(|subscribe| {
+        assert_eq!(Buttons::register_handlers(&handlers, subscribe), Ok(()));
+        assert_eq!(Buttons::enable_interrupts_range(0..10), Ok(()));
+
+        assert_eq!(driver.set_pressed(1, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(first.take(), None);
+        assert_eq!(second.take(), Some(ButtonState::Pressed));
+
+        // A button without a handler is delivered but ignored.
+        assert_eq!(driver.set_pressed(7, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!((first.take(), second.take()), (None, None));
+
+        // So is a button number a buggy kernel made up.
+        driver.deliver_raw_event(10, true);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        driver.deliver_raw_event(u32::MAX, true);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!((first.take(), second.take()), (None, None));
+    });
+
+    Upcall::<libtock_platform::subscribe::OneId<3, 0>>::upcall(&handlers, 0, 0, 0);
+    assert_eq!(first.take(), Some(ButtonState::Released));
+}
+
+#[test]
+fn log_errors() {
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(Buttons::read(11), Err(ErrorCode::Invalid));
+    #[cfg(feature = "console_log")]
+    assert_eq!(console.take_bytes(), b"buttons: read failed: INVALID\n");
+    #[cfg(not(feature = "console_log"))]
+    assert_eq!(console.take_bytes(), b"");
+}
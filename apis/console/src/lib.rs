@@ -2,6 +2,7 @@
 
 use core::cell::Cell;
 use core::fmt;
+use core::fmt::Write;
 use core::marker::PhantomData;
 use libtock_platform as platform;
 use libtock_platform::allow_ro::AllowRo;
@@ -39,6 +40,12 @@ impl<S: Syscalls, C: Config> Console<S, C> {
     /// This is an alternative to `fmt::Write::write`
     /// because this can actually return an error code.
     pub fn write(s: &[u8]) -> Result<(), ErrorCode> {
+        Self::write_partial(s).map(|_| ())
+    }
+
+    /// Writes bytes, returning how many the driver accepted. The driver may
+    /// accept fewer bytes than `s` holds; use `write_all` to write the rest.
+    pub fn write_partial(s: &[u8]) -> Result<usize, ErrorCode> {
         let called: Cell<Option<(u32,)>> = Cell::new(None);
         share::scope::<
             (
@@ -58,13 +65,35 @@ impl<S: Syscalls, C: Config> Console<S, C> {
 
             loop {
                 S::yield_wait();
-                if let Some((_,)) = called.get() {
-                    return Ok(());
+                if let Some((written,)) = called.get() {
+                    return Ok(written as usize);
                 }
             }
         })
     }
 
+    /// Writes all of `s`, issuing further writes for whatever the driver did
+    /// not accept. Returns how much was written along with the result, so
+    /// that a failure part way through reports how far the write got. A
+    /// driver that accepts no bytes fails the write with `Fail`, rather than
+    /// looping forever.
+    pub fn write_all(s: &[u8]) -> (WriteProgress, Result<(), ErrorCode>) {
+        let mut progress = WriteProgress {
+            written: 0,
+            remaining: s.len(),
+        };
+        while progress.remaining > 0 {
+            let written = match Self::write_partial(&s[progress.written..]) {
+                Ok(0) => return (progress, Err(ErrorCode::Fail)),
+                Ok(written) => written.min(progress.remaining),
+                Err(error) => return (progress, Err(error)),
+            };
+            progress.written += written;
+            progress.remaining -= written;
+        }
+        (progress, Ok(()))
+    }
+
     /// Reads bytes
     /// Reads from the device and writes to `buf`, starting from index 0.
     /// No special guarantees about when the read stops.
@@ -103,11 +132,44 @@ impl<S: Syscalls, C: Config> Console<S, C> {
         (bytes_received, r)
     }
 
+    /// Writes a line describing `error`, for on-device diagnostics. Error codes
+    /// defined by TRD 104 are written by name, while reserved codes (which the
+    /// kernel is not expected to return) are reported as unexpected along with
+    /// their raw value.
+    pub fn write_error(error: ErrorCode) -> Result<(), ErrorCode> {
+        let mut writer = Self::writer();
+        let result = match error.is_reserved() {
+            false => writeln!(writer, "error: {:?}", error),
+            true => writeln!(writer, "unexpected error: {}", error as u32),
+        };
+        result.map_err(|_| ErrorCode::Fail)
+    }
+
     pub fn writer() -> ConsoleWriter<S> {
         ConsoleWriter {
             syscalls: Default::default(),
         }
     }
+
+    /// Returns a writer that collects up to `N` bytes before writing them to
+    /// the console, so that many small writes cost a single write system call
+    /// sequence.
+    pub fn buffered<const N: usize>() -> BufferedConsole<S, N> {
+        BufferedConsole {
+            buffer: [0; N],
+            len: 0,
+            syscalls: PhantomData,
+        }
+    }
+}
+
+/// How much of a buffer `Console::write_all` wrote.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct WriteProgress {
+    /// The number of bytes the driver accepted.
+    pub written: usize,
+    /// The number of bytes that were not written.
+    pub remaining: usize,
 }
 
 pub struct ConsoleWriter<S: Syscalls> {
@@ -120,6 +182,112 @@ impl<S: Syscalls> fmt::Write for ConsoleWriter<S> {
     }
 }
 
+/// A console writer that collects bytes in an `N`-byte buffer, and writes
+/// them to the console when the buffer is full, when `flush` is called, or
+/// when it is dropped. Created by `Console::buffered`.
+///
+/// Errors from writes triggered by a full buffer are returned by the write
+/// that filled it. An error while writing from `Drop` cannot be reported, so
+/// call `flush` before dropping the writer to observe it.
+///
+/// # Example
+/// ```ignore
+/// let mut console = Console::buffered::<64>();
+/// for sample in samples {
+///     writeln!(console, "lux={}", sample)?;
+/// }
+/// console.flush()?;
+/// ```
+pub struct BufferedConsole<S: Syscalls, const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+    syscalls: PhantomData<S>,
+}
+
+impl<S: Syscalls, const N: usize> BufferedConsole<S, N> {
+    /// Adds `bytes` to the buffer, writing the buffer to the console each
+    /// time it fills up.
+    pub fn write(&mut self, mut bytes: &[u8]) -> Result<(), ErrorCode> {
+        if N == 0 {
+            return Console::<S>::write_all(bytes).1;
+        }
+        while !bytes.is_empty() {
+            if self.len == N {
+                self.flush()?;
+            }
+            let count = bytes.len().min(N - self.len);
+            self.buffer[self.len..self.len + count].copy_from_slice(&bytes[..count]);
+            self.len += count;
+            bytes = &bytes[count..];
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered bytes to the console. If the write fails, the
+    /// bytes the driver did not accept stay buffered, and are written by the
+    /// next flush.
+    pub fn flush(&mut self) -> Result<(), ErrorCode> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        let (progress, result) = Console::<S>::write_all(&self.buffer[..self.len]);
+        self.buffer.copy_within(progress.written..self.len, 0);
+        self.len = progress.remaining;
+        result
+    }
+
+    /// The number of bytes waiting to be written.
+    pub fn buffered_len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<S: Syscalls, const N: usize> fmt::Write for BufferedConsole<S, N> {
+    fn write_str(&mut self, s: &str) -> Result<(), fmt::Error> {
+        self.write(s.as_bytes()).map_err(|_e| fmt::Error)
+    }
+}
+
+impl<S: Syscalls, const N: usize> Drop for BufferedConsole<S, N> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Writes a formatted line to the console using the given `Syscalls`
+/// implementation, for on-device diagnostics. Write errors are ignored.
+///
+/// `log!` only writes when the `console_log` feature is enabled; otherwise it
+/// expands to nothing and its arguments are not evaluated.
+///
+/// # Example
+/// ```ignore
+/// libtock_console::log!(TockSyscalls, "intensity: {}", intensity);
+/// ```
+#[cfg(feature = "console_log")]
+#[macro_export]
+macro_rules! log {
+    ($syscalls:ty, $($arg:tt)*) => {{
+        use core::fmt::Write as _;
+        let _ = writeln!($crate::Console::<$syscalls>::writer(), $($arg)*);
+    }};
+}
+
+/// Writes a formatted line to the console using the given `Syscalls`
+/// implementation, for on-device diagnostics. Write errors are ignored.
+///
+/// `log!` only writes when the `console_log` feature is enabled; otherwise it
+/// expands to nothing and its arguments are not evaluated.
+#[cfg(not(feature = "console_log"))]
+#[macro_export]
+macro_rules! log {
+    ($syscalls:ty, $($arg:tt)*) => {{
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
+
 /// System call configuration trait for `Console`.
 pub trait Config:
     platform::allow_ro::Config + platform::allow_rw::Config + platform::subscribe::Config
@@ -137,7 +305,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 1;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::CONSOLE;
 
 // Command IDs
 #[allow(unused)]
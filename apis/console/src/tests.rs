@@ -72,6 +72,143 @@ fn read_bytes_alot() {
     assert_eq!(&buf[..count], b" Alot");
 }
 
+#[test]
+fn write_all_chunked() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+    driver.set_write_chunk(4);
+
+    assert_eq!(Console::write_partial(b"abcdef"), Ok(4));
+    assert_eq!(driver.take_bytes(), b"abcd");
+
+    let (progress, result) = Console::write_all(b"hello, world");
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        progress,
+        WriteProgress {
+            written: 12,
+            remaining: 0
+        }
+    );
+    assert_eq!(driver.take_bytes(), b"hello, world");
+}
+
+#[test]
+fn write_all_error() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+    driver.set_write_chunk(4);
+    kernel
+        .expect()
+        .allow_ro(DRIVER_NUM, allow_ro::WRITE)
+        .subscribe(DRIVER_NUM, subscribe::WRITE)
+        .command(DRIVER_NUM, command::WRITE)
+        .args(10, 0)
+        .yield_wait()
+        // share::scope cleanup
+        .allow_ro(DRIVER_NUM, allow_ro::WRITE)
+        .subscribe(DRIVER_NUM, subscribe::WRITE)
+        // The second write, of the remaining 6 bytes, fails.
+        .allow_ro(DRIVER_NUM, allow_ro::WRITE)
+        .subscribe(DRIVER_NUM, subscribe::WRITE)
+        .command(DRIVER_NUM, command::WRITE)
+        .args(6, 0)
+        .returns(command_return::failure(ErrorCode::Busy));
+
+    let (progress, result) = Console::write_all(b"0123456789");
+    assert_eq!(result, Err(ErrorCode::Busy));
+    assert_eq!(
+        progress,
+        WriteProgress {
+            written: 4,
+            remaining: 6
+        }
+    );
+}
+
+#[test]
+fn buffered() {
+    use libtock_unittest::SyscallLogEntry;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+    // Counts the buffers shared (empty allows unsharing them are not counted)
+    // and the write commands issued since the last call, as (allows, writes).
+    let syscalls = |kernel: &fake::Kernel| {
+        let log = kernel.take_syscall_log();
+        let allows = log
+            .iter()
+            .filter(|entry| matches!(entry, SyscallLogEntry::AllowRo { len, .. } if *len > 0))
+            .count();
+        let writes = log
+            .iter()
+            .filter(|entry| {
+                matches!(entry, SyscallLogEntry::Command { command_id, .. }
+                    if *command_id == command::WRITE)
+            })
+            .count();
+        (allows, writes)
+    };
+
+    let mut console = Console::buffered::<16>();
+    write!(console, "a={}", 1).unwrap();
+    console.write(b" ").unwrap();
+    write!(console, "b={}", 2).unwrap();
+    assert_eq!(console.buffered_len(), 7);
+    assert_eq!(syscalls(&kernel), (0, 0));
+    assert_eq!(console.flush(), Ok(()));
+    assert_eq!(syscalls(&kernel), (1, 1));
+    assert_eq!(driver.take_bytes(), b"a=1 b=2");
+    assert_eq!(console.flush(), Ok(()));
+    assert_eq!(syscalls(&kernel), (0, 0));
+
+    // A full buffer is written out, and dropping writes the rest.
+    console.write(b"0123456789abcdefXYZ").unwrap();
+    assert_eq!(syscalls(&kernel), (1, 1));
+    assert_eq!(driver.take_bytes(), b"0123456789abcdef");
+    drop(console);
+    assert_eq!(syscalls(&kernel), (1, 1));
+    assert_eq!(driver.take_bytes(), b"XYZ");
+}
+
+#[test]
+fn buffered_flush_error() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    let mut console = Console::buffered::<8>();
+    console.write(b"abc").unwrap();
+    kernel
+        .expect()
+        .allow_ro(DRIVER_NUM, allow_ro::WRITE)
+        .subscribe(DRIVER_NUM, subscribe::WRITE)
+        .command(DRIVER_NUM, command::WRITE)
+        .args(3, 0)
+        .returns(command_return::failure(ErrorCode::Busy));
+    assert_eq!(console.flush(), Err(ErrorCode::Busy));
+    assert_eq!(console.buffered_len(), 3);
+    driver.take_bytes();
+
+    // The failed bytes are written by the next flush.
+    assert_eq!(console.flush(), Ok(()));
+    assert_eq!(driver.take_bytes(), b"abc");
+
+    // A failure while writing a full buffer is returned by the write.
+    console.write(b"01234567").unwrap();
+    kernel
+        .expect()
+        .allow_ro(DRIVER_NUM, allow_ro::WRITE)
+        .subscribe(DRIVER_NUM, subscribe::WRITE)
+        .command(DRIVER_NUM, command::WRITE)
+        .args(8, 0)
+        .returns(command_return::failure(ErrorCode::Fail));
+    assert_eq!(console.write(b"8"), Err(ErrorCode::Fail));
+    assert_eq!(console.buffered_len(), 8);
+}
+
 #[test]
 fn failed_print() {
     let kernel = fake::Kernel::new();
@@ -129,3 +266,29 @@ fn failed_read() {
     assert_eq!(res, Err(ErrorCode::Fail));
     assert_eq!(count, 0);
 }
+
+#[test]
+fn write_error() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(Console::write_error(ErrorCode::Busy), Ok(()));
+    assert_eq!(driver.take_bytes(), b"error: BUSY\n");
+
+    assert_eq!(Console::write_error(ErrorCode::N00042), Ok(()));
+    assert_eq!(driver.take_bytes(), b"unexpected error: 42\n");
+}
+
+#[test]
+fn log() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    crate::log!(fake::Syscalls, "intensity: {}", 42);
+    #[cfg(feature = "console_log")]
+    assert_eq!(driver.take_bytes(), b"intensity: 42\n");
+    #[cfg(not(feature = "console_log"))]
+    assert_eq!(driver.take_bytes(), b"");
+}
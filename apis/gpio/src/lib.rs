@@ -1,5 +1,8 @@
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 use core::marker::PhantomData;
 
 use libtock_platform::{
@@ -23,17 +26,32 @@ pub enum GpioState {
     High = 1,
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum PinInterruptEdge {
     Either = 0,
     Rising = 1,
     Falling = 2,
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Error {
     Invalid,
     Failed,
 }
 
+#[cfg(feature = "std")]
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Error::Invalid => "invalid GPIO operation",
+            Error::Failed => "GPIO operation failed",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
 pub trait Pull {
     const MODE: u32;
 }
@@ -69,6 +87,7 @@ impl<S: Syscalls> Gpio<S> {
         Self::disable(pin)?;
         Ok(Pin {
             pin_number: pin,
+            drop_error_handler: ignore_drop_error,
             _syscalls: PhantomData,
         })
     }
@@ -121,10 +140,20 @@ impl From<u32> for GpioState {
 
 pub struct Pin<S: Syscalls> {
     pin_number: u32,
+    drop_error_handler: fn(u32, ErrorCode),
     _syscalls: PhantomData<S>,
 }
 
 impl<S: Syscalls> Pin<S> {
+    /// Sets the function that is called if disabling the pin fails while an
+    /// `OutputPin` or `InputPin` is being dropped. The handler receives the pin
+    /// number and the error returned by the kernel.
+    ///
+    /// By default, errors during teardown are ignored.
+    pub fn set_drop_error_handler(&mut self, handler: fn(u32, ErrorCode)) {
+        self.drop_error_handler = handler;
+    }
+
     pub fn make_output(&mut self) -> Result<OutputPin<S>, ErrorCode> {
         Gpio::<S>::enable_gpio_output(self.pin_number)?;
         Ok(OutputPin { pin: self })
@@ -172,17 +201,69 @@ impl<'a, S: Syscalls, P: Pull> InputPin<'a, S, P> {
     pub fn disable_interrupts(&self) -> Result<(), ErrorCode> {
         Gpio::<S>::disable_interrupts(self.pin.pin_number)
     }
+
+    /// Registers `listener` and enables interrupts on this pin for `edge`.
+    /// Interrupts are disabled again when the returned `PinInterrupt` is
+    /// dropped. The listener stays registered until the end of the
+    /// `share::scope` that `subscribe` belongs to.
+    ///
+    /// All pins share a single listener, so this replaces any listener
+    /// registered for other pins.
+    ///
+    /// ```ignore
+    /// let listener = GpioInterruptListener(|gpio, state| {
+    ///     // make use of the pin number and its new state
+    /// });
+    /// share::scope(|subscribe| {
+    ///     let _interrupt = pin.enable_interrupt(PinInterruptEdge::Rising, &listener, subscribe)?;
+    ///     TockSyscalls::yield_wait();
+    /// });
+    /// ```
+    pub fn enable_interrupt<'share, F: Fn(u32, GpioState)>(
+        &self,
+        edge: PinInterruptEdge,
+        listener: &'share GpioInterruptListener<F>,
+        subscribe: Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+    ) -> Result<PinInterrupt<'a, S>, ErrorCode> {
+        Gpio::<S>::register_listener(listener, subscribe)?;
+        Gpio::<S>::enable_interrupts(self.pin.pin_number, edge)?;
+        Ok(PinInterrupt { pin: self.pin })
+    }
+}
+
+/// Keeps interrupts enabled on a pin, returned by `InputPin::enable_interrupt`.
+/// Interrupts are disabled when it is dropped. Errors while disabling them on
+/// drop are reported to the pin's drop error handler.
+pub struct PinInterrupt<'a, S: Syscalls> {
+    pin: &'a Pin<S>,
+}
+
+impl<S: Syscalls> PinInterrupt<'_, S> {
+    /// Disables interrupts on the pin, returning any error from the kernel.
+    pub fn disable_interrupt(self) -> Result<(), ErrorCode> {
+        let pin_number = self.pin.pin_number;
+        core::mem::forget(self);
+        Gpio::<S>::disable_interrupts(pin_number)
+    }
+}
+
+impl<S: Syscalls> Drop for PinInterrupt<'_, S> {
+    fn drop(&mut self) {
+        if let Err(error) = Gpio::<S>::disable_interrupts(self.pin.pin_number) {
+            (self.pin.drop_error_handler)(self.pin.pin_number, error);
+        }
+    }
 }
 
 impl<S: Syscalls> Drop for OutputPin<'_, S> {
     fn drop(&mut self) {
-        let _ = Gpio::<S>::disable(self.pin.pin_number);
+        self.pin.disable_on_drop();
     }
 }
 
 impl<S: Syscalls, P: Pull> Drop for InputPin<'_, S, P> {
     fn drop(&mut self) {
-        let _ = Gpio::<S>::disable(self.pin.pin_number);
+        self.pin.disable_on_drop();
     }
 }
 
@@ -190,6 +271,17 @@ impl<S: Syscalls, P: Pull> Drop for InputPin<'_, S, P> {
 // Implementation details below
 // -----------------------------------------------------------------------------
 
+// The default drop error handler, which ignores the error.
+fn ignore_drop_error(_pin: u32, _error: ErrorCode) {}
+
+impl<S: Syscalls> Pin<S> {
+    fn disable_on_drop(&self) {
+        if let Err(error) = Gpio::<S>::disable(self.pin_number) {
+            (self.drop_error_handler)(self.pin_number, error);
+        }
+    }
+}
+
 impl<S: Syscalls> Gpio<S> {
     fn enable_gpio_output(pin: u32) -> Result<(), ErrorCode> {
         S::command(DRIVER_NUM, GPIO_ENABLE_OUTPUT, pin, 0).to_result()
@@ -236,7 +328,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 4;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::GPIO;
 
 // Command IDs
 const GPIO_COUNT: u32 = 0;
@@ -294,3 +294,114 @@ fn subscribe() {
     assert_eq!(driver.set_value(0, false), Ok(()));
     assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
 }
+
+// Tests enabling interrupts together with a listener through an input pin.
+#[test]
+fn enable_interrupt() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Gpio::<10>::new();
+    kernel.add_driver(&driver);
+
+    let fired = Cell::<Option<(u32, GpioState)>>::new(None);
+    let listener = GpioInterruptListener(|gpio, state| fired.set(Some((gpio, state))));
+
+    let pin = Gpio::get_pin(3).unwrap();
+    let input_pin = pin.make_input::<PullNone>().unwrap();
+    share::scope(|subscribe| {
+        let interrupt = input_pin
+            .enable_interrupt(PinInterruptEdge::Rising, &listener, subscribe)
+            .unwrap();
+        assert_eq!(
+            driver.get_gpio_state(3).unwrap().interrupt_enabled,
+            Some(InterruptEdge::Rising)
+        );
+
+        // A rising edge fires the callback with the pin number.
+        assert_eq!(driver.set_value(3, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(fired.take(), Some((3, GpioState::High)));
+
+        // A falling edge does not.
+        assert_eq!(driver.set_value(3, false), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+
+        assert_eq!(interrupt.disable_interrupt(), Ok(()));
+        assert_eq!(driver.get_gpio_state(3).unwrap().interrupt_enabled, None);
+        assert_eq!(driver.set_value(3, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+
+    // Dropping the guard disables interrupts as well.
+    assert_eq!(driver.set_value(3, false), Ok(()));
+    share::scope(|subscribe| {
+        let _interrupt = input_pin
+            .enable_interrupt(PinInterruptEdge::Either, &listener, subscribe)
+            .unwrap();
+        assert_eq!(
+            driver.get_gpio_state(3).unwrap().interrupt_enabled,
+            Some(InterruptEdge::Either)
+        );
+    });
+    assert_eq!(driver.get_gpio_state(3).unwrap().interrupt_enabled, None);
+    assert_eq!(fired.get(), None);
+}
+
+// Tests that errors returned while disabling a pin on drop are reported to the
+// pin's drop error handler.
+#[test]
+fn drop_error_handler() {
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use libtock_unittest::{command_return, ExpectedSyscall};
+
+    // Stores the pin number and error code passed to the handler, packed as
+    // (pin << 16) | error_code.
+    static REPORTED: AtomicU32 = AtomicU32::new(0);
+    fn handler(pin: u32, error: ErrorCode) {
+        REPORTED.store((pin << 16) | error as u32, Ordering::Relaxed);
+    }
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Gpio::<10>::new();
+    kernel.add_driver(&driver);
+
+    let mut pin = Gpio::get_pin(2).ok().unwrap();
+    pin.set_drop_error_handler(handler);
+
+    // A successful disable does not call the handler.
+    drop(pin.make_output());
+    assert_eq!(REPORTED.load(Ordering::Relaxed), 0);
+
+    let output_pin = pin.make_output();
+    assert!(output_pin.is_ok());
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: crate::DRIVER_NUM,
+        command_id: crate::GPIO_DISABLE,
+        argument0: 2,
+        argument1: 0,
+        override_return: Some(command_return::failure(ErrorCode::Fail)),
+    });
+    drop(output_pin);
+    assert_eq!(
+        REPORTED.load(Ordering::Relaxed),
+        (2 << 16) | ErrorCode::Fail as u32
+    );
+}
+
+#[test]
+fn comparisons() {
+    assert_eq!(PinInterruptEdge::Rising, PinInterruptEdge::Rising);
+    assert_ne!(PinInterruptEdge::Rising, PinInterruptEdge::Falling);
+    assert_eq!(crate::Error::Invalid, crate::Error::Invalid);
+    assert_ne!(crate::Error::Invalid, crate::Error::Failed);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn std_error() {
+    use std::error::Error;
+    use std::string::ToString;
+
+    assert_eq!(crate::Error::Invalid.to_string(), "invalid GPIO operation");
+    assert_eq!(crate::Error::Failed.to_string(), "GPIO operation failed");
+    assert!(crate::Error::Failed.source().is_none());
+}
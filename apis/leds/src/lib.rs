@@ -44,7 +44,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 2;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::LEDS;
 
 // Command IDs
 const LEDS_COUNT: u32 = 0;
@@ -57,6 +57,7 @@ impl<S: Syscalls> LowLevelDebug<S> {
 }
 
 /// A predefined alert code, for use with [`LowLevelDebug::print_alert_code`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum AlertCode {
     /// Application panic (e.g. `panic!()` called in Rust code).
     Panic = 0x01,
@@ -73,7 +74,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 8;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::LOW_LEVEL_DEBUG;
 
 // Command IDs
 const DRIVER_CHECK: u32 = 0;
@@ -2,7 +2,8 @@
 
 use core::cell::Cell;
 use libtock_platform::{
-    share, subscribe::OneId, DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall,
+    share, subscribe::OneId, Calibration, DefaultConfig, ErrorCode, IdentityCalibration, RawCount,
+    Subscribe, Syscalls, Upcall,
 };
 
 pub struct Temperature<S: Syscalls>(S);
@@ -57,12 +58,62 @@ impl<S: Syscalls> Temperature<S> {
             Some(temp_val) => Ok(temp_val),
         }
     }
+
+    /// Like `read_temperature_sync`, but returns the reading as reported by
+    /// the driver, for use with a `Calibration`.
+    pub fn read_raw() -> Result<RawCount, ErrorCode> {
+        Self::read_temperature_sync().map(RawCount::signed)
+    }
+
+    /// Like `read_temperature_sync`, but returns the reading in tenths of a
+    /// degree, rounded to the nearest tenth.
+    pub fn read_celsius_sync() -> Result<DeciCelsius, ErrorCode> {
+        Self::read_temperature_sync().map(DeciCelsius::from_centi)
+    }
+
+    /// Like `read_temperature_sync`, but passes the reading through
+    /// `calibration` before returning it.
+    pub fn read_temperature_calibrated_sync<C: Calibration>(
+        calibration: &C,
+    ) -> Result<i32, ErrorCode> {
+        Self::read_raw().map(|raw| calibration.calibrate(raw))
+    }
+}
+
+/// A temperature, in tenths of a degree Celsius. The driver reports
+/// hundredths of a degree; `from_centi` converts its readings.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct DeciCelsius(pub i32);
+
+impl DeciCelsius {
+    /// Converts a reading in hundredths of a degree, rounding halves away
+    /// from zero.
+    pub fn from_centi(centi: i32) -> DeciCelsius {
+        let half = if centi < 0 { -5 } else { 5 };
+        DeciCelsius(((centi as i64 + half) / 10) as i32)
+    }
+
+    pub fn as_celsius_f32(self) -> f32 {
+        self.0 as f32 / 10.0
+    }
+}
+
+/// Displays the temperature with one decimal place, e.g. `23.5°C` or
+/// `-1.5°C`.
+impl core::fmt::Display for DeciCelsius {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        // The sign is written separately, as the integer part of e.g. -0.5
+        // is 0.
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        write!(f, "{}{}.{}°C", sign, abs / 10, abs % 10)
+    }
 }
 
 pub struct TemperatureListener<F: Fn(i32)>(pub F);
 impl<F: Fn(i32)> Upcall<OneId<DRIVER_NUM, 0>> for TemperatureListener<F> {
     fn upcall(&self, temp_val: u32, _arg1: u32, _arg2: u32) {
-        self.0(temp_val as i32)
+        self.0(IdentityCalibration.calibrate(RawCount::signed(temp_val as i32)))
     }
 }
 
@@ -73,7 +124,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 0x60000;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::TEMPERATURE;
 
 // Command IDs
 
@@ -79,3 +79,75 @@ fn negative_value() {
     driver.set_value_sync(-1000);
     assert_eq!(Temperature::read_temperature_sync(), Ok(-1000));
 }
+
+#[test]
+fn calibrated() {
+    use libtock_platform::{IdentityCalibration, LinearCalibration};
+    let kernel = fake::Kernel::new();
+    let driver = fake::Temperature::new();
+    kernel.add_driver(&driver);
+
+    driver.set_value_sync(-1000);
+    assert_eq!(
+        Temperature::read_temperature_calibrated_sync(&IdentityCalibration),
+        Ok(-1000)
+    );
+    // Corrects a sensor that reads 1.5 degrees low.
+    let offset = LinearCalibration {
+        scale: 1,
+        offset: 150,
+    };
+    driver.set_value_sync(2000);
+    assert_eq!(
+        Temperature::read_temperature_calibrated_sync(&offset),
+        Ok(2150)
+    );
+}
+
+#[test]
+fn read_raw() {
+    use libtock_platform::RawCount;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Temperature::new();
+    kernel.add_driver(&driver);
+
+    driver.set_value_sync(-1000);
+    let raw = Temperature::read_raw().unwrap();
+    assert_eq!(raw, RawCount::signed(-1000));
+    // Getting a number out of a raw count takes an explicit conversion.
+    assert_eq!(i64::from(raw), -1000);
+}
+
+#[test]
+fn deci_celsius() {
+    extern crate std;
+    use crate::DeciCelsius;
+    use std::string::ToString;
+
+    assert_eq!(DeciCelsius(235).to_string(), "23.5°C");
+    assert_eq!(DeciCelsius(-15).to_string(), "-1.5°C");
+    assert_eq!(DeciCelsius(-5).to_string(), "-0.5°C");
+    assert_eq!(DeciCelsius(0).to_string(), "0.0°C");
+    assert_eq!(DeciCelsius(i32::MIN).to_string(), "-214748364.8°C");
+    assert_eq!(DeciCelsius(-15).as_celsius_f32(), -1.5);
+    assert!(DeciCelsius(-15) < DeciCelsius(-5));
+    assert!(DeciCelsius(-5) < DeciCelsius(235));
+
+    assert_eq!(DeciCelsius::from_centi(2354), DeciCelsius(235));
+    assert_eq!(DeciCelsius::from_centi(2355), DeciCelsius(236));
+    assert_eq!(DeciCelsius::from_centi(-154), DeciCelsius(-15));
+    assert_eq!(DeciCelsius::from_centi(-155), DeciCelsius(-16));
+}
+
+#[test]
+fn read_celsius() {
+    use crate::DeciCelsius;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Temperature::new();
+    kernel.add_driver(&driver);
+
+    driver.set_value_sync(-1500);
+    assert_eq!(Temperature::read_celsius_sync(), Ok(DeciCelsius(-150)));
+    driver.set_value_sync(2350);
+    assert_eq!(Temperature::read_celsius_sync(), Ok(DeciCelsius(235)));
+}
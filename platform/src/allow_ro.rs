@@ -1,4 +1,4 @@
-use crate::share::List;
+use crate::share::{AllowState, List};
 use crate::Syscalls;
 use core::marker::PhantomData;
 
@@ -11,6 +11,34 @@ use core::marker::PhantomData;
 /// before 'share ends. It is intended for use with the `share::scope` function,
 /// which offers a safe interface for constructing `share::Handle<AllowRo>`
 /// instances.
+///
+/// The buffer is immutably borrowed for as long as it is shared, so the process
+/// may keep reading it:
+///
+/// ```
+/// use libtock_platform::{share, AllowRo, DefaultConfig, ErrorCode, Syscalls};
+/// fn share_and_read<S: Syscalls>() -> Result<u8, ErrorCode> {
+///     let buffer = [1, 2, 3];
+///     share::scope::<AllowRo<S, 1, 0>, _, _>(|allow_ro| {
+///         S::allow_ro::<DefaultConfig, 1, 0>(allow_ro, &buffer)?;
+///         Ok(buffer[0])
+///     })
+/// }
+/// ```
+///
+/// but it cannot modify the buffer while the kernel has access to it:
+///
+/// ```compile_fail,E0506
+/// use libtock_platform::{share, AllowRo, DefaultConfig, ErrorCode, Syscalls};
+/// fn share_and_write<S: Syscalls>() -> Result<(), ErrorCode> {
+///     let mut buffer = [1, 2, 3];
+///     share::scope::<AllowRo<S, 1, 0>, _, _>(|allow_ro| {
+///         S::allow_ro::<DefaultConfig, 1, 0>(allow_ro, &buffer)?;
+///         buffer[0] = 4;
+///         Ok(())
+///     })
+/// }
+/// ```
 pub struct AllowRo<'share, S: Syscalls, const DRIVER_NUM: u32, const BUFFER_NUM: u32> {
     _syscalls: PhantomData<S>,
 
@@ -29,6 +57,8 @@ pub struct AllowRo<'share, S: Syscalls, const DRIVER_NUM: u32, const BUFFER_NUM:
     // Sync would allow for races between threads sharing buffers with the
     // kernel.
     _share: PhantomData<core::cell::Cell<&'share [u8]>>,
+
+    pub(crate) state: AllowState,
 }
 
 // We can't derive(Default) because S is not Default, and derive(Default)
@@ -41,6 +71,7 @@ impl<'share, S: Syscalls, const DRIVER_NUM: u32, const BUFFER_NUM: u32> Default
         Self {
             _syscalls: PhantomData,
             _share: PhantomData,
+            state: AllowState::default(),
         }
     }
 }
@@ -49,7 +80,9 @@ impl<'share, S: Syscalls, const DRIVER_NUM: u32, const BUFFER_NUM: u32> Drop
     for AllowRo<'share, S, DRIVER_NUM, BUFFER_NUM>
 {
     fn drop(&mut self) {
-        S::unallow_ro(DRIVER_NUM, BUFFER_NUM);
+        if self.state.must_revoke() {
+            S::unallow_ro(DRIVER_NUM, BUFFER_NUM);
+        }
     }
 }
 
@@ -70,4 +103,16 @@ pub trait Config {
     /// In some applications, this may indicate unexpected reentrance. By
     /// default, the non-zero buffer is ignored.
     fn returned_nonzero_buffer(_driver_num: u32, _buffer_num: u32) {}
+
+    /// Called before `returned_nonzero_buffer`. If this returns true, a
+    /// Read-Only Allow call that would swap out a buffer that is still shared
+    /// (e.g. by another live share for the same buffer number) is undone:
+    /// the previous buffer is shared again and the call returns
+    /// `ErrorCode::Already`. The rejected share does not revoke the Allow ID
+    /// when it is dropped, so the live share keeps its buffer. Callers must
+    /// revoke the existing share before sharing a new buffer. By default,
+    /// swapping is permitted.
+    fn reject_nonzero_buffer(_driver_num: u32, _buffer_num: u32) -> bool {
+        false
+    }
 }
@@ -1,4 +1,4 @@
-use crate::share::List;
+use crate::share::{AllowState, List};
 use crate::Syscalls;
 use core::marker::PhantomData;
 
@@ -11,6 +11,20 @@ use core::marker::PhantomData;
 /// before 'share ends. It is intended for use with the `share::scope` function,
 /// which offers a safe interface for constructing `share::Handle<AllowRw>`
 /// instances.
+///
+/// The buffer is mutably borrowed for as long as it is shared, so the process
+/// cannot read it while the kernel may be writing to it:
+///
+/// ```compile_fail,E0503
+/// use libtock_platform::{share, AllowRw, DefaultConfig, ErrorCode, Syscalls};
+/// fn share_and_read<S: Syscalls>() -> Result<u8, ErrorCode> {
+///     let mut buffer = [1, 2, 3];
+///     share::scope::<AllowRw<S, 1, 0>, _, _>(|allow_rw| {
+///         S::allow_rw::<DefaultConfig, 1, 0>(allow_rw, &mut buffer)?;
+///         Ok(buffer[0])
+///     })
+/// }
+/// ```
 pub struct AllowRw<'share, S: Syscalls, const DRIVER_NUM: u32, const BUFFER_NUM: u32> {
     _syscalls: PhantomData<S>,
 
@@ -29,6 +43,8 @@ pub struct AllowRw<'share, S: Syscalls, const DRIVER_NUM: u32, const BUFFER_NUM:
     // Sync would allow for races between threads sharing buffers with the
     // kernel.
     _share: PhantomData<core::cell::Cell<&'share mut [u8]>>,
+
+    pub(crate) state: AllowState,
 }
 
 // We can't derive(Default) because S is not Default, and derive(Default)
@@ -41,6 +57,7 @@ impl<'share, S: Syscalls, const DRIVER_NUM: u32, const BUFFER_NUM: u32> Default
         Self {
             _syscalls: PhantomData,
             _share: PhantomData,
+            state: AllowState::default(),
         }
     }
 }
@@ -49,7 +66,9 @@ impl<'share, S: Syscalls, const DRIVER_NUM: u32, const BUFFER_NUM: u32> Drop
     for AllowRw<'share, S, DRIVER_NUM, BUFFER_NUM>
 {
     fn drop(&mut self) {
-        S::unallow_rw(DRIVER_NUM, BUFFER_NUM);
+        if self.state.must_revoke() {
+            S::unallow_rw(DRIVER_NUM, BUFFER_NUM);
+        }
     }
 }
 
@@ -70,4 +89,16 @@ pub trait Config {
     /// buffer. In some applications, this may indicate unexpected reentrance.
     /// By default, the non-zero buffer is ignored.
     fn returned_nonzero_buffer(_driver_num: u32, _buffer_num: u32) {}
+
+    /// Called before `returned_nonzero_buffer`. If this returns true, a
+    /// Read-Write Allow call that would swap out a buffer that is still shared
+    /// (e.g. by another live share for the same buffer number) is undone:
+    /// the previous buffer is shared again and the call returns
+    /// `ErrorCode::Already`. The rejected share does not revoke the Allow ID
+    /// when it is dropped, so the live share keeps its buffer. Callers must
+    /// revoke the existing share before sharing a new buffer. By default,
+    /// swapping is permitted.
+    fn reject_nonzero_buffer(_driver_num: u32, _buffer_num: u32) -> bool {
+        false
+    }
 }
@@ -0,0 +1,105 @@
+//! Conversion of raw sensor readings into calibrated values.
+
+/// A raw reading as reported by a sensor driver, before calibration. It has no
+/// physical unit, so it cannot be displayed, or used where a `Percent`, lux or
+/// temperature value is expected:
+///
+/// ```compile_fail,E0308
+/// use libtock_platform::{Percent, RawCount};
+/// let percent: Percent = RawCount::new(42);
+/// ```
+///
+/// ```compile_fail,E0277
+/// use libtock_platform::RawCount;
+/// let text = format!("{}", RawCount::new(42));
+/// ```
+///
+/// Convert it with a `Calibration`, or with `i64::from` or `u32::from` where
+/// the raw value itself is wanted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RawCount(i64);
+
+impl RawCount {
+    /// A reading from a driver that reports unsigned values, such as the
+    /// ambient light driver.
+    pub const fn new(count: u32) -> RawCount {
+        RawCount(count as i64)
+    }
+
+    /// A reading from a driver that reports signed values, such as the
+    /// temperature driver.
+    pub const fn signed(count: i32) -> RawCount {
+        RawCount(count as i64)
+    }
+}
+
+impl From<RawCount> for i64 {
+    fn from(raw: RawCount) -> i64 {
+        raw.0
+    }
+}
+
+/// Returns the 32 bits the driver reported, reinterpreting negative readings
+/// from signed drivers as two's complement.
+impl From<RawCount> for u32 {
+    fn from(raw: RawCount) -> u32 {
+        raw.0 as u32
+    }
+}
+
+/// Converts a raw reading reported by a sensor driver into a calibrated value.
+/// Implemented by `IdentityCalibration` and `LinearCalibration`; applications
+/// can implement it themselves to apply a non-linear correction curve.
+///
+/// `apply` takes the reading widened to `i64`, so that it can represent both
+/// unsigned drivers' readings above `i32::MAX` and signed drivers' negative
+/// readings. The provided calibrations saturate at the bounds of `i32`.
+pub trait Calibration {
+    fn apply(&self, raw: i64) -> i32;
+
+    /// Like `apply`, for a reading returned by a sensor's `read_raw`.
+    fn calibrate(&self, raw: RawCount) -> i32 {
+        self.apply(raw.0)
+    }
+}
+
+/// Passes readings through unchanged, saturating at the bounds of `i32`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IdentityCalibration;
+
+impl Calibration for IdentityCalibration {
+    fn apply(&self, raw: i64) -> i32 {
+        raw.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+}
+
+/// Computes `scale * raw + offset`, saturating at the bounds of `i32`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinearCalibration {
+    pub scale: i32,
+    pub offset: i32,
+}
+
+impl Default for LinearCalibration {
+    fn default() -> Self {
+        LinearCalibration {
+            scale: 1,
+            offset: 0,
+        }
+    }
+}
+
+impl Calibration for LinearCalibration {
+    fn apply(&self, raw: i64) -> i32 {
+        let value = raw
+            .saturating_mul(self.scale as i64)
+            .saturating_add(self.offset as i64);
+        value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+}
+
+impl<C: Calibration + ?Sized> Calibration for &C {
+    fn apply(&self, raw: i64) -> i32 {
+        (**self).apply(raw)
+    }
+}
@@ -0,0 +1,71 @@
+use crate::calibration::{Calibration, IdentityCalibration, LinearCalibration, RawCount};
+
+#[test]
+fn identity() {
+    assert_eq!(IdentityCalibration.apply(0), 0);
+    assert_eq!(IdentityCalibration.apply(450), 450);
+    assert_eq!(IdentityCalibration.apply(-1000), -1000);
+    // Readings outside i32's range saturate rather than wrapping.
+    assert_eq!(IdentityCalibration.apply(u32::MAX as i64), i32::MAX);
+    assert_eq!(
+        IdentityCalibration.calibrate(RawCount::new(u32::MAX)),
+        i32::MAX
+    );
+    assert_eq!(
+        IdentityCalibration.calibrate(RawCount::signed(-1000)),
+        -1000
+    );
+}
+
+#[test]
+fn linear() {
+    let calibration = LinearCalibration {
+        scale: 3,
+        offset: -20,
+    };
+    assert_eq!(calibration.apply(0), -20);
+    assert_eq!(calibration.apply(10), 10);
+    assert_eq!(calibration.apply(-10), -50);
+    assert_eq!(calibration.apply(i32::MAX as i64), i32::MAX);
+    assert_eq!(calibration.apply(i32::MIN as i64), i32::MIN);
+
+    assert_eq!(LinearCalibration::default().apply(1234), 1234);
+}
+
+// A custom correction curve, as an application might use for a sensor with a
+// non-linear response: 2x^2 - x + 5.
+struct Quadratic;
+
+impl Calibration for Quadratic {
+    fn apply(&self, raw: i64) -> i32 {
+        let x = raw as i32;
+        2 * x * x - x + 5
+    }
+}
+
+#[test]
+fn custom() {
+    fn apply_generic<C: Calibration>(calibration: C, raw: i64) -> i32 {
+        calibration.apply(raw)
+    }
+    assert_eq!(Quadratic.apply(0), 5);
+    assert_eq!(Quadratic.apply(3), 20);
+    assert_eq!(apply_generic(&Quadratic, 3), 20);
+    let dynamic: &dyn Calibration = &Quadratic;
+    assert_eq!(apply_generic(dynamic, 3), 20);
+}
+
+#[test]
+fn raw_count() {
+    let raw = RawCount::new(10);
+    assert_eq!(u32::from(raw), 10);
+    assert_eq!(IdentityCalibration.calibrate(raw), 10);
+    let calibration = LinearCalibration {
+        scale: 3,
+        offset: -20,
+    };
+    assert_eq!(calibration.calibrate(raw), calibration.apply(10));
+    assert_eq!(RawCount::default(), RawCount::new(0));
+    assert_eq!(u32::from(RawCount::signed(-1)), u32::MAX);
+    assert_eq!(i64::from(RawCount::new(u32::MAX)), u32::MAX as i64);
+}
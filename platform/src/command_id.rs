@@ -0,0 +1,37 @@
+/// A command number belonging to the driver with number `DRIVER_NUM`.
+///
+/// Drivers declare their command numbers as `CommandId` constants and invoke
+/// them through `Syscalls::command_id`, which takes the driver number from the
+/// type. Passing a command number declared for one driver to another driver's
+/// code is therefore a type error rather than a runtime surprise:
+///
+/// ```compile_fail,E0308
+/// use libtock_platform::{CommandId, CommandReturn, Syscalls};
+///
+/// const BUTTONS_READ: CommandId<3> = CommandId(3);
+///
+/// fn read_light<S: Syscalls>() -> CommandReturn {
+///     // Error: BUTTONS_READ belongs to driver 3, not driver 0x60002.
+///     S::command_id::<0x60002>(BUTTONS_READ, 0, 0)
+/// }
+/// ```
+///
+/// Subscribe numbers do not need a similar wrapper, as `Subscribe` already
+/// carries both the driver number and the subscribe number in its type.
+///
+/// `CommandId` is `#[repr(transparent)]`, so it has the same representation as
+/// a `u32`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct CommandId<const DRIVER_NUM: u32>(pub u32);
+
+impl<const DRIVER_NUM: u32> CommandId<DRIVER_NUM> {
+    /// The number of the driver this command belongs to.
+    pub const DRIVER_NUM: u32 = DRIVER_NUM;
+}
+
+impl<const DRIVER_NUM: u32> From<CommandId<DRIVER_NUM>> for u32 {
+    fn from(command_id: CommandId<DRIVER_NUM>) -> u32 {
+        command_id.0
+    }
+}
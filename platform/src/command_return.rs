@@ -109,6 +109,13 @@ impl CommandReturn {
         self.return_variant == return_variant::SUCCESS
     }
 
+    /// Returns true if this CommandReturn is any of the success types,
+    /// regardless of the data it carries.
+    pub fn is_any_success(&self) -> bool {
+        (return_variant::SUCCESS.into()..=return_variant::SUCCESS_U32_U64.into())
+            .contains(&u32::from(self.return_variant))
+    }
+
     /// Returns true if this CommandReturn is of type Success with u32.
     pub fn is_success_u32(&self) -> bool {
         self.return_variant == return_variant::SUCCESS_U32
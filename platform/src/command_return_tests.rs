@@ -551,3 +551,22 @@ fn success_u32_u64() {
         Ok((1001, 0x0000_1003_0000_1002))
     );
 }
+
+#[test]
+fn any_success() {
+    use return_variant::*;
+    for variant in [FAILURE, FAILURE_U32, FAILURE_2_U32, FAILURE_U64] {
+        assert!(!unsafe { CommandReturn::new(variant, 1, 0, 0) }.is_any_success());
+    }
+    for variant in [
+        SUCCESS,
+        SUCCESS_U32,
+        SUCCESS_2_U32,
+        SUCCESS_U64,
+        SUCCESS_3_U32,
+        SUCCESS_U32_U64,
+    ] {
+        assert!(unsafe { CommandReturn::new(variant, 0, 0, 0) }.is_any_success());
+    }
+    assert!(!unsafe { CommandReturn::new(134.into(), 0, 0, 0) }.is_any_success());
+}
@@ -1,5 +1,19 @@
 //! Defines constants shared between multiple `libtock-rs` crates.
 
+/// The driver numbers of the drivers that have an API crate, used by both the
+/// API crates and libtock_unittest's fake drivers so that they cannot disagree.
+pub mod driver_numbers {
+    pub const ALARM: u32 = 0;
+    pub const CONSOLE: u32 = 1;
+    pub const LEDS: u32 = 2;
+    pub const BUTTONS: u32 = 3;
+    pub const GPIO: u32 = 4;
+    pub const LOW_LEVEL_DEBUG: u32 = 8;
+    pub const TEMPERATURE: u32 = 0x60000;
+    pub const AMBIENT_LIGHT: u32 = 0x60002;
+    pub const PROXIMITY: u32 = 0x60005;
+}
+
 pub mod exit_id {
     pub const TERMINATE: u32 = 0;
     pub const RESTART: u32 = 1;
@@ -0,0 +1,68 @@
+/// Generates the boilerplate shared by drivers: the driver type, an optional
+/// existence check, and a `register_listener`/`unregister_listener` pair for
+/// the listener registered through subscribe number 0.
+///
+/// The listener type must be a wrapper around a closure implementing the given
+/// `Fn` bound, and must implement `Upcall<OneId<DRIVER_NUM, 0>>`. Additional
+/// methods are added in a separate `impl` block as usual.
+///
+/// # Example
+/// ```ignore
+/// libtock_platform::define_driver! {
+///     /// The light driver.
+///     pub struct Light;
+///     driver_num: DRIVER_NUM;
+///     exists: EXISTS;
+///     listener: LightListener, Fn(u32);
+/// }
+/// ```
+/// generates `pub struct Light<S: Syscalls>(S)` with `Light::exists()`,
+/// `Light::register_listener(&LightListener(..), subscribe)` and
+/// `Light::unregister_listener()`.
+#[macro_export]
+macro_rules! define_driver {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident;
+        driver_num: $driver_num:expr;
+        $(exists: $exists:expr;)?
+        listener: $listener:ident, $bound:path;
+    ) => {
+        $(#[$attr])*
+        $vis struct $name<S: $crate::Syscalls>(S);
+
+        impl<S: $crate::Syscalls> $name<S> {
+            $(
+                /// Returns `Ok(())` if the driver is present. This does not
+                /// necessarily mean that the driver is working.
+                pub fn exists() -> Result<(), $crate::ErrorCode> {
+                    S::command($driver_num, $exists, 0, 0).to_result()
+                }
+            )?
+
+            /// Register an events listener
+            ///
+            /// There can be only one single listener registered at a time.
+            /// Each time this function is used, it will replace the
+            /// previously registered listener.
+            pub fn register_listener<'share, F: $bound>(
+                listener: &'share $listener<F>,
+                subscribe: $crate::share::Handle<
+                    $crate::Subscribe<'share, S, { $driver_num }, 0>,
+                >,
+            ) -> Result<(), $crate::ErrorCode> {
+                S::subscribe::<_, _, $crate::DefaultConfig, { $driver_num }, 0>(
+                    subscribe, listener,
+                )
+            }
+
+            /// Unregister the events listener
+            ///
+            /// This function may be used even if there was no
+            /// previously registered listener.
+            pub fn unregister_listener() {
+                S::unsubscribe($driver_num, 0)
+            }
+        }
+    };
+}
@@ -241,8 +241,21 @@ pub enum ErrorCode {
 pub struct NotAnErrorCode;
 
 impl ErrorCode {
+    /// Returns true if this is one of the error codes reserved for future use,
+    /// which the kernel is not expected to return.
+    pub fn is_reserved(self) -> bool {
+        self.name().is_none()
+    }
+
+    /// Returns the name of this error code, such as `"BUSY"`, or `"UNKNOWN"`
+    /// for reserved error codes. Unlike `Debug`, this does not require any
+    /// formatting machinery.
+    pub fn as_str(self) -> &'static str {
+        self.name().unwrap_or("UNKNOWN")
+    }
+
     /// Represent this error code as a string, if defined.
-    fn as_str(self) -> Option<&'static str> {
+    fn name(self) -> Option<&'static str> {
         match self {
             Self::Fail => Some("FAIL"),
             Self::Busy => Some("BUSY"),
@@ -265,7 +278,7 @@ impl ErrorCode {
 
 impl fmt::Debug for ErrorCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.as_str() {
+        match self.name() {
             Some(s) => write!(f, "{}", s),
             None => write!(f, "code {}", *self as u16),
         }
@@ -283,3 +296,26 @@ impl TryFrom<u32> for ErrorCode {
         }
     }
 }
+
+#[cfg(feature = "std")]
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(s) => f.write_str(s),
+            None => write!(f, "reserved error code {}", *self as u16),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ErrorCode {}
+
+#[cfg(feature = "std")]
+impl fmt::Display for NotAnErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("not a TRD 104 error code")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NotAnErrorCode {}
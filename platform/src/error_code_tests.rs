@@ -20,3 +20,46 @@ fn error_code_try_into() {
     }
     assert_eq!(TryInto::<ErrorCode>::try_into(1025u32), Err(NotAnErrorCode));
 }
+
+#[test]
+fn error_code_is_reserved() {
+    assert!(!ErrorCode::Fail.is_reserved());
+    assert!(!ErrorCode::NoAck.is_reserved());
+    assert!(!ErrorCode::BadRVal.is_reserved());
+    assert!(ErrorCode::N00014.is_reserved());
+    assert!(ErrorCode::N01023.is_reserved());
+}
+
+#[test]
+fn error_code_as_str() {
+    assert_eq!(ErrorCode::Fail.as_str(), "FAIL");
+    assert_eq!(ErrorCode::NoMem.as_str(), "NOMEM");
+    assert_eq!(ErrorCode::Busy.as_str(), "BUSY");
+    assert_eq!(ErrorCode::BadRVal.as_str(), "BADRVAL");
+    assert_eq!(ErrorCode::N00014.as_str(), "UNKNOWN");
+    assert_eq!(ErrorCode::N01023.as_str(), "UNKNOWN");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn error_code_std_error() {
+    use std::error::Error;
+
+    assert_eq!(ErrorCode::Busy.to_string(), "BUSY");
+    assert_eq!(ErrorCode::BadRVal.to_string(), "BADRVAL");
+    assert_eq!(ErrorCode::N00014.to_string(), "reserved error code 14");
+    assert_eq!(NotAnErrorCode.to_string(), "not a TRD 104 error code");
+    assert!(ErrorCode::Fail.source().is_none());
+    assert!(NotAnErrorCode.source().is_none());
+
+    // Both compose with `?` in functions returning `Box<dyn Error>`.
+    fn parse(value: u32) -> Result<ErrorCode, Box<dyn Error>> {
+        let error_code: ErrorCode = value.try_into()?;
+        Err(error_code)?
+    }
+    assert_eq!(
+        parse(0).unwrap_err().to_string(),
+        "not a TRD 104 error code"
+    );
+    assert_eq!(parse(4).unwrap_err().to_string(), "OFF");
+}
@@ -1,29 +1,42 @@
 #![cfg_attr(not(test), no_std)]
 #![warn(unsafe_op_in_unsafe_fn)]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 pub mod allow_ro;
 pub mod allow_rw;
+pub mod calibration;
+mod command_id;
 pub mod command_return;
 mod constants;
 mod default_config;
+mod define_driver;
 mod error_code;
 pub mod exit_on_drop;
+mod percent;
 mod raw_syscalls;
 mod register;
 pub mod return_variant;
 pub mod share;
 pub mod subscribe;
+pub mod subscription_guard;
 mod syscalls;
 mod syscalls_impl;
 mod termination;
+pub mod util;
+pub mod wait_any;
 mod yield_types;
 
 pub use allow_ro::AllowRo;
 pub use allow_rw::AllowRw;
+pub use calibration::{Calibration, IdentityCalibration, LinearCalibration, RawCount};
+pub use command_id::CommandId;
 pub use command_return::CommandReturn;
-pub use constants::{exit_id, syscall_class, yield_id};
+pub use constants::{driver_numbers, exit_id, syscall_class, yield_id};
 pub use default_config::DefaultConfig;
 pub use error_code::ErrorCode;
+pub use percent::Percent;
 pub use raw_syscalls::RawSyscalls;
 pub use register::Register;
 pub use return_variant::ReturnVariant;
@@ -32,8 +45,17 @@ pub use syscalls::Syscalls;
 pub use termination::Termination;
 pub use yield_types::YieldNoWaitReturn;
 
+#[cfg(test)]
+mod calibration_tests;
+
 #[cfg(test)]
 mod command_return_tests;
 
 #[cfg(test)]
 mod error_code_tests;
+
+#[cfg(test)]
+mod percent_tests;
+
+#[cfg(test)]
+mod util_tests;
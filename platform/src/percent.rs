@@ -0,0 +1,37 @@
+/// A percentage, from 0 to 100.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Percent(u8);
+
+impl Percent {
+    /// Creates a `Percent`, clamping values above 100 to 100.
+    pub const fn new(value: u8) -> Percent {
+        match value {
+            0..=100 => Percent(value),
+            _ => Percent(100),
+        }
+    }
+
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+}
+
+/// Clamps values above 100 to 100, like `Percent::new`.
+impl From<u8> for Percent {
+    fn from(value: u8) -> Percent {
+        Percent::new(value)
+    }
+}
+
+impl From<Percent> for u8 {
+    fn from(percent: Percent) -> u8 {
+        percent.0
+    }
+}
+
+/// Displays the percentage followed by `%`, e.g. `42%`.
+impl core::fmt::Display for Percent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
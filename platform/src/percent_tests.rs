@@ -0,0 +1,19 @@
+use crate::Percent;
+
+#[test]
+fn clamping() {
+    assert_eq!(Percent::new(0).get(), 0);
+    assert_eq!(Percent::new(42).get(), 42);
+    assert_eq!(Percent::new(100).get(), 100);
+    assert_eq!(Percent::new(101).get(), 100);
+    assert_eq!(Percent::new(u8::MAX).get(), 100);
+    assert_eq!(Percent::from(200), Percent::new(100));
+    assert_eq!(u8::from(Percent::new(42)), 42);
+}
+
+#[test]
+fn display() {
+    assert_eq!(std::format!("{}", Percent::new(42)), "42%");
+    assert_eq!(std::format!("{}", Percent::new(0)), "0%");
+    assert_eq!(std::format!("{}", Percent::new(150)), "100%");
+}
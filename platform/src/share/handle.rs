@@ -1,5 +1,4 @@
 use crate::share::List;
-use core::marker::PhantomData;
 
 /// A `Handle`'s existence indicates two things:
 ///
@@ -10,13 +9,14 @@ use core::marker::PhantomData;
 /// `Handle`s are used to call system calls, and should generally be created by
 /// using `share::scope`.
 pub struct Handle<'handle, L: List> {
-    // Handle acts like a &'handle L, so set its variance accordingly. Note that
-    // the most important lifetime -- the lifetime the share can live for -- is
-    // a parameter of L, not Handle.
+    // Handle is a &'handle L, which gives it the right variance. Note that the
+    // most important lifetime -- the lifetime the share can live for -- is a
+    // parameter of L, not Handle. The reference lets system call
+    // implementations record per-share state in L (see `AllowRw`).
     //
-    // Additionally, _list is a private member, which prevents code outside this
+    // Additionally, list is a private member, which prevents code outside this
     // module from constructing a Handle without calling Handle's constructors.
-    _list: PhantomData<&'handle L>,
+    list: &'handle L,
 }
 
 // We can't #[derive(Clone, Copy)] because derive's implementations of Clone and
@@ -37,20 +37,13 @@ impl<'handle, L: List> Handle<'handle, L> {
     /// The calling code must guarantee that `Drop::drop` is called on `_list`'s
     /// pointee before `_list`'s pointee becomes invalid. In other words,
     /// `_list`'s pointee may not be forgotten or leaked.
-    pub unsafe fn new(_list: &'handle L) -> Self {
-        Handle { _list: PhantomData }
+    pub unsafe fn new(list: &'handle L) -> Self {
+        Handle { list }
     }
 
-    /// Converts this `Handle` to a `Handle` of a different type. Used to create
-    /// a handle to a sub-object of this handle's `L`.
-    ///
-    /// # Safety
-    /// The calling code must guarantee that an `Other` exists, and that
-    /// `Drop::drop` will be called on the `Other` before the `Other` becomes
-    /// invalid. In general, the `Other` should be contained inside the `L`, so
-    /// `Drop::drop` is executed on the `Other` when the `L` is dropped.
-    pub unsafe fn change_type<Other: List>(self) -> Handle<'handle, Other> {
-        Handle { _list: PhantomData }
+    /// Returns the list this handle refers to.
+    pub(crate) fn list(self) -> &'handle L {
+        self.list
     }
 
     /// Splits this `Handle` into a list of handles to sub-lists of `L`. Used
@@ -73,6 +66,6 @@ pub trait SplittableHandle<'handle>: List {
     type SplitHandles;
 
     /// Split the specified handle into sub-handles. Implementations of `split`
-    /// should use `Handle::change_type` to create the sub-handles.
+    /// should use `Handle::new` on the sub-lists to create the sub-handles.
     fn split(handle: Handle<'handle, Self>) -> Self::SplitHandles;
 }
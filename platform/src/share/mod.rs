@@ -6,6 +6,35 @@ mod tuple_impls;
 
 pub use handle::{Handle, SplittableHandle};
 
+use core::cell::Cell;
+
+/// Records what an Allow share has done with its Allow ID, so the share's
+/// destructor can tell whether it needs to revoke it.
+#[derive(Default)]
+pub(crate) struct AllowState {
+    shared: Cell<bool>,
+    rejected: Cell<bool>,
+}
+
+impl AllowState {
+    /// Records that the kernel accepted a buffer from this share.
+    pub(crate) fn set_shared(&self) {
+        self.shared.set(true);
+    }
+
+    /// Records that a call was rejected and the kernel handed the buffer back.
+    pub(crate) fn set_rejected(&self) {
+        self.rejected.set(true);
+    }
+
+    /// Returns false only if every Allow through this share was rejected, in
+    /// which case the buffer in the Allow ID belongs to another share and must
+    /// not be revoked by this one.
+    pub(crate) fn must_revoke(&self) -> bool {
+        self.shared.get() || !self.rejected.get()
+    }
+}
+
 /// Creates a scope in which objects may safely be shared with the kernel.
 pub fn scope<L: List, Output, F: FnOnce(Handle<L>) -> Output>(fcn: F) -> Output {
     let list = Default::default();
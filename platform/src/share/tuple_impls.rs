@@ -23,13 +23,15 @@ macro_rules! tuple_impls {
         impl<'handle, $($name: List + 'handle),*> SplittableHandle<'handle> for ($($name),*,) {
             type SplitHandles = ($(Handle<'handle, $name>),*,);
 
+            #[allow(non_snake_case)]
             fn split(handle: Handle<'handle, Self>) -> Self::SplitHandles {
+                let ($($name),*,) = handle.list();
                 // Safety: handle guarantees that an instance of Self exists and
                 // will be cleaned up before it becomes invalid. Self is a
-                // tuple, and the types we are changing handle into are elements
-                // of that tuple, so when the tuple is cleaned up they will be
+                // tuple, and the handles we are creating refer to elements of
+                // that tuple, so when the tuple is cleaned up they will be
                 // cleaned up as well.
-                ($(unsafe { handle.change_type::<$name>() }),*,)
+                ($(unsafe { Handle::new($name) }),*,)
             }
         }
     }
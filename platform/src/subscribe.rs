@@ -1,5 +1,5 @@
 use crate::share::List;
-use crate::Syscalls;
+use crate::{ReturnVariant, Syscalls};
 
 // -----------------------------------------------------------------------------
 // `Subscribe` struct
@@ -63,6 +63,11 @@ impl<'share, S: Syscalls, const DRIVER_NUM: u32, const SUBSCRIBE_NUM: u32> List
 /// `SupportedIds` parameter. The types `AnySubscribeId` and `OneSubscribeId`
 /// are provided to use as `SupportedIds` parameters in `Upcall`
 /// implementations.
+///
+/// The kernel only invokes upcalls during Yield, so an upcall is never
+/// entered again while it runs unless it yields itself (directly or through a
+/// blocking API). Upcalls that may do so can be wrapped in `NonReentrant` to
+/// keep that single-entry guarantee.
 pub trait Upcall<SupportedIds> {
     fn upcall(&self, arg0: u32, arg1: u32, arg2: u32);
 }
@@ -147,6 +152,84 @@ fn upcall_impls() {
     assert_eq!(cell_three.get(), Some((1, 2, 3)));
 }
 
+// -----------------------------------------------------------------------------
+// `NonReentrant`
+// -----------------------------------------------------------------------------
+
+/// Wraps an `Upcall`, dropping deliveries that arrive while the wrapped upcall
+/// is still running. A nested delivery means the upcall yielded, which usually
+/// indicates a bug, so it also fails a `debug_assert!`.
+pub struct NonReentrant<U> {
+    upcall: U,
+    running: core::cell::Cell<bool>,
+}
+
+impl<U> NonReentrant<U> {
+    pub fn new(upcall: U) -> Self {
+        NonReentrant {
+            upcall,
+            running: core::cell::Cell::new(false),
+        }
+    }
+
+    /// Returns the wrapped upcall.
+    pub fn get_ref(&self) -> &U {
+        &self.upcall
+    }
+}
+
+impl<IDS, U: Upcall<IDS>> Upcall<IDS> for NonReentrant<U> {
+    fn upcall(&self, arg0: u32, arg1: u32, arg2: u32) {
+        if self.running.replace(true) {
+            debug_assert!(false, "nested upcall delivery");
+            return;
+        }
+        // Clears the flag even if the wrapped upcall unwinds.
+        struct Running<'a>(&'a core::cell::Cell<bool>);
+        impl Drop for Running<'_> {
+            fn drop(&mut self) {
+                self.0.set(false);
+            }
+        }
+        let _running = Running(&self.running);
+        self.upcall.upcall(arg0, arg1, arg2);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn non_reentrant() {
+    use core::cell::Cell;
+
+    // Delivers a nested upcall to `nested` from within its upcall.
+    struct Nested<'a> {
+        calls: Cell<u32>,
+        nested: Cell<Option<&'a dyn Upcall<AnyId>>>,
+    }
+    impl Upcall<AnyId> for Nested<'_> {
+        fn upcall(&self, _: u32, _: u32, _: u32) {
+            self.calls.set(self.calls.get() + 1);
+            if let Some(nested) = self.nested.take() {
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    nested.upcall(0, 0, 0)
+                }));
+                assert_eq!(result.is_err(), cfg!(debug_assertions));
+            }
+        }
+    }
+
+    let upcall = NonReentrant::new(Nested {
+        calls: Cell::new(0),
+        nested: Cell::new(None),
+    });
+    upcall.get_ref().nested.set(Some(&upcall));
+    upcall.upcall(0, 0, 0);
+    // The nested delivery was dropped.
+    assert_eq!(upcall.get_ref().calls.get(), 1);
+    upcall.upcall(0, 0, 0);
+    assert_eq!(upcall.get_ref().calls.get(), 2);
+}
+
 // -----------------------------------------------------------------------------
 // `Config` trait
 // -----------------------------------------------------------------------------
@@ -160,3 +243,20 @@ pub trait Config {
     /// the non-null upcall is ignored.
     fn returned_nonnull_upcall(_driver_num: u32, _subscribe_num: u32) {}
 }
+
+// -----------------------------------------------------------------------------
+// `SubscribeReturn`
+// -----------------------------------------------------------------------------
+
+/// The registers returned by a Subscribe system call, as reported by
+/// `Syscalls::subscribe_raw`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SubscribeReturn {
+    pub return_variant: ReturnVariant,
+    /// On success, the previous upcall's function pointer; on failure, the
+    /// error code.
+    pub r1: usize,
+    /// On success, the previous upcall's data; on failure, the upcall function
+    /// pointer that was passed in.
+    pub r2: usize,
+}
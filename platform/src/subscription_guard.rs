@@ -0,0 +1,88 @@
+//! RAII guards for tearing down upcall registrations in a defined order.
+
+/// Unregisters the upcall with ID (`DRIVER_NUM`, `SUBSCRIBE_NUM`) when dropped.
+///
+/// `share::scope` already unregisters its upcalls when the scope ends, in no
+/// particular order. A `SubscriptionGuard` unregisters its upcall when it is
+/// dropped, so that guards can be combined with `AppSubscriptions` to tear
+/// several registrations down in a known order, such as when an app exits
+/// early.
+///
+/// A guard is neither `Send` nor `Sync`: the upcall it unregisters was
+/// registered by the current thread, and may point at data only that thread
+/// can access.
+pub struct SubscriptionGuard<S: crate::Syscalls, const DRIVER_NUM: u32, const SUBSCRIBE_NUM: u32>(
+    core::marker::PhantomData<(S, *const ())>,
+);
+
+impl<S: crate::Syscalls, const DRIVER_NUM: u32, const SUBSCRIBE_NUM: u32> Default
+    for SubscriptionGuard<S, DRIVER_NUM, SUBSCRIBE_NUM>
+{
+    fn default() -> Self {
+        SubscriptionGuard(core::marker::PhantomData)
+    }
+}
+
+impl<S: crate::Syscalls, const DRIVER_NUM: u32, const SUBSCRIBE_NUM: u32>
+    SubscriptionGuard<S, DRIVER_NUM, SUBSCRIBE_NUM>
+{
+    /// Consumes the guard without unregistering the upcall, leaving it
+    /// registered with the kernel, e.g. for an app that is about to exit.
+    ///
+    /// The upcall's data pointer refers to data borrowed by the enclosing
+    /// `share::scope`, so the scope still unregisters the upcall when it ends;
+    /// leaking the guard cannot leave the kernel with a dangling pointer. It
+    /// only skips the unregistration this guard would otherwise do.
+    pub fn leak(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl<S: crate::Syscalls, const DRIVER_NUM: u32, const SUBSCRIBE_NUM: u32> Drop
+    for SubscriptionGuard<S, DRIVER_NUM, SUBSCRIBE_NUM>
+{
+    fn drop(&mut self) {
+        S::unsubscribe(DRIVER_NUM, SUBSCRIBE_NUM);
+    }
+}
+
+/// Owns several guards and drops them in the reverse order they were added in
+/// (last in, first out) when it is dropped.
+///
+/// Any type can be added, so `SubscriptionGuard`s can be mixed with other
+/// guards such as drivers' power or mode guards. Each `add` returns a new
+/// `AppSubscriptions` type, which keeps this allocation-free.
+///
+/// # Example
+/// ```ignore
+/// let subscriptions = AppSubscriptions::new()
+///     .add(SubscriptionGuard::<TockSyscalls, BUTTONS, 0>::default())
+///     .add(SubscriptionGuard::<TockSyscalls, AMBIENT_LIGHT, 0>::default());
+/// // Unregisters the ambient light upcall, then the buttons upcall.
+/// drop(subscriptions);
+/// ```
+pub struct AppSubscriptions<L = ()>(L);
+
+impl AppSubscriptions {
+    pub fn new() -> Self {
+        AppSubscriptions(())
+    }
+}
+
+impl Default for AppSubscriptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L> AppSubscriptions<L> {
+    /// Adds `guard`, which will be dropped before all the guards added so far.
+    // Clippy suggests implementing core::ops::Add instead, but `+` would read
+    // as if the operands were interchangeable, while the order matters here.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<G>(self, guard: G) -> AppSubscriptions<(G, L)> {
+        // Tuple fields are dropped in declaration order, so the newest guard
+        // is dropped first.
+        AppSubscriptions((guard, self.0))
+    }
+}
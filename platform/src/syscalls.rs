@@ -1,6 +1,6 @@
 use crate::{
-    allow_ro, allow_rw, share, subscribe, AllowRo, AllowRw, CommandReturn, ErrorCode, RawSyscalls,
-    Subscribe, Upcall, YieldNoWaitReturn,
+    allow_ro, allow_rw, share, subscribe, AllowRo, AllowRw, CommandId, CommandReturn, ErrorCode,
+    RawSyscalls, Subscribe, Upcall, YieldNoWaitReturn,
 };
 
 /// `Syscalls` provides safe abstractions over Tock's system calls. It is
@@ -20,6 +20,11 @@ pub trait Syscalls: RawSyscalls + Sized {
     /// callback, then returns.
     fn yield_wait();
 
+    /// Runs callbacks with `yield_no_wait` until no callback is pending, and
+    /// returns how many ran. Callbacks that become pending while this runs are
+    /// also run.
+    fn run_all_pending() -> usize;
+
     // -------------------------------------------------------------------------
     // Subscribe
     // -------------------------------------------------------------------------
@@ -37,6 +42,21 @@ pub trait Syscalls: RawSyscalls + Sized {
         upcall: &'share U,
     ) -> Result<(), ErrorCode>;
 
+    /// Like `subscribe`, but also returns the registers the kernel returned,
+    /// whether the call succeeded or not, for diagnosing kernels whose
+    /// Subscribe implementation misbehaves.
+    fn subscribe_raw<
+        'share,
+        IDS: subscribe::SupportsId<DRIVER_NUM, SUBSCRIBE_NUM>,
+        U: Upcall<IDS>,
+        CONFIG: subscribe::Config,
+        const DRIVER_NUM: u32,
+        const SUBSCRIBE_NUM: u32,
+    >(
+        subscribe: share::Handle<Subscribe<'share, Self, DRIVER_NUM, SUBSCRIBE_NUM>>,
+        upcall: &'share U,
+    ) -> (Result<(), ErrorCode>, subscribe::SubscribeReturn);
+
     /// Unregisters the upcall with the given ID. If no upcall is registered
     /// with the given ID, `unsubscribe` does nothing.
     fn unsubscribe(driver_num: u32, subscribe_num: u32);
@@ -47,6 +67,26 @@ pub trait Syscalls: RawSyscalls + Sized {
 
     fn command(driver_id: u32, command_id: u32, argument0: u32, argument1: u32) -> CommandReturn;
 
+    /// Like `command`, but takes the arguments as `i64`, so that arguments
+    /// computed with signed arithmetic (or packed from several fields) are
+    /// checked before they are encoded. In debug builds, it panics if an
+    /// argument is negative or does not fit in the 32 bits a command argument
+    /// carries. Release builds truncate the arguments without checking.
+    fn command_validated(
+        driver_id: u32,
+        command_id: u32,
+        argument0: i64,
+        argument1: i64,
+    ) -> CommandReturn;
+
+    /// Invokes a typed command. The driver number is taken from the type of
+    /// `command_id`, so it cannot be mismatched with the command number.
+    fn command_id<const DRIVER_NUM: u32>(
+        command_id: CommandId<DRIVER_NUM>,
+        argument0: u32,
+        argument1: u32,
+    ) -> CommandReturn;
+
     // -------------------------------------------------------------------------
     // Read-Write Allow
     // -------------------------------------------------------------------------
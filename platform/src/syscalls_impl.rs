@@ -2,8 +2,8 @@
 
 use crate::{
     allow_ro, allow_rw, exit_id, exit_on_drop, return_variant, share, subscribe, syscall_class,
-    yield_id, AllowRo, AllowRw, CommandReturn, ErrorCode, RawSyscalls, Register, ReturnVariant,
-    Subscribe, Syscalls, Upcall, YieldNoWaitReturn,
+    yield_id, AllowRo, AllowRw, CommandId, CommandReturn, ErrorCode, RawSyscalls, Register,
+    ReturnVariant, Subscribe, Syscalls, Upcall, YieldNoWaitReturn,
 };
 
 impl<S: RawSyscalls> Syscalls for S {
@@ -26,6 +26,14 @@ impl<S: RawSyscalls> Syscalls for S {
         }
     }
 
+    fn run_all_pending() -> usize {
+        let mut count = 0;
+        while Self::yield_no_wait() == YieldNoWaitReturn::Upcall {
+            count += 1;
+        }
+        count
+    }
+
     fn yield_wait() {
         // Safety: yield-wait does not return a value, which satisfies yield1's
         // requirement. The yield-wait system call cannot trigger undefined
@@ -47,9 +55,23 @@ impl<S: RawSyscalls> Syscalls for S {
         const DRIVER_NUM: u32,
         const SUBSCRIBE_NUM: u32,
     >(
-        _subscribe: share::Handle<Subscribe<'share, Self, DRIVER_NUM, SUBSCRIBE_NUM>>,
+        subscribe: share::Handle<Subscribe<'share, Self, DRIVER_NUM, SUBSCRIBE_NUM>>,
         upcall: &'share U,
     ) -> Result<(), ErrorCode> {
+        Self::subscribe_raw::<IDS, U, CONFIG, DRIVER_NUM, SUBSCRIBE_NUM>(subscribe, upcall).0
+    }
+
+    fn subscribe_raw<
+        'share,
+        IDS: subscribe::SupportsId<DRIVER_NUM, SUBSCRIBE_NUM>,
+        U: Upcall<IDS>,
+        CONFIG: subscribe::Config,
+        const DRIVER_NUM: u32,
+        const SUBSCRIBE_NUM: u32,
+    >(
+        _subscribe: share::Handle<Subscribe<'share, Self, DRIVER_NUM, SUBSCRIBE_NUM>>,
+        upcall: &'share U,
+    ) -> (Result<(), ErrorCode>, subscribe::SubscribeReturn) {
         // The upcall function passed to the Tock kernel.
         //
         // Safety: data must be a reference to a valid instance of U.
@@ -78,7 +100,7 @@ impl<S: RawSyscalls> Syscalls for S {
             subscribe_num: u32,
             upcall_fcn: Register,
             upcall_data: Register,
-        ) -> Result<(), ErrorCode> {
+        ) -> (Result<(), ErrorCode>, subscribe::SubscribeReturn) {
             // Safety: syscall4's documentation indicates it can be used to call
             // Subscribe. These arguments follow TRD104. kernel_upcall has the
             // required signature. This function's preconditions mean that
@@ -88,7 +110,7 @@ impl<S: RawSyscalls> Syscalls for S {
             // that if this Subscribe succeeds then the upcall will be cleaned
             // up before the 'scope lifetime ends, guaranteeing that upcall is
             // still alive when kernel_upcall is invoked.
-            let [r0, r1, _, _] = unsafe {
+            let [r0, r1, r2, _] = unsafe {
                 S::syscall4::<{ syscall_class::SUBSCRIBE }>([
                     driver_num.into(),
                     subscribe_num.into(),
@@ -98,6 +120,11 @@ impl<S: RawSyscalls> Syscalls for S {
             };
 
             let return_variant: ReturnVariant = r0.as_u32().into();
+            let registers = subscribe::SubscribeReturn {
+                return_variant,
+                r1: r1.into(),
+                r2: r2.into(),
+            };
             // TRD 104 guarantees that Subscribe returns either Success with 2
             // U32 or Failure with 2 U32. We check the return variant by
             // comparing against Failure with 2 U32 for 2 reasons:
@@ -118,7 +145,7 @@ impl<S: RawSyscalls> Syscalls for S {
                 // then r1 will contain a valid error code. ErrorCode is
                 // designed to be safely transmuted directly from a kernel error
                 // code.
-                return Err(unsafe { core::mem::transmute(r1.as_u32()) });
+                return (Err(unsafe { core::mem::transmute(r1.as_u32()) }), registers);
             }
 
             // r0 indicates Success with 2 u32s. Confirm the null upcall was
@@ -131,7 +158,7 @@ impl<S: RawSyscalls> Syscalls for S {
             if returned_upcall != 0usize {
                 CONFIG::returned_nonnull_upcall(driver_num, subscribe_num);
             }
-            Ok(())
+            (Ok(()), registers)
         }
 
         let upcall_fcn = (kernel_upcall::<S, IDS, U> as *const ()).into();
@@ -181,23 +208,52 @@ impl<S: RawSyscalls> Syscalls for S {
         }
     }
 
+    fn command_validated(
+        driver_id: u32,
+        command_id: u32,
+        argument0: i64,
+        argument1: i64,
+    ) -> CommandReturn {
+        for (name, argument) in [("argument0", argument0), ("argument1", argument1)] {
+            debug_assert!(
+                (0..=u32::MAX as i64).contains(&argument),
+                "Command {} of driver {:#x}: {} ({}) does not fit in a u32",
+                command_id,
+                driver_id,
+                name,
+                argument
+            );
+        }
+        Self::command(driver_id, command_id, argument0 as u32, argument1 as u32)
+    }
+
+    fn command_id<const DRIVER_NUM: u32>(
+        command_id: CommandId<DRIVER_NUM>,
+        argument0: u32,
+        argument1: u32,
+    ) -> CommandReturn {
+        Self::command(DRIVER_NUM, command_id.0, argument0, argument1)
+    }
+
     // -------------------------------------------------------------------------
     // Read-Write Allow
     // -------------------------------------------------------------------------
 
     fn allow_rw<'share, CONFIG: allow_rw::Config, const DRIVER_NUM: u32, const BUFFER_NUM: u32>(
-        _allow_rw: share::Handle<AllowRw<'share, Self, DRIVER_NUM, BUFFER_NUM>>,
+        allow_rw: share::Handle<AllowRw<'share, Self, DRIVER_NUM, BUFFER_NUM>>,
         buffer: &'share mut [u8],
     ) -> Result<(), ErrorCode> {
         // Inner function that does the majority of the work. This is not
         // monomorphized over DRIVER_NUM and BUFFER_NUM to keep code size small.
         //
         // Safety: A share::Handle<AllowRw<'share, S, driver_num, buffer_num>>
-        // must exist, and `buffer` must last for at least the 'share lifetime.
+        // must exist, `state` must belong to that share, and `buffer` must last
+        // for at least the 'share lifetime.
         unsafe fn inner<S: Syscalls, CONFIG: allow_rw::Config>(
             driver_num: u32,
             buffer_num: u32,
             buffer: &mut [u8],
+            state: &share::AllowState,
         ) -> Result<(), ErrorCode> {
             // Safety: syscall4's documentation indicates it can be used to call
             // Read-Write Allow. These arguments follow TRD104.
@@ -240,15 +296,43 @@ impl<S: RawSyscalls> Syscalls for S {
             // returned_nozero_buffer is a no-op.
             let returned_buffer: (usize, usize) = (r1.into(), r2.into());
             if returned_buffer != (0, 0) {
+                if CONFIG::reject_nonzero_buffer(driver_num, buffer_num) {
+                    // Swap the previously-shared buffer back in, so the live
+                    // share that owns it keeps it. If the kernel hands
+                    // `buffer` back to us, this share holds nothing and must
+                    // not revoke the Allow ID when it is dropped, or it would
+                    // revoke the other share's buffer. If it somehow does
+                    // not, our handle still revokes it before 'share ends.
+                    // Safety: r1 and r2 describe a buffer that the kernel
+                    // had access to until this call, and whose owner has not
+                    // yet revoked it.
+                    let [r0, r1, r2, _] = unsafe {
+                        S::syscall4::<{ syscall_class::ALLOW_RW }>([
+                            driver_num.into(),
+                            buffer_num.into(),
+                            r1,
+                            r2,
+                        ])
+                    };
+                    let return_variant: ReturnVariant = r0.as_u32().into();
+                    let returned_buffer: (usize, usize) = (r1.into(), r2.into());
+                    if return_variant == return_variant::SUCCESS_2_U32
+                        && returned_buffer == (buffer.as_ptr() as usize, buffer.len())
+                    {
+                        state.set_rejected();
+                    }
+                    return Err(ErrorCode::Already);
+                }
                 CONFIG::returned_nonzero_buffer(driver_num, buffer_num);
             }
+            state.set_shared();
             Ok(())
         }
 
         // Safety: The presence of the share::Handle<AllowRw<'share, ...>>
         // guarantees that an AllowRw exists and will clean up this Allow ID
         // before the 'share lifetime ends.
-        unsafe { inner::<Self, CONFIG>(DRIVER_NUM, BUFFER_NUM, buffer) }
+        unsafe { inner::<Self, CONFIG>(DRIVER_NUM, BUFFER_NUM, buffer, &allow_rw.list().state) }
     }
 
     fn unallow_rw(driver_num: u32, buffer_num: u32) {
@@ -270,7 +354,7 @@ impl<S: RawSyscalls> Syscalls for S {
     // -------------------------------------------------------------------------
 
     fn allow_ro<'share, CONFIG: allow_ro::Config, const DRIVER_NUM: u32, const BUFFER_NUM: u32>(
-        _allow_ro: share::Handle<AllowRo<'share, Self, DRIVER_NUM, BUFFER_NUM>>,
+        allow_ro: share::Handle<AllowRo<'share, Self, DRIVER_NUM, BUFFER_NUM>>,
         buffer: &'share [u8],
     ) -> Result<(), ErrorCode> {
         // Inner function that does the majority of the work. This is not
@@ -285,6 +369,7 @@ impl<S: RawSyscalls> Syscalls for S {
             driver_num: u32,
             buffer_num: u32,
             buffer: &[u8],
+            state: &share::AllowState,
         ) -> Result<(), ErrorCode> {
             // Safety: syscall4's documentation indicates it can be used to call
             // Read-Only Allow. These arguments follow TRD104.
@@ -327,15 +412,43 @@ impl<S: RawSyscalls> Syscalls for S {
             // returned_nozero_buffer is a no-op.
             let returned_buffer: (usize, usize) = (r1.into(), r2.into());
             if returned_buffer != (0, 0) {
+                if CONFIG::reject_nonzero_buffer(driver_num, buffer_num) {
+                    // Swap the previously-shared buffer back in, so the live
+                    // share that owns it keeps it. If the kernel hands
+                    // `buffer` back to us, this share holds nothing and must
+                    // not revoke the Allow ID when it is dropped, or it would
+                    // revoke the other share's buffer. If it somehow does
+                    // not, our handle still revokes it before 'share ends.
+                    // Safety: r1 and r2 describe a buffer that the kernel
+                    // had access to until this call, and whose owner has not
+                    // yet revoked it.
+                    let [r0, r1, r2, _] = unsafe {
+                        S::syscall4::<{ syscall_class::ALLOW_RO }>([
+                            driver_num.into(),
+                            buffer_num.into(),
+                            r1,
+                            r2,
+                        ])
+                    };
+                    let return_variant: ReturnVariant = r0.as_u32().into();
+                    let returned_buffer: (usize, usize) = (r1.into(), r2.into());
+                    if return_variant == return_variant::SUCCESS_2_U32
+                        && returned_buffer == (buffer.as_ptr() as usize, buffer.len())
+                    {
+                        state.set_rejected();
+                    }
+                    return Err(ErrorCode::Already);
+                }
                 CONFIG::returned_nonzero_buffer(driver_num, buffer_num);
             }
+            state.set_shared();
             Ok(())
         }
 
         // Security: The presence of the share::Handle<AllowRo<'share, ...>>
         // guarantees that an AllowRo exists and will clean up this Allow ID
         // before the 'share lifetime ends.
-        inner::<Self, CONFIG>(DRIVER_NUM, BUFFER_NUM, buffer)
+        inner::<Self, CONFIG>(DRIVER_NUM, BUFFER_NUM, buffer, &allow_ro.list().state)
     }
 
     fn unallow_ro(driver_num: u32, buffer_num: u32) {
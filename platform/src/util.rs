@@ -0,0 +1,53 @@
+//! Helpers for decoding data that drivers write into shared buffers.
+
+/// Decodes a buffer of packed little-endian `u16` samples. If `buffer` has an
+/// odd length, the trailing byte is not part of a complete sample and is
+/// skipped; use `U16LeView::remainder` to access it.
+pub fn decode_u16_le(buffer: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    buffer
+        .chunks_exact(2)
+        .map(|sample| u16::from_le_bytes([sample[0], sample[1]]))
+}
+
+/// A view of a buffer of packed little-endian `u16` samples, with bounds
+/// checked access to individual samples.
+#[derive(Clone, Copy, Debug)]
+pub struct U16LeView<'a>(&'a [u8]);
+
+impl<'a> U16LeView<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        U16LeView(buffer)
+    }
+
+    /// The number of complete samples in the buffer.
+    pub fn len(&self) -> usize {
+        self.0.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the sample at `index`, or `None` if the buffer does not contain
+    /// a complete sample at that index.
+    pub fn get(&self, index: usize) -> Option<u16> {
+        let start = index.checked_mul(2)?;
+        match self.0.get(start..start.checked_add(2)?)? {
+            &[low, high] => Some(u16::from_le_bytes([low, high])),
+            _ => None,
+        }
+    }
+
+    /// The trailing byte of an odd-length buffer, which is not part of any
+    /// sample. `None` if the buffer has an even length.
+    pub fn remainder(&self) -> Option<u8> {
+        match self.0.len() % 2 {
+            0 => None,
+            _ => self.0.last().copied(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u16> + 'a {
+        decode_u16_le(self.0)
+    }
+}
@@ -0,0 +1,38 @@
+use crate::util::{decode_u16_le, U16LeView};
+
+#[test]
+fn empty() {
+    assert_eq!(decode_u16_le(&[]).next(), None);
+    let view = U16LeView::new(&[]);
+    assert!(view.is_empty());
+    assert_eq!(view.get(0), None);
+    assert_eq!(view.remainder(), None);
+}
+
+#[test]
+fn odd_length() {
+    let buffer = [0x34, 0x12, 0xff];
+    assert!(decode_u16_le(&buffer).eq([0x1234]));
+    let view = U16LeView::new(&buffer);
+    assert_eq!(view.len(), 1);
+    assert_eq!(view.get(0), Some(0x1234));
+    assert_eq!(view.get(1), None);
+    assert_eq!(view.remainder(), Some(0xff));
+
+    let view = U16LeView::new(&[0xff]);
+    assert!(view.is_empty());
+    assert_eq!(view.get(0), None);
+    assert_eq!(view.remainder(), Some(0xff));
+}
+
+#[test]
+fn multiple_samples() {
+    let buffer = [0x01, 0x00, 0x00, 0x01, 0xff, 0xff];
+    assert!(decode_u16_le(&buffer).eq([0x0001, 0x0100, 0xffff]));
+    let view = U16LeView::new(&buffer);
+    assert_eq!(view.len(), 3);
+    assert!(view.iter().eq([0x0001, 0x0100, 0xffff]));
+    assert_eq!(view.get(2), Some(0xffff));
+    assert_eq!(view.get(usize::MAX), None);
+    assert_eq!(view.remainder(), None);
+}
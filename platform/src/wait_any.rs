@@ -0,0 +1,70 @@
+//! Blocking until the first of several upcalls arrives.
+
+use crate::subscribe::OneId;
+use crate::{Syscalls, Upcall};
+use core::cell::Cell;
+
+/// Identifies an upcall by its driver number and subscribe number.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Event {
+    pub driver_num: u32,
+    pub subscribe_num: u32,
+}
+
+/// An upcall that can be subscribed to any number of upcall IDs, recording
+/// which one fired. Used with `wait_for_any`.
+///
+/// # Example
+/// ```ignore
+/// let first = FirstEvent::new();
+/// share::scope::<(Subscribe<_, BUTTONS, 0>, Subscribe<_, AMBIENT_LIGHT, 0>), _, _>(|handle| {
+///     let (buttons, light) = handle.split();
+///     TockSyscalls::subscribe::<_, _, DefaultConfig, BUTTONS, 0>(buttons, &first)?;
+///     TockSyscalls::subscribe::<_, _, DefaultConfig, AMBIENT_LIGHT, 0>(light, &first)?;
+///     match wait_for_any::<TockSyscalls>(&first, &[BUTTON_EVENT, LIGHT_EVENT]) {
+///         BUTTON_EVENT => { /* a button changed */ }
+///         _ => { /* a light reading arrived */ }
+///     }
+/// });
+/// ```
+#[derive(Default)]
+pub struct FirstEvent {
+    fired: Cell<Option<Event>>,
+}
+
+impl FirstEvent {
+    pub fn new() -> FirstEvent {
+        Self::default()
+    }
+}
+
+impl<const DRIVER_NUM: u32, const SUBSCRIBE_NUM: u32> Upcall<OneId<DRIVER_NUM, SUBSCRIBE_NUM>>
+    for FirstEvent
+{
+    fn upcall(&self, _: u32, _: u32, _: u32) {
+        self.fired.set(Some(Event {
+            driver_num: DRIVER_NUM,
+            subscribe_num: SUBSCRIBE_NUM,
+        }));
+    }
+}
+
+/// Yields until one of `events` fires, and returns it. `first` must be
+/// subscribed to each of `events`; upcalls delivered to `first` for other
+/// IDs are ignored.
+///
+/// Exactly one event is returned per call. `yield_wait` runs a single upcall,
+/// so when several events are pending the one the kernel delivers first is
+/// returned, and the others are returned by later calls. The upcall arguments
+/// are not kept; an app that needs them should read them from the driver
+/// afterwards.
+pub fn wait_for_any<S: Syscalls>(first: &FirstEvent, events: &[Event]) -> Event {
+    loop {
+        if let Some(event) = first.fired.take() {
+            if events.contains(&event) {
+                return event;
+            }
+        }
+        S::yield_wait();
+    }
+}
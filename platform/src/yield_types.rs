@@ -13,3 +13,10 @@ pub enum YieldNoWaitReturn {
     NoUpcall = 0,
     Upcall = 1,
 }
+
+impl From<YieldNoWaitReturn> for bool {
+    /// Returns `true` if an upcall was run.
+    fn from(value: YieldNoWaitReturn) -> bool {
+        value == YieldNoWaitReturn::Upcall
+    }
+}
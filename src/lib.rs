@@ -6,32 +6,86 @@ extern crate libtock_debug_panic;
 pub use libtock_platform as platform;
 pub use libtock_runtime as runtime;
 
+/// Writes a formatted line to the console, for on-device diagnostics. Only
+/// writes when the `console_log` feature is enabled; see
+/// `libtock_console::log`.
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::console::log!($crate::runtime::TockSyscalls, $($arg)*)
+    };
+}
+
 pub mod alarm {
     use libtock_alarm as alarm;
     pub type Alarm = alarm::Alarm<super::runtime::TockSyscalls>;
-    pub use alarm::{Convert, Hz, Milliseconds, Ticks};
+    pub use alarm::{Convert, Hz, Milliseconds, PowerBudget, Ticks, DRIVER_NUM};
+    pub type SamplingScheduler = alarm::SamplingScheduler<super::runtime::TockSyscalls>;
 }
+#[cfg(feature = "ambient_light")]
 pub mod ambient_light {
     use libtock_ambient_light as ambient_light;
     pub type AmbientLight = ambient_light::AmbientLight<super::runtime::TockSyscalls>;
-    pub use ambient_light::IntensityListener;
+    pub use ambient_light::{
+        compute_lux, lux_abs_diff, AsyncReading, AutoRange, Continuous, Decimated, Decimation,
+        IntensityListener, LightChannels, LightRate, Lux, LuxDisplay, Millilux, PoweredUp, Range,
+        RawUpcall, ReadIntensity, ReadingFlags, Subscription, CONTINUOUS_RATES,
+        DEFAULT_RESOLUTION_BITS,
+    };
+    pub type LightController = ambient_light::LightController<super::runtime::TockSyscalls>;
+    pub type RateLimitedReader = ambient_light::RateLimitedReader<super::runtime::TockSyscalls>;
+    pub type SignificantChange = ambient_light::SignificantChange<super::runtime::TockSyscalls>;
+    pub type ThresholdWatch<F> = ambient_light::ThresholdWatch<super::runtime::TockSyscalls, F>;
+    pub use ambient_light::{LightLevel, PendingPolicy, WatchMode};
+    pub type ScheduledReads<F> = ambient_light::ScheduledReads<super::runtime::TockSyscalls, F>;
 }
+#[cfg(feature = "button")]
 pub mod buttons {
     use libtock_buttons as buttons;
     pub type Buttons = buttons::Buttons<super::runtime::TockSyscalls>;
-    pub use buttons::{ButtonListener, ButtonState};
+    pub use buttons::{
+        decode_history, AsyncButtonEvents, ButtonEvent, ButtonEventQueue, ButtonEvents,
+        ButtonHandlers, ButtonListener, ButtonState, ButtonsError, EventMode, NextEvent,
+        OverflowPolicy, PendingDisables, SubscribeDiagnostics, SubscribeReturn, HISTORY_EVENT_SIZE,
+    };
+    pub type ButtonEventSource<F> = buttons::ButtonEventSource<super::runtime::TockSyscalls, F>;
+}
+pub mod capabilities {
+    pub use libtock_board::Capabilities;
+
+    /// Probes each driver with a single existence command.
+    pub fn probe() -> Capabilities {
+        Capabilities::probe_with::<super::runtime::TockSyscalls>()
+    }
 }
 pub mod console {
     use libtock_console as console;
     pub type Console = console::Console<super::runtime::TockSyscalls>;
-    pub use console::ConsoleWriter;
+    pub use console::{log, ConsoleWriter, WriteProgress};
+    pub type BufferedConsole<const N: usize> =
+        console::BufferedConsole<super::runtime::TockSyscalls, N>;
+}
+#[cfg(all(feature = "ambient_light", feature = "temperature"))]
+pub mod environment {
+    pub use libtock_board::Environment;
+    use libtock_platform::ErrorCode;
+
+    /// Reads every environmental sensor that is present; see
+    /// `libtock_board::read_environment_with`.
+    pub fn read_environment() -> Result<Environment, ErrorCode> {
+        let result = libtock_board::read_environment_with::<super::runtime::TockSyscalls>();
+        if let Err(error) = &result {
+            crate::log!("environment: read_environment failed: {:?}", error);
+        }
+        result
+    }
 }
 pub mod gpio {
     use libtock_gpio as gpio;
     pub type Gpio = gpio::Gpio<super::runtime::TockSyscalls>;
     pub use gpio::{
-        Error, GpioInterruptListener, GpioState, InputPin, OutputPin, PinInterruptEdge, Pull,
-        PullDown, PullNone, PullUp,
+        Error, GpioInterruptListener, GpioState, InputPin, OutputPin, PinInterrupt,
+        PinInterruptEdge, Pull, PullDown, PullNone, PullUp,
     };
 }
 pub mod leds {
@@ -43,12 +97,14 @@ pub mod low_level_debug {
     pub type LowLevelDebug = lldb::LowLevelDebug<super::runtime::TockSyscalls>;
     pub use lldb::AlertCode;
 }
+#[cfg(feature = "proximity")]
 pub mod proximity {
     use libtock_proximity as proximity;
     pub type Proximity = proximity::Proximity<super::runtime::TockSyscalls>;
 }
+#[cfg(feature = "temperature")]
 pub mod temperature {
     use libtock_temperature as temperature;
     pub type Temperature = temperature::Temperature<super::runtime::TockSyscalls>;
-    pub use temperature::TemperatureListener;
+    pub use temperature::{DeciCelsius, TemperatureListener};
 }
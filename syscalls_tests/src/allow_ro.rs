@@ -114,3 +114,60 @@ fn allow_ro() {
         }]
     );
 }
+
+struct RejectConfig;
+
+impl allow_ro::Config for RejectConfig {
+    fn reject_nonzero_buffer(_driver_num: u32, _buffer_num: u32) -> bool {
+        true
+    }
+}
+
+#[test]
+fn reject_live_buffer() {
+    let kernel = fake::Kernel::new();
+    let driver = Rc::new(TestDriver::default());
+    kernel.add_driver(&driver);
+    let buffer1 = [1, 2, 3, 4];
+    let buffer2 = [5, 6];
+    share::scope(|outer| {
+        let result = fake::Syscalls::allow_ro::<RejectConfig, 42, 0>(outer, &buffer1);
+        assert_eq!(result, Ok(()));
+        kernel.take_syscall_log();
+        share::scope(|inner| {
+            // buffer1 is still shared through `outer`, so this must fail and
+            // leave buffer1 in place.
+            let result = fake::Syscalls::allow_ro::<RejectConfig, 42, 0>(inner, &buffer2);
+            assert_eq!(result, Err(ErrorCode::Already));
+            assert_eq!(
+                kernel.take_syscall_log(),
+                [
+                    SyscallLogEntry::AllowRo {
+                        driver_num: 42,
+                        buffer_num: 0,
+                        len: 2,
+                    },
+                    SyscallLogEntry::AllowRo {
+                        driver_num: 42,
+                        buffer_num: 0,
+                        len: 4,
+                    },
+                ]
+            );
+        });
+        // The rejected share must not revoke buffer1 when its scope ends.
+        assert_eq!(kernel.take_syscall_log(), []);
+        let buffer = driver.buffer_0.take();
+        assert_eq!(*buffer, buffer1);
+        driver.buffer_0.set(buffer);
+    });
+    // `outer` still owns the Allow ID, so it revokes buffer1.
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::AllowRo {
+            driver_num: 42,
+            buffer_num: 0,
+            len: 0,
+        }]
+    );
+}
@@ -123,3 +123,64 @@ fn allow_rw() {
     // Verify the buffer write occurred.
     assert_eq!(buffer2, [5, 31]);
 }
+
+struct RejectConfig;
+
+impl allow_rw::Config for RejectConfig {
+    fn reject_nonzero_buffer(_driver_num: u32, _buffer_num: u32) -> bool {
+        true
+    }
+}
+
+#[test]
+fn reject_live_buffer() {
+    let kernel = fake::Kernel::new();
+    let driver = Rc::new(TestDriver::default());
+    kernel.add_driver(&driver);
+    let mut buffer1 = [1, 2, 3, 4];
+    let mut buffer2 = [5, 6];
+    share::scope(|outer| {
+        let result = fake::Syscalls::allow_rw::<RejectConfig, 42, 0>(outer, &mut buffer1);
+        assert_eq!(result, Ok(()));
+        kernel.take_syscall_log();
+        share::scope(|inner| {
+            // buffer1 is still shared through `outer`, so this must fail and
+            // leave buffer1 in place.
+            let result = fake::Syscalls::allow_rw::<RejectConfig, 42, 0>(inner, &mut buffer2);
+            assert_eq!(result, Err(ErrorCode::Already));
+            assert_eq!(
+                kernel.take_syscall_log(),
+                [
+                    SyscallLogEntry::AllowRw {
+                        driver_num: 42,
+                        buffer_num: 0,
+                        len: 2,
+                    },
+                    SyscallLogEntry::AllowRw {
+                        driver_num: 42,
+                        buffer_num: 0,
+                        len: 4,
+                    },
+                ]
+            );
+            let buffer = driver.buffer_0.take();
+            assert_eq!(buffer.len(), 4);
+            driver.buffer_0.set(buffer);
+        });
+        // The rejected share must not revoke buffer1 when its scope ends.
+        assert_eq!(kernel.take_syscall_log(), []);
+        let buffer = driver.buffer_0.take();
+        assert_eq!(buffer.len(), 4);
+        driver.buffer_0.set(buffer);
+    });
+    // `outer` still owns the Allow ID, so it revokes buffer1.
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::AllowRw {
+            driver_num: 42,
+            buffer_num: 0,
+            len: 0,
+        }]
+    );
+    assert_eq!(buffer2, [5, 6]);
+}
@@ -1,7 +1,7 @@
 //! Tests for the Command system call implementation in
 //! `libtock_platform::Syscalls`.
 
-use libtock_platform::Syscalls;
+use libtock_platform::{CommandId, Syscalls};
 use libtock_unittest::{command_return, fake, ExpectedSyscall, SyscallLogEntry};
 
 #[test]
@@ -28,3 +28,67 @@ fn command() {
         }]
     );
 }
+
+#[test]
+fn command_id() {
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: 1,
+        command_id: 2,
+        argument0: 3,
+        argument1: 4,
+        override_return: Some(command_return::success_u32(5)),
+    });
+    const COMMAND: CommandId<1> = CommandId(2);
+    assert_eq!(
+        fake::Syscalls::command_id(COMMAND, 3, 4).get_success_u32(),
+        Some(5)
+    );
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Command {
+            driver_id: 1,
+            command_id: 2,
+            argument0: 3,
+            argument1: 4,
+        }]
+    );
+}
+
+#[test]
+fn command_validated() {
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: 1,
+        command_id: 2,
+        argument0: 0,
+        argument1: u32::MAX,
+        override_return: Some(command_return::success()),
+    });
+    assert!(fake::Syscalls::command_validated(1, 2, 0, u32::MAX as i64).is_success());
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Command {
+            driver_id: 1,
+            command_id: 2,
+            argument0: 0,
+            argument1: u32::MAX,
+        }]
+    );
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "argument0 (-1) does not fit in a u32")]
+fn command_validated_negative() {
+    let _kernel = fake::Kernel::new();
+    let _ = fake::Syscalls::command_validated(1, 2, -1, 0);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "argument1 (4294967296) does not fit in a u32")]
+fn command_validated_too_large() {
+    let _kernel = fake::Kernel::new();
+    let _ = fake::Syscalls::command_validated(1, 2, 0, 1 << 32);
+}
@@ -0,0 +1,55 @@
+use core::cell::Cell;
+use libtock_platform::{share, subscribe::OneId, ErrorCode, Syscalls, Upcall};
+use libtock_unittest::fake;
+
+const DRIVER_NUM: u32 = 0x60002;
+const EXISTS: u32 = 0;
+
+pub struct LightListener<F: Fn(u32)>(pub F);
+
+impl<F: Fn(u32)> Upcall<OneId<DRIVER_NUM, 0>> for LightListener<F> {
+    fn upcall(&self, intensity: u32, _arg1: u32, _arg2: u32) {
+        self.0(intensity)
+    }
+}
+
+libtock_platform::define_driver! {
+    /// A driver generated by `define_driver!`, using the fake ambient light
+    /// driver.
+    pub struct Light;
+    driver_num: DRIVER_NUM;
+    exists: EXISTS;
+    listener: LightListener, Fn(u32);
+}
+
+#[test]
+fn exists() {
+    let kernel = fake::Kernel::new();
+    assert_eq!(Light::<fake::Syscalls>::exists(), Err(ErrorCode::NoDevice));
+    kernel.add_driver(&fake::AmbientLight::new());
+    assert_eq!(Light::<fake::Syscalls>::exists(), Ok(()));
+}
+
+#[test]
+fn listener() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::AmbientLight::new();
+    kernel.add_driver(&driver);
+
+    let intensity = Cell::new(None);
+    let listener = LightListener(|value| intensity.set(Some(value)));
+    share::scope(|subscribe| {
+        assert_eq!(
+            Light::<fake::Syscalls>::register_listener(&listener, subscribe),
+            Ok(())
+        );
+        assert!(kernel.has_subscription(DRIVER_NUM, 0));
+        assert!(fake::Syscalls::command(DRIVER_NUM, 1, 0, 0).is_success());
+        driver.set_value(100);
+        fake::Syscalls::yield_wait();
+        assert_eq!(intensity.get(), Some(100));
+
+        Light::<fake::Syscalls>::unregister_listener();
+        assert!(!kernel.has_subscription(DRIVER_NUM, 0));
+    });
+}
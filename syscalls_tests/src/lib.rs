@@ -19,6 +19,9 @@ mod allow_rw;
 #[cfg(test)]
 mod command_tests;
 
+#[cfg(test)]
+mod define_driver;
+
 #[cfg(test)]
 mod exit_on_drop;
 
@@ -29,5 +32,11 @@ mod exit_on_drop;
 #[cfg(test)]
 mod subscribe_tests;
 
+#[cfg(test)]
+mod subscription_guard;
+
+#[cfg(test)]
+mod wait_any;
+
 #[cfg(test)]
 mod yield_tests;
@@ -104,6 +104,49 @@ fn success() {
     assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
 }
 
+#[test]
+fn raw() {
+    use libtock_platform::return_variant;
+
+    std::thread_local! {static CALLED: core::cell::Cell<bool> = Default::default(); }
+    struct TestConfig;
+    impl subscribe::Config for TestConfig {
+        fn returned_nonnull_upcall(_: u32, _: u32) {
+            CALLED.with(|cell| cell.set(true));
+        }
+    }
+
+    let kernel = fake::Kernel::new();
+    let first = core::cell::Cell::new(false);
+    let second = core::cell::Cell::new(false);
+    share::scope(|subscribe| {
+        let (result, registers) =
+            fake::Syscalls::subscribe_raw::<_, _, TestConfig, 1, 0>(subscribe, &first);
+        assert_eq!(result, Err(ErrorCode::NoDevice));
+        assert_eq!(registers.return_variant, return_variant::FAILURE_2_U32);
+        assert_eq!(registers.r1, ErrorCode::NoDevice as usize);
+    });
+
+    kernel.add_driver(&Rc::new(MockDriver::default()));
+    share::scope(|subscribe| {
+        let (result, registers) =
+            fake::Syscalls::subscribe_raw::<_, _, TestConfig, 1, 0>(subscribe, &first);
+        assert_eq!(result, Ok(()));
+        assert_eq!((registers.r1, registers.r2), (0, 0));
+        assert!(!CALLED.with(|cell| cell.get()));
+
+        // Replacing the upcall returns the previous one and still goes through
+        // the configured returned_nonnull_upcall.
+        let (result, registers) =
+            fake::Syscalls::subscribe_raw::<_, _, TestConfig, 1, 0>(subscribe, &second);
+        assert_eq!(result, Ok(()));
+        assert_eq!(registers.return_variant, return_variant::SUCCESS_2_U32);
+        assert_ne!(registers.r1, 0);
+        assert_eq!(registers.r2, &first as *const _ as usize);
+        assert!(CALLED.with(|cell| cell.get()));
+    });
+}
+
 #[cfg(not(miri))]
 #[test]
 fn unwinding_upcall() {
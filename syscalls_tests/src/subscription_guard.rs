@@ -0,0 +1,99 @@
+use core::cell::Cell;
+use libtock_platform::subscription_guard::{AppSubscriptions, SubscriptionGuard};
+use libtock_platform::{share, DefaultConfig, Subscribe, Syscalls};
+use libtock_unittest::{fake, SyscallLogEntry};
+
+#[test]
+fn lifo_teardown() {
+    const ALARM: u32 = 0x0;
+    const BUTTONS: u32 = 0x3;
+    const AMBIENT_LIGHT: u32 = 0x60002;
+
+    let kernel = fake::Kernel::new();
+    kernel.add_driver(&fake::Alarm::new(1000));
+    kernel.add_driver(&fake::Buttons::<2>::new());
+    kernel.add_driver(&fake::AmbientLight::new());
+
+    let buttons_called: Cell<Option<(u32, u32)>> = Cell::new(None);
+    let light_called: Cell<Option<(u32,)>> = Cell::new(None);
+    let alarm_called: Cell<Option<(u32, u32)>> = Cell::new(None);
+    share::scope::<
+        (
+            Subscribe<_, BUTTONS, 0>,
+            Subscribe<_, AMBIENT_LIGHT, 0>,
+            Subscribe<_, ALARM, 0>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (buttons, light, alarm) = handle.split();
+        let subscriptions = AppSubscriptions::new();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, BUTTONS, 0>(buttons, &buttons_called)
+            .unwrap();
+        let subscriptions =
+            subscriptions.add(SubscriptionGuard::<fake::Syscalls, BUTTONS, 0>::default());
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, AMBIENT_LIGHT, 0>(light, &light_called)
+            .unwrap();
+        let subscriptions =
+            subscriptions.add(SubscriptionGuard::<fake::Syscalls, AMBIENT_LIGHT, 0>::default());
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, ALARM, 0>(alarm, &alarm_called).unwrap();
+        let subscriptions =
+            subscriptions.add(SubscriptionGuard::<fake::Syscalls, ALARM, 0>::default());
+        kernel.take_syscall_log();
+
+        drop(subscriptions);
+        assert!(!kernel.has_subscription(BUTTONS, 0));
+        assert!(!kernel.has_subscription(AMBIENT_LIGHT, 0));
+        assert!(!kernel.has_subscription(ALARM, 0));
+        assert_eq!(
+            kernel.take_syscall_log(),
+            [
+                SyscallLogEntry::Subscribe {
+                    driver_num: ALARM,
+                    subscribe_num: 0,
+                },
+                SyscallLogEntry::Subscribe {
+                    driver_num: AMBIENT_LIGHT,
+                    subscribe_num: 0,
+                },
+                SyscallLogEntry::Subscribe {
+                    driver_num: BUTTONS,
+                    subscribe_num: 0,
+                },
+            ]
+        );
+    });
+}
+
+#[test]
+fn leak() {
+    const BUTTONS: u32 = 0x3;
+
+    let kernel = fake::Kernel::new();
+    kernel.add_driver(&fake::Buttons::<2>::new());
+
+    let called: Cell<Option<(u32, u32)>> = Cell::new(None);
+    share::scope(|subscribe| {
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, BUTTONS, 0>(subscribe, &called).unwrap();
+        let guard = SubscriptionGuard::<fake::Syscalls, BUTTONS, 0>::default();
+        kernel.take_syscall_log();
+
+        guard.leak();
+        kernel.assert_no_syscalls();
+        assert!(kernel.has_subscription(BUTTONS, 0));
+    });
+    // The scope still unregisters the upcall.
+    assert!(!kernel.has_subscription(BUTTONS, 0));
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Subscribe {
+            driver_num: BUTTONS,
+            subscribe_num: 0,
+        }]
+    );
+}
+
+#[test]
+fn not_send() {
+    libtock_unittest::assert_not_impl!(SubscriptionGuard<fake::Syscalls, 0x3, 0>: Send, Sync);
+}
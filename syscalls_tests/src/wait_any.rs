@@ -0,0 +1,72 @@
+use libtock_platform::wait_any::{wait_for_any, Event, FirstEvent};
+use libtock_platform::{share, DefaultConfig, ErrorCode, Subscribe, Syscalls};
+use libtock_unittest::fake;
+
+const BUTTONS: u32 = 0x3;
+const AMBIENT_LIGHT: u32 = 0x60002;
+
+const BUTTON_EVENT: Event = Event {
+    driver_num: BUTTONS,
+    subscribe_num: 0,
+};
+const LIGHT_EVENT: Event = Event {
+    driver_num: AMBIENT_LIGHT,
+    subscribe_num: 0,
+};
+
+#[test]
+fn first_event_wins() {
+    let kernel = fake::Kernel::new();
+    let buttons = fake::Buttons::<2>::new();
+    kernel.add_driver(&buttons);
+    let light = fake::AmbientLight::new();
+    kernel.add_driver(&light);
+
+    let first = FirstEvent::new();
+    share::scope::<(Subscribe<_, BUTTONS, 0>, Subscribe<_, AMBIENT_LIGHT, 0>), _, _>(|handle| {
+        let (buttons_handle, light_handle) = handle.split();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, BUTTONS, 0>(buttons_handle, &first)
+            .unwrap();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, AMBIENT_LIGHT, 0>(light_handle, &first)
+            .unwrap();
+        let events = [LIGHT_EVENT, BUTTON_EVENT];
+
+        // Start a light reading, which stays pending while a button is
+        // pressed.
+        fake::Syscalls::command(AMBIENT_LIGHT, 1, 0, 0)
+            .to_result::<(), ErrorCode>()
+            .unwrap();
+        fake::Syscalls::command(BUTTONS, 1, 0, 0)
+            .to_result::<(), ErrorCode>()
+            .unwrap();
+        buttons.set_pressed(0, true).unwrap();
+        assert_eq!(
+            wait_for_any::<fake::Syscalls>(&first, &events),
+            BUTTON_EVENT
+        );
+
+        // Each call returns a single event.
+        light.set_value(450);
+        buttons.set_pressed(0, false).unwrap();
+        assert_eq!(wait_for_any::<fake::Syscalls>(&first, &events), LIGHT_EVENT);
+        assert_eq!(
+            wait_for_any::<fake::Syscalls>(&first, &events),
+            BUTTON_EVENT
+        );
+
+        // Events that were not asked for are skipped.
+        buttons.set_pressed(0, true).unwrap();
+        fake::Syscalls::command(AMBIENT_LIGHT, 1, 0, 0)
+            .to_result::<(), ErrorCode>()
+            .unwrap();
+        light.set_value(300);
+        assert_eq!(
+            wait_for_any::<fake::Syscalls>(&first, &[LIGHT_EVENT]),
+            LIGHT_EVENT
+        );
+        assert_eq!(
+            fake::Syscalls::yield_no_wait(),
+            libtock_platform::YieldNoWaitReturn::NoUpcall
+        );
+    });
+}
@@ -28,6 +28,21 @@ fn no_wait_no_upcall() {
     assert_eq!(kernel.take_syscall_log(), [SyscallLogEntry::YieldNoWait]);
 }
 
+// Tests the conversion of both yield_no_wait outcomes into whether an upcall
+// ran.
+#[test]
+fn no_wait_into_bool() {
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::YieldNoWait {
+        override_return: Some(YieldNoWaitReturn::Upcall),
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::YieldNoWait {
+        override_return: Some(YieldNoWaitReturn::NoUpcall),
+    });
+    assert!(bool::from(fake::Syscalls::yield_no_wait()));
+    assert!(!bool::from(fake::Syscalls::yield_no_wait()));
+}
+
 // Tests yield_wait.
 #[test]
 fn wait() {
@@ -36,3 +51,50 @@ fn wait() {
     fake::Syscalls::yield_wait();
     assert_eq!(kernel.take_syscall_log(), [SyscallLogEntry::YieldWait]);
 }
+
+// Tests that run_all_pending runs every queued upcall and counts them.
+#[test]
+fn run_all_pending() {
+    use libtock_platform::{share, subscribe, CommandReturn, DefaultConfig, ErrorCode, Upcall};
+    use libtock_unittest::{command_return, DriverInfo, DriverShareRef};
+
+    #[derive(Default)]
+    struct MockDriver {
+        share_ref: DriverShareRef,
+    }
+    impl fake::SyscallDriver for MockDriver {
+        fn info(&self) -> DriverInfo {
+            DriverInfo::new(1).upcall_count(1)
+        }
+        fn register(&self, share_ref: DriverShareRef) {
+            self.share_ref.replace(share_ref);
+        }
+        fn command(&self, _: u32, _: u32, _: u32) -> CommandReturn {
+            command_return::failure(ErrorCode::NoSupport)
+        }
+    }
+
+    struct Counter(core::cell::Cell<u32>);
+    impl Upcall<subscribe::AnyId> for Counter {
+        fn upcall(&self, _: u32, _: u32, _: u32) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let kernel = fake::Kernel::new();
+    let driver = std::rc::Rc::new(MockDriver::default());
+    kernel.add_driver(&driver);
+    let counter = Counter(Default::default());
+    share::scope(|subscribe| {
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, 1, 0>(subscribe, &counter).unwrap();
+        for _ in 0..3 {
+            driver.share_ref.schedule_upcall(0, (0, 0, 0)).unwrap();
+        }
+        kernel.take_syscall_log();
+        assert_eq!(fake::Syscalls::run_all_pending(), 3);
+        assert_eq!(counter.0.get(), 3);
+        // The final yield found nothing to run.
+        assert_eq!(kernel.take_syscall_log().len(), 4);
+        assert_eq!(fake::Syscalls::run_all_pending(), 0);
+    });
+}
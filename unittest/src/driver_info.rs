@@ -22,6 +22,11 @@ impl DriverInfo {
         }
     }
 
+    /// Returns the driver number the driver registers with the kernel.
+    pub fn driver_num(&self) -> u32 {
+        self.driver_num
+    }
+
     /// Sets `upcall_count` and returns `self`. Used similar to a builder.
     ///
     /// # Example
@@ -0,0 +1,50 @@
+//! A minimal executor for testing futures against the fake kernel. Futures are
+//! woken from upcalls, so the executor yields to the fake kernel while the
+//! future is pending.
+
+use crate::fake;
+use libtock_platform::Syscalls;
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+std::thread_local! {static WOKEN: Cell<bool> = Cell::new(false); }
+
+const VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+fn clone(_: *const ()) -> RawWaker {
+    RawWaker::new(std::ptr::null(), &VTABLE)
+}
+fn wake(_: *const ()) {
+    WOKEN.with(|woken| woken.set(true));
+}
+fn drop(_: *const ()) {}
+
+/// Polls `future` once. Whether it was woken afterwards can be checked with
+/// `take_woken`.
+pub fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+    // Safety: The waker's data pointer is unused, and VTABLE's functions do
+    // not access any data, so they uphold RawWaker's contract.
+    let waker = unsafe { Waker::from_raw(clone(std::ptr::null())) };
+    Pin::new(future).poll(&mut Context::from_waker(&waker))
+}
+
+/// Returns whether a future polled by this executor has been woken since the
+/// last call, and clears the flag.
+pub fn take_woken() -> bool {
+    WOKEN.with(|woken| woken.replace(false))
+}
+
+/// Polls `future` until it completes, calling `fake::Syscalls::yield_wait`
+/// until the future is woken whenever it is pending.
+pub fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+    loop {
+        take_woken();
+        if let Poll::Ready(output) = poll(&mut future) {
+            return output;
+        }
+        while !take_woken() {
+            fake::Syscalls::yield_wait();
+        }
+    }
+}
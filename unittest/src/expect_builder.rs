@@ -0,0 +1,204 @@
+use crate::fake::Kernel;
+use crate::ExpectedSyscall;
+use libtock_platform::{CommandReturn, ErrorCode, YieldNoWaitReturn};
+
+/// Adds a sequence of `ExpectedSyscall`s to a `fake::Kernel`'s expected
+/// syscall queue, created by `fake::Kernel::expect`. Each method adds one
+/// expected system call with default settings (zero command arguments, no
+/// overridden return values), which the modifier methods (`args`, `returns`,
+/// `fails_with`, `skip_upcall`) change for the most recently added call.
+///
+/// Each call is added to the queue as soon as it is described, so the result
+/// is the same as calling `add_expected_syscall` for each of them, even if
+/// the builder is kept in a `let` binding. The modifiers change the queue's
+/// last entry, so they must be used before a system call consumes it.
+///
+/// # Example
+/// ```ignore
+/// kernel
+///     .expect()
+///     .subscribe(BUTTONS, 0)
+///     .command(BUTTONS, 1)
+///     .command(BUTTONS, 3)
+///     .returns(command_return::success_u32(1))
+///     .yield_wait();
+/// ```
+pub struct ExpectBuilder<'k> {
+    kernel: &'k Kernel,
+    // Whether this builder has added a call, which the modifiers apply to.
+    added: bool,
+}
+
+impl<'k> ExpectBuilder<'k> {
+    pub(crate) fn new(kernel: &'k Kernel) -> ExpectBuilder<'k> {
+        ExpectBuilder {
+            kernel,
+            added: false,
+        }
+    }
+
+    pub fn yield_no_wait(mut self) -> Self {
+        self.push(ExpectedSyscall::YieldNoWait {
+            override_return: None,
+        });
+        self
+    }
+
+    pub fn yield_wait(mut self) -> Self {
+        self.push(ExpectedSyscall::YieldWait { skip_upcall: false });
+        self
+    }
+
+    pub fn subscribe(mut self, driver_num: u32, subscribe_num: u32) -> Self {
+        self.push(ExpectedSyscall::Subscribe {
+            driver_num,
+            subscribe_num,
+            skip_with_error: None,
+        });
+        self
+    }
+
+    /// Expects a command with both arguments 0; use `args` to expect other
+    /// arguments.
+    pub fn command(mut self, driver_id: u32, command_id: u32) -> Self {
+        self.push(ExpectedSyscall::Command {
+            driver_id,
+            command_id,
+            argument0: 0,
+            argument1: 0,
+            override_return: None,
+        });
+        self
+    }
+
+    pub fn allow_ro(mut self, driver_num: u32, buffer_num: u32) -> Self {
+        self.push(ExpectedSyscall::AllowRo {
+            driver_num,
+            buffer_num,
+            return_error: None,
+        });
+        self
+    }
+
+    pub fn allow_rw(mut self, driver_num: u32, buffer_num: u32) -> Self {
+        self.push(ExpectedSyscall::AllowRw {
+            driver_num,
+            buffer_num,
+            return_error: None,
+        });
+        self
+    }
+
+    /// Sets the arguments of the last expected command.
+    #[track_caller]
+    pub fn args(self, arg0: u32, arg1: u32) -> Self {
+        let mut last = self.take_last("args");
+        match &mut last {
+            ExpectedSyscall::Command {
+                argument0,
+                argument1,
+                ..
+            } => {
+                *argument0 = arg0;
+                *argument1 = arg1;
+            }
+            other => panic!("args() applies to a Command, not {}", other),
+        }
+        self.kernel.add_expected_syscall(last);
+        self
+    }
+
+    /// Overrides the return value of the last expected command or
+    /// yield-no-wait.
+    #[track_caller]
+    pub fn returns<R: Into<Return>>(self, value: R) -> Self {
+        let mut last = self.take_last("returns");
+        match (&mut last, value.into()) {
+            (
+                ExpectedSyscall::Command {
+                    override_return, ..
+                },
+                Return::Command(value),
+            ) => *override_return = Some(value),
+            (ExpectedSyscall::YieldNoWait { override_return }, Return::YieldNoWait(value)) => {
+                *override_return = Some(value)
+            }
+            (other, value) => panic!("returns({:?}) does not apply to {}", value, other),
+        }
+        self.kernel.add_expected_syscall(last);
+        self
+    }
+
+    /// Makes the last expected Subscribe or Allow fail with `error` without
+    /// reaching the driver.
+    #[track_caller]
+    pub fn fails_with(self, error: ErrorCode) -> Self {
+        let mut last = self.take_last("fails_with");
+        match &mut last {
+            ExpectedSyscall::Subscribe {
+                skip_with_error, ..
+            } => *skip_with_error = Some(error),
+            ExpectedSyscall::AllowRo { return_error, .. }
+            | ExpectedSyscall::AllowRw { return_error, .. } => *return_error = Some(error),
+            other => panic!(
+                "fails_with() applies to a Subscribe or Allow, not {}",
+                other
+            ),
+        }
+        self.kernel.add_expected_syscall(last);
+        self
+    }
+
+    /// Makes the last expected yield-wait return without running an upcall.
+    #[track_caller]
+    pub fn skip_upcall(self) -> Self {
+        let mut last = self.take_last("skip_upcall");
+        match &mut last {
+            ExpectedSyscall::YieldWait { skip_upcall } => *skip_upcall = true,
+            other => panic!("skip_upcall() applies to a YieldWait, not {}", other),
+        }
+        self.kernel.add_expected_syscall(last);
+        self
+    }
+
+    fn push(&mut self, syscall: ExpectedSyscall) {
+        self.kernel.add_expected_syscall(syscall);
+        self.added = true;
+    }
+
+    // Removes the last queued call so a modifier can change it. If the
+    // modifier does not apply to it, the call is dropped along with the
+    // panic.
+    #[track_caller]
+    fn take_last(&self, modifier: &str) -> ExpectedSyscall {
+        if !self.added {
+            panic!("{}() called before any system call was described", modifier);
+        }
+        match self.kernel.take_last_expected_syscall() {
+            Some(last) => last,
+            None => panic!(
+                "{}() called after a system call consumed the call it applies to",
+                modifier
+            ),
+        }
+    }
+}
+
+/// A value accepted by `ExpectBuilder::returns`.
+#[derive(Clone, Copy, Debug)]
+pub enum Return {
+    Command(CommandReturn),
+    YieldNoWait(YieldNoWaitReturn),
+}
+
+impl From<CommandReturn> for Return {
+    fn from(value: CommandReturn) -> Return {
+        Return::Command(value)
+    }
+}
+
+impl From<YieldNoWaitReturn> for Return {
+    fn from(value: YieldNoWaitReturn) -> Return {
+        Return::YieldNoWait(value)
+    }
+}
@@ -75,14 +75,124 @@ pub enum ExpectedSyscall {
 }
 
 impl ExpectedSyscall {
-    // Panics with a message describing that the named system call was called
-    // instead of the expected system call. Used by fake::Kernel to report
-    // incorrect system calls.
-    pub(crate) fn panic_wrong_call(&self, called: &str) -> ! {
-        // TODO: Implement Display for ExpectedSyscall and replace {:?} with {}
-        panic!(
-            "Expected system call {:?}, but {} was called instead.",
-            self, called
-        );
+    // Panics with a message describing that `called` was made instead of the
+    // expected system call. Used by fake::Kernel to report incorrect system
+    // calls.
+    pub(crate) fn panic_wrong_call(&self, called: &crate::SyscallLogEntry) -> ! {
+        panic!("Expected {}, but {} was called instead.", self, called);
+    }
+
+    // Returns true if `entry` is a call this expectation would accept.
+    pub(crate) fn matches(&self, entry: &crate::SyscallLogEntry) -> bool {
+        use crate::SyscallLogEntry as Entry;
+        match (*self, entry) {
+            (ExpectedSyscall::YieldNoWait { .. }, Entry::YieldNoWait) => true,
+            (ExpectedSyscall::YieldWait { .. }, Entry::YieldWait) => true,
+            (
+                ExpectedSyscall::Subscribe {
+                    driver_num,
+                    subscribe_num,
+                    ..
+                },
+                &Entry::Subscribe {
+                    driver_num: called_driver,
+                    subscribe_num: called_subscribe,
+                },
+            ) => driver_num == called_driver && subscribe_num == called_subscribe,
+            (
+                ExpectedSyscall::Command {
+                    driver_id,
+                    command_id,
+                    argument0,
+                    argument1,
+                    ..
+                },
+                &Entry::Command {
+                    driver_id: called_driver,
+                    command_id: called_command,
+                    argument0: called_argument0,
+                    argument1: called_argument1,
+                },
+            ) => {
+                driver_id == called_driver
+                    && command_id == called_command
+                    && argument0 == called_argument0
+                    && argument1 == called_argument1
+            }
+            (
+                ExpectedSyscall::AllowRo {
+                    driver_num,
+                    buffer_num,
+                    ..
+                },
+                &Entry::AllowRo {
+                    driver_num: called_driver,
+                    buffer_num: called_buffer,
+                    ..
+                },
+            )
+            | (
+                ExpectedSyscall::AllowRw {
+                    driver_num,
+                    buffer_num,
+                    ..
+                },
+                &Entry::AllowRw {
+                    driver_num: called_driver,
+                    buffer_num: called_buffer,
+                    ..
+                },
+            ) => driver_num == called_driver && buffer_num == called_buffer,
+            _ => false,
+        }
+    }
+}
+
+/// Displays the system call and the values it is matched against, in the same
+/// format `SyscallLogEntry` uses for the system calls that were made.
+impl std::fmt::Display for ExpectedSyscall {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ExpectedSyscall::YieldNoWait { .. } => write!(f, "YieldNoWait"),
+            ExpectedSyscall::YieldWait { .. } => write!(f, "YieldWait"),
+            ExpectedSyscall::Subscribe {
+                driver_num,
+                subscribe_num,
+                ..
+            } => write!(
+                f,
+                "Subscribe{{driver={:#x} sub={}}}",
+                driver_num, subscribe_num
+            ),
+            ExpectedSyscall::Command {
+                driver_id,
+                command_id,
+                argument0,
+                argument1,
+                ..
+            } => write!(
+                f,
+                "Command{{driver={:#x} cmd={} arg0={} arg1={}}}",
+                driver_id, command_id, argument0, argument1
+            ),
+            ExpectedSyscall::AllowRo {
+                driver_num,
+                buffer_num,
+                ..
+            } => write!(
+                f,
+                "AllowRo{{driver={:#x} buffer={}}}",
+                driver_num, buffer_num
+            ),
+            ExpectedSyscall::AllowRw {
+                driver_num,
+                buffer_num,
+                ..
+            } => write!(
+                f,
+                "AllowRw{{driver={:#x} buffer={}}}",
+                driver_num, buffer_num
+            ),
+        }
     }
 }
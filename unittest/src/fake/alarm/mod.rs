@@ -1,7 +1,8 @@
 //! Fake implementation of the Alarm API.
 //!
-//! Supports frequency and set_relative.
-//! Will schedule the upcall immediately.
+//! Supports frequency, time and set_relative.
+//! Will schedule the upcall immediately, advancing the virtual clock to the
+//! wake time. The clock can also be advanced directly with `advance`.
 
 use core::cell::Cell;
 use core::num::Wrapping;
@@ -23,6 +24,12 @@ impl Alarm {
             share_ref: Default::default(),
         })
     }
+
+    /// Advances the virtual clock by `ticks`, wrapping around like the real
+    /// counter.
+    pub fn advance(&self, ticks: u32) {
+        self.now.set(self.now.get() + Wrapping(ticks));
+    }
 }
 
 impl crate::fake::SyscallDriver for Alarm {
@@ -37,6 +44,7 @@ impl crate::fake::SyscallDriver for Alarm {
     fn command(&self, command_number: u32, argument0: u32, _argument1: u32) -> CommandReturn {
         match command_number {
             command::FREQUENCY => crate::command_return::success_u32(self.frequency_hz),
+            command::TIME => crate::command_return::success_u32(self.now.get().0),
             command::SET_RELATIVE => {
                 // We're not actually sleeping, just ticking the timer.
                 // The semantics of sleeping aren't clear,
@@ -62,7 +70,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 0;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::ALARM;
 
 // Command IDs
 #[allow(unused)]
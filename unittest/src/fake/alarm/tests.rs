@@ -12,3 +12,32 @@ fn command() {
         Some(10)
     );
 }
+
+#[test]
+fn time() {
+    use fake::SyscallDriver;
+    let alarm = Alarm::new(10);
+
+    assert_eq!(
+        alarm.command(command::TIME, 0, 0).get_success_u32(),
+        Some(0)
+    );
+    alarm.advance(5);
+    assert_eq!(
+        alarm.command(command::TIME, 0, 0).get_success_u32(),
+        Some(5)
+    );
+    alarm.advance(u32::MAX);
+    assert_eq!(
+        alarm.command(command::TIME, 0, 0).get_success_u32(),
+        Some(4)
+    );
+    assert_eq!(
+        alarm.command(command::SET_RELATIVE, 6, 0).get_success_u32(),
+        Some(10)
+    );
+    assert_eq!(
+        alarm.command(command::TIME, 0, 0).get_success_u32(),
+        Some(10)
+    );
+}
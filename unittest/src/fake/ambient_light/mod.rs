@@ -4,25 +4,77 @@
 //! Like the real API, `AmbientLight` controls a fake ambient light sensor. It provides
 //! a function `set_value` used to immediately call an upcall with a intensity value read by the sensor
 //! and a function 'set_value_sync' used to call the upcall when the read command is received.
+//! Values passed to consecutive `set_value_sync` calls are queued, and each read command consumes one.
+//! The functions `set_saturated` and `set_stale` set the reading flags passed
+//! to the upcall along with the value. The range selected through the range command can be retrieved via `get_range`.
+//! While the sensor is powered down, read commands fail with `Off`; `is_powered` and the is-powered
+//! command report the power state.
+//! In continuous mode, `set_value` calls the upcall even when no read is pending; `get_sample_rate`
+//! and `is_continuous` report the continuous mode configuration. The resolution reported by the driver
+//! is set through `set_resolution_bits`. `set_continuous_supported(false)` simulates a basic sensor
+//! without continuous mode, whose continuous mode commands fail with `NoSupport`. The reset command
+//! restores the default range, powers the sensor up and stops continuous mode. The driver acts as a
+//! single-channel sensor until channel counts are set with `set_channels`.
+//! The threshold command arms a one-shot interrupt, delivered as a reading once a value is on the
+//! requested side of the threshold; values queued with `set_value_sync` are checked first.
+//! `set_threshold_supported(false)` makes the threshold command fail with `NoSupport`.
+//! The maximum measurable intensity is only reported once set with `set_max_lux`.
+//! The integration time, in milliseconds, starts at 100 and can be retrieved via
+//! `get_integration_time`; the reset command restores it too.
 
 use crate::{DriverInfo, DriverShareRef};
 use libtock_platform::{CommandReturn, ErrorCode};
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 
-// The `upcall_on_command` field is set to Some(value) if an upcall(with value as its argument) should be called when read command is received,
-// or None otherwise. It was needed for testing `read_sync` library function which simulates a synchronous temperature read,
+// The `upcall_on_command` field holds the values for which an upcall should be called when read commands are received,
+// in the order they were set. It was needed for testing `read_sync` library function which simulates a synchronous temperature read,
 // because it was impossible to schedule an upcall during the `synchronous` read in other ways.
 pub struct AmbientLight {
+    driver_num: u32,
     busy: Cell<bool>,
-    upcall_on_command: Cell<Option<u32>>,
+    upcall_on_command: RefCell<VecDeque<u32>>,
+    range: Cell<u32>,
+    flags: Cell<u32>,
+    powered: Cell<bool>,
+    sample_rate: Cell<u32>,
+    continuous: Cell<bool>,
+    continuous_supported: Cell<bool>,
+    resolution_bits: Cell<u32>,
+    channels: Cell<Option<(u32, u32)>>,
+    // The armed threshold, and whether it waits for a value at or above it
+    // (true) or below it (false).
+    threshold: Cell<Option<(u32, bool)>>,
+    threshold_supported: Cell<bool>,
+    max_lux: Cell<Option<u32>>,
+    integration_time: Cell<u32>,
     share_ref: DriverShareRef,
 }
 
 impl AmbientLight {
     pub fn new() -> std::rc::Rc<AmbientLight> {
+        Self::new_with_driver_num(DRIVER_NUM)
+    }
+
+    /// Creates a fake ambient light driver registered under a non-standard
+    /// driver number.
+    pub fn new_with_driver_num(driver_num: u32) -> std::rc::Rc<AmbientLight> {
         std::rc::Rc::new(AmbientLight {
+            driver_num,
             busy: Cell::new(false),
-            upcall_on_command: Cell::new(None),
+            upcall_on_command: RefCell::new(VecDeque::new()),
+            range: Cell::new(DEFAULT_RANGE),
+            flags: Cell::new(0),
+            powered: Cell::new(true),
+            sample_rate: Cell::new(0),
+            continuous: Cell::new(false),
+            continuous_supported: Cell::new(true),
+            resolution_bits: Cell::new(DEFAULT_RESOLUTION_BITS),
+            channels: Cell::new(None),
+            threshold: Cell::new(None),
+            threshold_supported: Cell::new(true),
+            max_lux: Cell::new(None),
+            integration_time: Cell::new(DEFAULT_INTEGRATION_TIME),
             share_ref: Default::default(),
         })
     }
@@ -31,41 +83,181 @@ impl AmbientLight {
         self.busy.get()
     }
     pub fn set_value(&self, value: u32) {
-        if self.busy.get() {
+        let crossed = self.crosses_threshold(value);
+        if self.busy.get() || self.continuous.get() || crossed {
             self.share_ref
-                .schedule_upcall(0, (value as u32, 0, 0))
+                .schedule_upcall(0, (value as u32, self.flags.get(), 0))
                 .expect("Unable to schedule upcall");
             self.busy.set(false);
         }
+        if crossed {
+            self.threshold.set(None);
+        }
     }
     pub fn set_value_sync(&self, value: u32) {
-        self.upcall_on_command.set(Some(value));
+        self.upcall_on_command.borrow_mut().push_back(value);
+    }
+    pub fn set_saturated(&self, saturated: bool) {
+        self.set_flag(FLAG_SATURATED, saturated);
+    }
+    pub fn set_stale(&self, stale: bool) {
+        self.set_flag(FLAG_STALE, stale);
+    }
+    pub fn get_range(&self) -> u32 {
+        self.range.get()
+    }
+    pub fn is_powered(&self) -> bool {
+        self.powered.get()
+    }
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate.get()
+    }
+    pub fn is_continuous(&self) -> bool {
+        self.continuous.get()
+    }
+    pub fn set_resolution_bits(&self, bits: u32) {
+        self.resolution_bits.set(bits);
+    }
+    pub fn set_channels(&self, visible: u32, ir: u32) {
+        self.channels.set(Some((visible, ir)));
+    }
+    pub fn set_continuous_supported(&self, supported: bool) {
+        self.continuous_supported.set(supported);
+    }
+    pub fn set_threshold_supported(&self, supported: bool) {
+        self.threshold_supported.set(supported);
+    }
+    pub fn set_max_lux(&self, max_lux: u32) {
+        self.max_lux.set(Some(max_lux));
+    }
+    pub fn get_integration_time(&self) -> u32 {
+        self.integration_time.get()
+    }
+}
+
+impl AmbientLight {
+    fn crosses_threshold(&self, value: u32) -> bool {
+        match self.threshold.get() {
+            Some((threshold, rising)) => (value >= threshold) == rising,
+            None => false,
+        }
+    }
+
+    fn set_flag(&self, flag: u32, value: bool) {
+        match value {
+            true => self.flags.set(self.flags.get() | flag),
+            false => self.flags.set(self.flags.get() & !flag),
+        }
     }
 }
 
 impl crate::fake::SyscallDriver for AmbientLight {
     fn info(&self) -> DriverInfo {
-        DriverInfo::new(DRIVER_NUM).upcall_count(1)
+        DriverInfo::new(self.driver_num).upcall_count(1)
     }
 
     fn register(&self, share_ref: DriverShareRef) {
         self.share_ref.replace(share_ref);
     }
 
-    fn command(&self, command_id: u32, _argument0: u32, _argument1: u32) -> CommandReturn {
+    fn command(&self, command_id: u32, argument0: u32, argument1: u32) -> CommandReturn {
         match command_id {
             EXISTS => crate::command_return::success(),
 
             READ_INTENSITY => {
+                if !self.powered.get() {
+                    return crate::command_return::failure(ErrorCode::Off);
+                }
                 if self.busy.get() {
                     return crate::command_return::failure(ErrorCode::Busy);
                 }
                 self.busy.set(true);
-                if let Some(val) = self.upcall_on_command.take() {
+                let value = self.upcall_on_command.borrow_mut().pop_front();
+                if let Some(val) = value {
                     self.set_value(val);
                 }
                 crate::command_return::success()
             }
+            SET_RANGE => {
+                if argument0 > MAX_RANGE {
+                    return crate::command_return::failure(ErrorCode::Invalid);
+                }
+                self.range.set(argument0);
+                crate::command_return::success()
+            }
+            POWER_DOWN => {
+                self.powered.set(false);
+                crate::command_return::success()
+            }
+            POWER_UP => {
+                self.powered.set(true);
+                crate::command_return::success()
+            }
+            IS_POWERED => crate::command_return::success_u32(self.powered.get() as u32),
+            SET_SAMPLE_RATE | START_CONTINUOUS | STOP_CONTINUOUS
+                if !self.continuous_supported.get() =>
+            {
+                crate::command_return::failure(ErrorCode::NoSupport)
+            }
+            SET_SAMPLE_RATE => {
+                if argument0 == 0 {
+                    return crate::command_return::failure(ErrorCode::Invalid);
+                }
+                self.sample_rate.set(argument0);
+                crate::command_return::success()
+            }
+            START_CONTINUOUS => {
+                if self.sample_rate.get() == 0 {
+                    return crate::command_return::failure(ErrorCode::Reserve);
+                }
+                self.continuous.set(true);
+                crate::command_return::success()
+            }
+            STOP_CONTINUOUS => {
+                self.continuous.set(false);
+                crate::command_return::success()
+            }
+            RESOLUTION => crate::command_return::success_u32(self.resolution_bits.get()),
+            READ_CHANNELS => match self.channels.get() {
+                Some((visible, ir)) => crate::command_return::success_2_u32(visible, ir),
+                None => crate::command_return::failure(ErrorCode::NoSupport),
+            },
+            WATCH_THRESHOLD => {
+                if !self.threshold_supported.get() {
+                    return crate::command_return::failure(ErrorCode::NoSupport);
+                }
+                self.threshold.set(Some((argument0, argument1 != 0)));
+                // Queued values are measured before any value set later.
+                while self.threshold.get().is_some() {
+                    let value = self.upcall_on_command.borrow_mut().pop_front();
+                    match value {
+                        Some(value) => self.set_value(value),
+                        None => break,
+                    }
+                }
+                crate::command_return::success()
+            }
+            MAX_LUX => match self.max_lux.get() {
+                Some(max_lux) => crate::command_return::success_u32(max_lux),
+                None => crate::command_return::failure(ErrorCode::NoSupport),
+            },
+            INTEGRATION_TIME => crate::command_return::success_u32(self.integration_time.get()),
+            SET_INTEGRATION_TIME => {
+                if argument0 == 0 {
+                    return crate::command_return::failure(ErrorCode::Invalid);
+                }
+                self.integration_time.set(argument0);
+                crate::command_return::success()
+            }
+            RESET => {
+                self.threshold.set(None);
+                self.integration_time.set(DEFAULT_INTEGRATION_TIME);
+                self.range.set(DEFAULT_RANGE);
+                self.powered.set(true);
+                self.sample_rate.set(0);
+                self.continuous.set(false);
+                crate::command_return::success()
+            }
             _ => crate::command_return::failure(ErrorCode::NoSupport),
         }
     }
@@ -77,9 +269,33 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 0x60002;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::AMBIENT_LIGHT;
 
 // Command IDs
 
 const EXISTS: u32 = 0;
 const READ_INTENSITY: u32 = 1;
+const SET_RANGE: u32 = 2;
+const POWER_DOWN: u32 = 3;
+const POWER_UP: u32 = 4;
+const SET_SAMPLE_RATE: u32 = 5;
+const START_CONTINUOUS: u32 = 6;
+const STOP_CONTINUOUS: u32 = 7;
+const RESOLUTION: u32 = 8;
+const RESET: u32 = 9;
+const READ_CHANNELS: u32 = 10;
+const WATCH_THRESHOLD: u32 = 11;
+const MAX_LUX: u32 = 12;
+const INTEGRATION_TIME: u32 = 13;
+const SET_INTEGRATION_TIME: u32 = 14;
+const IS_POWERED: u32 = 15;
+
+// Reading flags
+const FLAG_SATURATED: u32 = 1 << 0;
+const FLAG_STALE: u32 = 1 << 1;
+
+const DEFAULT_RANGE: u32 = 1;
+const MAX_RANGE: u32 = 2;
+
+const DEFAULT_RESOLUTION_BITS: u32 = 16;
+const DEFAULT_INTEGRATION_TIME: u32 = 100;
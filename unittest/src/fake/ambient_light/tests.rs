@@ -65,3 +65,194 @@ fn kernel_integration() {
         assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
     });
 }
+
+#[test]
+fn range() {
+    let amb = AmbientLight::new();
+    assert_eq!(amb.get_range(), DEFAULT_RANGE);
+
+    assert!(amb.command(SET_RANGE, 0, 0).is_success());
+    assert_eq!(amb.get_range(), 0);
+    assert!(amb.command(SET_RANGE, MAX_RANGE, 0).is_success());
+    assert_eq!(amb.get_range(), MAX_RANGE);
+    assert_eq!(
+        amb.command(SET_RANGE, MAX_RANGE + 1, 0).get_failure(),
+        Some(ErrorCode::Invalid)
+    );
+    assert_eq!(amb.get_range(), MAX_RANGE);
+}
+
+#[test]
+fn flags() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let ambient_light = AmbientLight::new();
+    kernel.add_driver(&ambient_light);
+
+    let listener = Cell::<Option<(u32, u32)>>::new(None);
+    share::scope(|subscribe| {
+        assert_eq!(
+            fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, &listener),
+            Ok(())
+        );
+
+        ambient_light.set_saturated(true);
+        ambient_light.set_stale(true);
+        assert!(ambient_light.command(READ_INTENSITY, 0, 0).is_success());
+        ambient_light.set_value(100);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.get(), Some((100, FLAG_SATURATED | FLAG_STALE)));
+
+        ambient_light.set_saturated(false);
+        assert!(ambient_light.command(READ_INTENSITY, 0, 0).is_success());
+        ambient_light.set_value(200);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.get(), Some((200, FLAG_STALE)));
+    });
+}
+
+#[test]
+fn power() {
+    let amb = AmbientLight::new();
+    assert!(amb.is_powered());
+
+    assert!(amb.command(POWER_DOWN, 0, 0).is_success());
+    assert!(!amb.is_powered());
+    assert_eq!(amb.command(IS_POWERED, 0, 0).get_success_u32(), Some(0));
+    assert_eq!(
+        amb.command(READ_INTENSITY, 0, 0).get_failure(),
+        Some(ErrorCode::Off)
+    );
+    assert!(!amb.is_busy());
+
+    assert!(amb.command(POWER_UP, 0, 0).is_success());
+    assert!(amb.is_powered());
+    assert!(amb.command(READ_INTENSITY, 0, 0).is_success());
+}
+
+#[test]
+fn continuous() {
+    let amb = AmbientLight::new();
+    assert_eq!(
+        amb.command(START_CONTINUOUS, 0, 0).get_failure(),
+        Some(ErrorCode::Reserve)
+    );
+    assert_eq!(
+        amb.command(SET_SAMPLE_RATE, 0, 0).get_failure(),
+        Some(ErrorCode::Invalid)
+    );
+    assert!(amb.command(SET_SAMPLE_RATE, 5, 0).is_success());
+    assert_eq!(amb.get_sample_rate(), 5);
+    assert!(amb.command(START_CONTINUOUS, 0, 0).is_success());
+    assert!(amb.is_continuous());
+    assert!(amb.command(STOP_CONTINUOUS, 0, 0).is_success());
+    assert!(!amb.is_continuous());
+
+    amb.set_continuous_supported(false);
+    for command_id in [SET_SAMPLE_RATE, START_CONTINUOUS, STOP_CONTINUOUS] {
+        assert_eq!(
+            amb.command(command_id, 5, 0).get_failure(),
+            Some(ErrorCode::NoSupport)
+        );
+    }
+    assert!(!amb.is_continuous());
+}
+
+#[test]
+fn threshold() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let amb = AmbientLight::new();
+    kernel.add_driver(&amb);
+
+    let listener = Cell::<Option<(u32,)>>::new(None);
+    share::scope(|subscribe| {
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, &listener)
+            .unwrap();
+
+        // Queued values below a rising threshold are consumed silently.
+        amb.set_value_sync(100);
+        amb.set_value_sync(600);
+        amb.set_value_sync(700);
+        assert!(amb.command(WATCH_THRESHOLD, 500, 1).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.get(), Some((600,)));
+        // The interrupt is one-shot.
+        amb.set_value(800);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+
+        // The remaining queued value is still above the threshold.
+        assert!(amb.command(WATCH_THRESHOLD, 500, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+        amb.set_value(499);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.get(), Some((499,)));
+    });
+
+    amb.set_threshold_supported(false);
+    assert_eq!(
+        amb.command(WATCH_THRESHOLD, 500, 1).get_failure(),
+        Some(ErrorCode::NoSupport)
+    );
+}
+
+#[test]
+fn resolution() {
+    let amb = AmbientLight::new();
+    assert_eq!(
+        amb.command(RESOLUTION, 0, 0).get_success_u32(),
+        Some(DEFAULT_RESOLUTION_BITS)
+    );
+    amb.set_resolution_bits(12);
+    assert_eq!(amb.command(RESOLUTION, 0, 0).get_success_u32(), Some(12));
+}
+
+#[test]
+fn reset() {
+    let amb = AmbientLight::new();
+    assert!(amb.command(SET_RANGE, 2, 0).is_success());
+    assert!(amb.command(SET_SAMPLE_RATE, 5, 0).is_success());
+    assert!(amb.command(START_CONTINUOUS, 0, 0).is_success());
+    assert!(amb.command(POWER_DOWN, 0, 0).is_success());
+    assert!(amb.command(SET_INTEGRATION_TIME, 400, 0).is_success());
+
+    assert!(amb.command(RESET, 0, 0).is_success());
+    assert_eq!(amb.get_range(), DEFAULT_RANGE);
+    assert_eq!(amb.get_sample_rate(), 0);
+    assert!(!amb.is_continuous());
+    assert!(amb.is_powered());
+    assert_eq!(amb.get_integration_time(), DEFAULT_INTEGRATION_TIME);
+}
+
+#[test]
+fn channels() {
+    let amb = AmbientLight::new();
+    assert_eq!(
+        amb.command(READ_CHANNELS, 0, 0).get_failure(),
+        Some(ErrorCode::NoSupport)
+    );
+    amb.set_channels(1000, 100);
+    assert_eq!(
+        amb.command(READ_CHANNELS, 0, 0).get_success_2_u32(),
+        Some((1000, 100))
+    );
+}
+
+#[test]
+fn integration_time() {
+    let amb = AmbientLight::new();
+    assert_eq!(
+        amb.command(INTEGRATION_TIME, 0, 0).get_success_u32(),
+        Some(DEFAULT_INTEGRATION_TIME)
+    );
+    assert!(amb.command(SET_INTEGRATION_TIME, 400, 0).is_success());
+    assert_eq!(amb.get_integration_time(), 400);
+    assert_eq!(
+        amb.command(SET_INTEGRATION_TIME, 0, 0).get_failure(),
+        Some(ErrorCode::Invalid)
+    );
+    assert_eq!(
+        amb.command(INTEGRATION_TIME, 0, 0).get_success_u32(),
+        Some(400)
+    );
+}
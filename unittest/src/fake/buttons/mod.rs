@@ -6,11 +6,26 @@
 //! status of a button.
 //!
 //! It also provides the function `set_pressed` that set the button's state.
+//!
+//! Events added through `push_history_event` are written to the shared
+//! history buffer when history recording is enabled, followed by a flush
+//! upcall reporting the number of events written.
+//!
+//! A query command reports whether interrupts are enabled for a button, as
+//! `get_button_state` does.
+//!
+//! The bulk read command reports the state of buttons 0 to 31 as a bitmask;
+//! `set_read_mask_supported(false)` makes it fail with `NoSupport`.
+//!
+//! `set_interrupts_supported(false)` simulates read-only buttons, whose
+//! interrupt commands fail with `NoSupport`. `set_pressed_on_enable` queues a
+//! state change that happens once interrupts are enabled for the button, to
+//! simulate a press that arrives while an app waits for events.
 
-use core::cell::Cell;
+use core::cell::{Cell, RefCell};
 use libtock_platform::{CommandReturn, ErrorCode};
 
-use crate::{DriverInfo, DriverShareRef};
+use crate::{DriverInfo, DriverShareRef, RwAllowBuffer};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct ButtonState {
@@ -20,6 +35,15 @@ pub struct ButtonState {
 
 pub struct Buttons<const NUM_BUTTONS: usize> {
     buttons: [Cell<ButtonState>; NUM_BUTTONS],
+    history_buffer: RefCell<RwAllowBuffer>,
+    history_enabled: Cell<bool>,
+    // Events not yet written to the history buffer, as (button, pressed,
+    // timestamp).
+    pending_history: RefCell<Vec<(u32, bool, u32)>>,
+    read_mask_supported: Cell<bool>,
+    interrupts_supported: Cell<bool>,
+    // State changes to apply when interrupts are enabled, as (button, pressed).
+    pressed_on_enable: RefCell<Vec<(u32, bool)>>,
     share_ref: DriverShareRef,
 }
 
@@ -32,6 +56,12 @@ impl<const NUM_BUTTONS: usize> Buttons<NUM_BUTTONS> {
         });
         std::rc::Rc::new(Buttons {
             buttons: [OFF; NUM_BUTTONS],
+            history_buffer: Default::default(),
+            history_enabled: Cell::new(false),
+            pending_history: Default::default(),
+            read_mask_supported: Cell::new(true),
+            interrupts_supported: Cell::new(true),
+            pressed_on_enable: Default::default(),
             share_ref: Default::default(),
         })
     }
@@ -56,23 +86,100 @@ impl<const NUM_BUTTONS: usize> Buttons<NUM_BUTTONS> {
             .ok_or(ErrorCode::Invalid)
     }
 
+    /// Delivers a button event upcall for `button` without checking that the
+    /// button exists or has interrupts enabled, to simulate a misbehaving
+    /// kernel.
+    pub fn deliver_raw_event(&self, button: u32, pressed: bool) {
+        self.share_ref
+            .schedule_upcall(0, (button, pressed as u32, 0))
+            .expect("Unable to schedule upcall {}");
+    }
+
     pub fn get_button_state(&self, button: u32) -> Option<ButtonState> {
         self.buttons.get(button as usize).map(|button| button.get())
     }
+
+    /// Adds an event to be recorded in the history buffer. Events are written
+    /// when history recording is enabled, or immediately if it already is.
+    pub fn push_history_event(&self, button: u32, pressed: bool, timestamp: u32) {
+        self.pending_history
+            .borrow_mut()
+            .push((button, pressed, timestamp));
+        if self.history_enabled.get() {
+            self.flush_history();
+        }
+    }
+
+    pub fn is_history_enabled(&self) -> bool {
+        self.history_enabled.get()
+    }
+
+    pub fn set_read_mask_supported(&self, supported: bool) {
+        self.read_mask_supported.set(supported);
+    }
+
+    pub fn set_interrupts_supported(&self, supported: bool) {
+        self.interrupts_supported.set(supported);
+    }
+
+    /// Sets the state of `button` once interrupts are next enabled for it,
+    /// delivering an event upcall if the state changes.
+    pub fn set_pressed_on_enable(&self, button: u32, pressed: bool) {
+        self.pressed_on_enable.borrow_mut().push((button, pressed));
+    }
+}
+
+impl<const NUM_BUTTONS: usize> Buttons<NUM_BUTTONS> {
+    // Writes as many pending events as fit into the history buffer, then
+    // schedules the flush upcall.
+    fn flush_history(&self) {
+        let mut pending = self.pending_history.borrow_mut();
+        let mut buffer = self.history_buffer.borrow_mut();
+        let count = core::cmp::min(pending.len(), buffer.len() / HISTORY_EVENT_SIZE);
+        if count == 0 {
+            return;
+        }
+        for (i, (button, pressed, timestamp)) in pending.drain(..count).enumerate() {
+            let event = &mut buffer[i * HISTORY_EVENT_SIZE..(i + 1) * HISTORY_EVENT_SIZE];
+            event[0..4].copy_from_slice(&button.to_le_bytes());
+            event[4..8].copy_from_slice(&(pressed as u32).to_le_bytes());
+            event[8..12].copy_from_slice(&timestamp.to_le_bytes());
+        }
+        self.share_ref
+            .schedule_upcall(SUBSCRIBE_HISTORY, (count as u32, 0, 0))
+            .expect("Unable to schedule upcall {}");
+    }
 }
 
 impl<const NUM_BUTTONS: usize> crate::fake::SyscallDriver for Buttons<NUM_BUTTONS> {
     fn info(&self) -> DriverInfo {
-        DriverInfo::new(DRIVER_NUM).upcall_count(1)
+        DriverInfo::new(DRIVER_NUM).upcall_count(2)
     }
 
     fn register(&self, share_ref: DriverShareRef) {
         self.share_ref.replace(share_ref);
     }
 
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        if buffer_num == ALLOW_HISTORY {
+            Ok(self.history_buffer.replace(buffer))
+        } else {
+            Err((buffer, ErrorCode::Invalid))
+        }
+    }
+
     fn command(&self, command_number: u32, argument0: u32, _argument1: u32) -> CommandReturn {
         match command_number {
             BUTTONS_COUNT => crate::command_return::success_u32(NUM_BUTTONS as u32),
+            BUTTONS_ENABLE_INTERRUPTS | BUTTONS_DISABLE_INTERRUPTS
+                if !self.interrupts_supported.get() =>
+            {
+                crate::command_return::failure(ErrorCode::NoSupport)
+            }
             BUTTONS_ENABLE_INTERRUPTS => {
                 if argument0 < NUM_BUTTONS as u32 {
                     let button = self.buttons[argument0 as usize].get();
@@ -80,6 +187,19 @@ impl<const NUM_BUTTONS: usize> crate::fake::SyscallDriver for Buttons<NUM_BUTTON
                         interrupt_enabled: true,
                         ..button
                     });
+                    let mut changes = Vec::new();
+                    self.pressed_on_enable
+                        .borrow_mut()
+                        .retain(|&(button, pressed)| match button == argument0 {
+                            true => {
+                                changes.push(pressed);
+                                false
+                            }
+                            false => true,
+                        });
+                    for pressed in changes {
+                        let _ = self.set_pressed(argument0, pressed);
+                    }
                     crate::command_return::success()
                 } else {
                     crate::command_return::failure(ErrorCode::Invalid)
@@ -106,6 +226,34 @@ impl<const NUM_BUTTONS: usize> crate::fake::SyscallDriver for Buttons<NUM_BUTTON
                     crate::command_return::failure(ErrorCode::Invalid)
                 }
             }
+            BUTTONS_INTERRUPTS_ENABLED => {
+                if argument0 < NUM_BUTTONS as u32 {
+                    crate::command_return::success_u32(
+                        self.buttons[argument0 as usize].get().interrupt_enabled as u32,
+                    )
+                } else {
+                    crate::command_return::failure(ErrorCode::Invalid)
+                }
+            }
+            BUTTONS_READ_MASK if self.read_mask_supported.get() => {
+                let mask = self
+                    .buttons
+                    .iter()
+                    .take(u32::BITS as usize)
+                    .enumerate()
+                    .filter(|(_, button)| button.get().pressed)
+                    .fold(0, |mask, (index, _)| mask | 1 << index);
+                crate::command_return::success_u32(mask)
+            }
+            BUTTONS_ENABLE_HISTORY => {
+                self.history_enabled.set(true);
+                self.flush_history();
+                crate::command_return::success()
+            }
+            BUTTONS_DISABLE_HISTORY => {
+                self.history_enabled.set(false);
+                crate::command_return::success()
+            }
             _ => crate::command_return::failure(ErrorCode::NoSupport),
         }
     }
@@ -118,7 +266,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 3;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::BUTTONS;
 
 // Command IDs
 const BUTTONS_COUNT: u32 = 0;
@@ -127,3 +275,18 @@ const BUTTONS_ENABLE_INTERRUPTS: u32 = 1;
 const BUTTONS_DISABLE_INTERRUPTS: u32 = 2;
 
 const BUTTONS_READ: u32 = 3;
+
+const BUTTONS_ENABLE_HISTORY: u32 = 4;
+const BUTTONS_DISABLE_HISTORY: u32 = 5;
+
+const BUTTONS_READ_MASK: u32 = 6;
+
+const BUTTONS_INTERRUPTS_ENABLED: u32 = 7;
+
+const SUBSCRIBE_HISTORY: u32 = 1;
+
+const ALLOW_HISTORY: u32 = 0;
+
+// Each history event is the button number, its state, and the timestamp, as
+// little-endian u32s.
+const HISTORY_EVENT_SIZE: usize = 12;
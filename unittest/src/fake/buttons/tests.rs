@@ -29,6 +29,12 @@ fn command() {
             .get_failure(),
         Some(ErrorCode::Invalid)
     );
+    assert_eq!(
+        buttons
+            .command(BUTTONS_INTERRUPTS_ENABLED, 11, 0)
+            .get_failure(),
+        Some(ErrorCode::Invalid)
+    );
 
     for button_index in 0..10 {
         assert_eq!(
@@ -49,6 +55,12 @@ fn command() {
                 interrupt_enabled: true
             })
         );
+        assert_eq!(
+            buttons
+                .command(BUTTONS_INTERRUPTS_ENABLED, button_index, 0)
+                .get_success_u32(),
+            Some(1)
+        );
 
         assert!(buttons
             .command(BUTTONS_DISABLE_INTERRUPTS, button_index, 0)
@@ -81,6 +93,60 @@ fn command() {
     }
 }
 
+#[test]
+fn read_mask() {
+    use fake::SyscallDriver;
+    let buttons = Buttons::<40>::new();
+    assert_eq!(
+        buttons.command(BUTTONS_READ_MASK, 0, 0).get_success_u32(),
+        Some(0)
+    );
+    assert_eq!(buttons.set_pressed(1, true), Ok(()));
+    assert_eq!(buttons.set_pressed(31, true), Ok(()));
+    // Buttons beyond 31 do not fit in the mask.
+    assert_eq!(buttons.set_pressed(35, true), Ok(()));
+    assert_eq!(
+        buttons.command(BUTTONS_READ_MASK, 0, 0).get_success_u32(),
+        Some(1 << 31 | 1 << 1)
+    );
+    buttons.set_read_mask_supported(false);
+    assert_eq!(
+        buttons.command(BUTTONS_READ_MASK, 0, 0).get_failure(),
+        Some(ErrorCode::NoSupport)
+    );
+}
+
+#[test]
+fn interrupt_support() {
+    use fake::SyscallDriver;
+    let buttons = Buttons::<2>::new();
+    buttons.set_pressed_on_enable(1, true);
+    assert!(buttons
+        .command(BUTTONS_ENABLE_INTERRUPTS, 0, 0)
+        .is_success());
+    // Only enabling the queued button's interrupts applies the change.
+    assert!(!buttons.get_button_state(1).unwrap().pressed);
+    assert!(buttons
+        .command(BUTTONS_ENABLE_INTERRUPTS, 1, 0)
+        .is_success());
+    assert!(buttons.get_button_state(1).unwrap().pressed);
+
+    buttons.set_interrupts_supported(false);
+    assert_eq!(
+        buttons
+            .command(BUTTONS_ENABLE_INTERRUPTS, 0, 0)
+            .get_failure(),
+        Some(ErrorCode::NoSupport)
+    );
+    assert_eq!(
+        buttons
+            .command(BUTTONS_DISABLE_INTERRUPTS, 0, 0)
+            .get_failure(),
+        Some(ErrorCode::NoSupport)
+    );
+    assert!(buttons.get_button_state(0).unwrap().interrupt_enabled);
+}
+
 // Integration test that verifies Buttons works with fake::Kernel and
 // libtock_platform::Syscalls.
 #[test]
@@ -110,3 +176,16 @@ fn kernel_integration() {
     );
     assert!(fake::Syscalls::command(DRIVER_NUM, BUTTONS_DISABLE_INTERRUPTS, 0, 0).is_success());
 }
+
+#[test]
+fn history() {
+    use fake::SyscallDriver;
+    let buttons = Buttons::<10>::new();
+
+    buttons.push_history_event(2, true, 100);
+    assert!(!buttons.is_history_enabled());
+    assert!(buttons.command(BUTTONS_ENABLE_HISTORY, 0, 0).is_success());
+    assert!(buttons.is_history_enabled());
+    assert!(buttons.command(BUTTONS_DISABLE_HISTORY, 0, 0).is_success());
+    assert!(!buttons.is_history_enabled());
+}
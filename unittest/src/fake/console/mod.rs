@@ -3,7 +3,8 @@
 //!
 //! Like the real API, `Console` stores each message written to it.
 //! The resulting byte stream can be retrieved via `take_bytes`
-//! for use in unit tests.
+//! for use in unit tests. `set_write_chunk` limits how many bytes each write
+//! command accepts, to simulate a driver that takes long messages in parts.
 
 use core::cell::{Cell, RefCell};
 use core::cmp;
@@ -14,6 +15,7 @@ use crate::{DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
 pub struct Console {
     messages: Cell<Vec<u8>>,
     buffer: Cell<RoAllowBuffer>,
+    write_chunk: Cell<Option<usize>>,
 
     read_buffer: RefCell<RwAllowBuffer>,
     /// To be returned on read
@@ -31,6 +33,7 @@ impl Console {
         std::rc::Rc::new(Console {
             messages: Default::default(),
             buffer: Default::default(),
+            write_chunk: Cell::new(None),
             read_buffer: Default::default(),
             input: Cell::new(Vec::from(inputs)),
             share_ref: Default::default(),
@@ -42,6 +45,12 @@ impl Console {
     pub fn take_bytes(&self) -> Vec<u8> {
         self.messages.take()
     }
+
+    /// Makes each write command accept at most `chunk` bytes. The write
+    /// upcall reports how many bytes were accepted.
+    pub fn set_write_chunk(&self, chunk: usize) {
+        self.write_chunk.set(Some(chunk));
+    }
 }
 
 impl crate::fake::SyscallDriver for Console {
@@ -83,7 +92,10 @@ impl crate::fake::SyscallDriver for Console {
             WRITE => {
                 let mut bytes = self.messages.take();
                 let buffer = self.buffer.take();
-                let size = cmp::min(buffer.len(), argument0 as usize);
+                let mut size = cmp::min(buffer.len(), argument0 as usize);
+                if let Some(chunk) = self.write_chunk.get() {
+                    size = cmp::min(size, chunk);
+                }
                 bytes.extend_from_slice(&(*buffer)[..size]);
                 self.buffer.set(buffer);
                 self.messages.set(bytes);
@@ -118,7 +130,7 @@ impl crate::fake::SyscallDriver for Console {
 #[cfg(test)]
 mod tests;
 
-const DRIVER_NUM: u32 = 1;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::CONSOLE;
 
 // Command numbers
 const DRIVER_CHECK: u32 = 0;
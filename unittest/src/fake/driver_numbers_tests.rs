@@ -0,0 +1,48 @@
+use crate::fake::{self, SyscallDriver};
+use libtock_platform::driver_numbers;
+
+// Tests that each fake driver registers under the driver number its API crate
+// uses, so the API crates' tests exercise the right driver.
+#[test]
+fn driver_numbers() {
+    assert_eq!(
+        fake::Alarm::new(1000).info().driver_num(),
+        driver_numbers::ALARM
+    );
+    assert_eq!(
+        fake::Console::new().info().driver_num(),
+        driver_numbers::CONSOLE
+    );
+    assert_eq!(
+        fake::Leds::<1>::new().info().driver_num(),
+        driver_numbers::LEDS
+    );
+    assert_eq!(
+        fake::Gpio::<1>::new().info().driver_num(),
+        driver_numbers::GPIO
+    );
+    assert_eq!(
+        fake::LowLevelDebug::new().info().driver_num(),
+        driver_numbers::LOW_LEVEL_DEBUG
+    );
+    #[cfg(feature = "ambient_light")]
+    assert_eq!(
+        fake::AmbientLight::new().info().driver_num(),
+        driver_numbers::AMBIENT_LIGHT
+    );
+    #[cfg(feature = "button")]
+    assert_eq!(
+        fake::Buttons::<1>::new().info().driver_num(),
+        driver_numbers::BUTTONS
+    );
+    #[cfg(feature = "proximity")]
+    assert_eq!(
+        fake::Proximity::new().info().driver_num(),
+        driver_numbers::PROXIMITY
+    );
+    #[cfg(feature = "temperature")]
+    assert_eq!(
+        fake::Temperature::new().info().driver_num(),
+        driver_numbers::TEMPERATURE
+    );
+}
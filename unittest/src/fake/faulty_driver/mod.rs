@@ -0,0 +1,90 @@
+//! `FaultyDriver` wraps another fake driver and makes some of its commands
+//! fail, to test how code copes with unreliable hardware.
+//!
+//! Faults are chosen by a pseudo-random generator seeded at construction, so a
+//! test sees the same sequence of faults on every run.
+
+use crate::{DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::cell::Cell;
+use std::rc::Rc;
+
+pub struct FaultyDriver<D: crate::fake::SyscallDriver> {
+    inner: Rc<D>,
+    one_in: u32,
+    state: Cell<u64>,
+    injected_faults: Cell<u32>,
+}
+
+impl<D: crate::fake::SyscallDriver> FaultyDriver<D> {
+    /// Wraps `inner` so that, on average, one in `one_in` commands fails with
+    /// `Busy`, `NoMem` or `Fail` instead of reaching `inner`. A `one_in` of 0
+    /// or 1 makes every command fail.
+    pub fn new(inner: Rc<D>, seed: u64, one_in: u32) -> Rc<FaultyDriver<D>> {
+        Rc::new(FaultyDriver {
+            inner,
+            one_in,
+            // The xorshift state must not be zero.
+            state: Cell::new(seed | 1),
+            injected_faults: Cell::new(0),
+        })
+    }
+
+    /// Returns the number of faults injected so far.
+    pub fn injected_faults(&self) -> u32 {
+        self.injected_faults.get()
+    }
+}
+
+impl<D: crate::fake::SyscallDriver> FaultyDriver<D> {
+    // xorshift64, see https://www.jstatsoft.org/article/view/v008i14
+    fn next_random(&self) -> u64 {
+        let mut x = self.state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state.set(x);
+        x
+    }
+}
+
+impl<D: crate::fake::SyscallDriver> crate::fake::SyscallDriver for FaultyDriver<D> {
+    fn info(&self) -> DriverInfo {
+        self.inner.info()
+    }
+
+    fn register(&self, share_ref: DriverShareRef) {
+        self.inner.register(share_ref);
+    }
+
+    fn command(&self, command_id: u32, argument0: u32, argument1: u32) -> CommandReturn {
+        let random = self.next_random();
+        if random % self.one_in.max(1) as u64 == 0 {
+            self.injected_faults.set(self.injected_faults.get() + 1);
+            let error = FAULTS[(random >> 32) as usize % FAULTS.len()];
+            return crate::command_return::failure(error);
+        }
+        self.inner.command(command_id, argument0, argument1)
+    }
+
+    fn allow_readonly(
+        &self,
+        buffer_num: u32,
+        buffer: RoAllowBuffer,
+    ) -> Result<RoAllowBuffer, (RoAllowBuffer, ErrorCode)> {
+        self.inner.allow_readonly(buffer_num, buffer)
+    }
+
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        self.inner.allow_readwrite(buffer_num, buffer)
+    }
+}
+
+#[cfg(all(test, feature = "button"))]
+mod tests;
+
+const FAULTS: [ErrorCode; 3] = [ErrorCode::Busy, ErrorCode::NoMem, ErrorCode::Fail];
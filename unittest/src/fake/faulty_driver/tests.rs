@@ -0,0 +1,64 @@
+use crate::fake;
+use libtock_platform::{ErrorCode, Syscalls};
+
+const BUTTONS_DRIVER_NUM: u32 = 3;
+const BUTTONS_COUNT: u32 = 0;
+
+// Reads the button count, retrying on failure.
+fn count_with_retry(attempts: u32) -> Result<u32, ErrorCode> {
+    let mut result = Err(ErrorCode::Fail);
+    for _ in 0..attempts {
+        result = fake::Syscalls::command(BUTTONS_DRIVER_NUM, BUTTONS_COUNT, 0, 0).to_result();
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
+#[test]
+fn retry_succeeds() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::FaultyDriver::new(fake::Buttons::<4>::new(), 42, 2);
+    kernel.add_driver(&driver);
+
+    let mut failures = 0;
+    for _ in 0..20 {
+        assert_eq!(count_with_retry(20), Ok(4));
+        if fake::Syscalls::command(BUTTONS_DRIVER_NUM, BUTTONS_COUNT, 0, 0).is_failure() {
+            failures += 1;
+        }
+    }
+    assert!(failures > 0);
+    assert!(driver.injected_faults() >= failures);
+}
+
+#[test]
+fn deterministic() {
+    let errors = |seed| {
+        let kernel = fake::Kernel::new();
+        let driver = fake::FaultyDriver::new(fake::Buttons::<4>::new(), seed, 3);
+        kernel.add_driver(&driver);
+        (0..32)
+            .map(|_| fake::Syscalls::command(BUTTONS_DRIVER_NUM, BUTTONS_COUNT, 0, 0).get_failure())
+            .collect::<Vec<_>>()
+    };
+    let first = errors(7);
+    assert_eq!(first, errors(7));
+    assert!(first.iter().any(|error| error.is_none()));
+    assert!(first.iter().flatten().all(|error| [
+        ErrorCode::Busy,
+        ErrorCode::NoMem,
+        ErrorCode::Fail
+    ]
+    .contains(error)));
+}
+
+#[test]
+fn always_fail() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::FaultyDriver::new(fake::Buttons::<4>::new(), 1, 1);
+    kernel.add_driver(&driver);
+    assert!(count_with_retry(5).is_err());
+    assert_eq!(driver.injected_faults(), 5);
+}
@@ -251,7 +251,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 4;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::GPIO;
 
 // Command IDs
 const GPIO_COUNT: u32 = 0;
@@ -35,6 +35,8 @@ impl Kernel {
                 create_location: std::panic::Location::caller(),
                 drivers: Default::default(),
                 expected_syscalls: Default::default(),
+                last_allows: Default::default(),
+                registers_32_bit: false,
                 syscall_log: Vec::new(),
                 upcall_queue: Default::default(),
             }))
@@ -49,6 +51,24 @@ impl Kernel {
         Kernel { _private: () }
     }
 
+    /// Creates a `Kernel` with fake ambient light and temperature drivers,
+    /// whose next readings are `lux` and `centi_celsius` (in hundredths of a
+    /// degree), matching `libtock_board::Environment`. Use
+    /// `fake::Kernel::new` and add the drivers directly to access them after
+    /// construction.
+    #[cfg(all(feature = "ambient_light", feature = "temperature"))]
+    #[track_caller]
+    pub fn with_environment(lux: u32, centi_celsius: i32) -> Kernel {
+        let kernel = Kernel::new();
+        let ambient_light = crate::fake::AmbientLight::new();
+        ambient_light.set_value_sync(lux);
+        kernel.add_driver(&ambient_light);
+        let temperature_driver = crate::fake::Temperature::new();
+        temperature_driver.set_value_sync(centi_celsius);
+        kernel.add_driver(&temperature_driver);
+        kernel
+    }
+
     /// Adds a `fake::SyscallDriver` to this `fake::Kernel`. After the call,
     /// system calls with this driver's ID will be routed to the driver.
     // TODO: It's kind of weird to implicitly clone the RC by default. Instead,
@@ -104,10 +124,136 @@ impl Kernel {
         });
     }
 
+    // Removes the most recently added expected syscall, if it has not been
+    // consumed yet. Used by `ExpectBuilder`'s modifiers.
+    pub(crate) fn take_last_expected_syscall(&self) -> Option<ExpectedSyscall> {
+        with_kernel_data(|kernel_data| kernel_data.unwrap().expected_syscalls.pop_back())
+    }
+
+    /// Emulates the registers of a 32-bit target in Command: argument
+    /// registers that hold more than 32 bits are truncated to their low 32
+    /// bits, as they would be on the device, instead of panicking. The
+    /// truncated values are what the driver, the expected syscall queue and
+    /// the system call log see. Off by default.
+    pub fn set_32_bit_registers(&self, enabled: bool) {
+        with_kernel_data(|kernel_data| kernel_data.unwrap().registers_32_bit = enabled);
+    }
+
+    /// Returns an `ExpectBuilder`, which describes a sequence of expected
+    /// system calls more concisely than repeated calls to
+    /// `add_expected_syscall`.
+    pub fn expect(&self) -> crate::ExpectBuilder<'_> {
+        crate::ExpectBuilder::new(self)
+    }
+
     /// Returns the system call log and empties it.
     pub fn take_syscall_log(&self) -> Vec<SyscallLogEntry> {
         with_kernel_data(|kernel_data| std::mem::take(&mut kernel_data.unwrap().syscall_log))
     }
+
+    /// Panics if any system call has been recorded in the system call log
+    /// since it was last taken. Useful to check that an API does not touch
+    /// the kernel until it is used. Does not empty the log.
+    #[track_caller]
+    pub fn assert_no_syscalls(&self) {
+        with_kernel_data(|kernel_data| {
+            let syscall_log = &kernel_data.unwrap().syscall_log;
+            assert!(
+                syscall_log.is_empty(),
+                "Expected no system calls, but {} were made: {:?}",
+                syscall_log.len(),
+                syscall_log
+            );
+        });
+    }
+
+    /// Panics if the contents of the last buffer shared with the given driver
+    /// through the given Allow buffer number differ from `expected`. The
+    /// contents are recorded when the buffer is un-shared (usually at the end
+    /// of a `share::scope`), so for Read-Write Allow they include any data the
+    /// driver wrote. Empty buffers are not recorded.
+    #[track_caller]
+    pub fn assert_last_allow(&self, driver_num: u32, buffer_num: u32, expected: &[u8]) {
+        let actual = with_kernel_data(|kernel_data| {
+            kernel_data
+                .unwrap()
+                .last_allows
+                .get(&(driver_num, buffer_num))
+                .cloned()
+        });
+        let actual = match actual {
+            None => panic!(
+                "No buffer was shared with driver {:#x} through Allow buffer {}",
+                driver_num, buffer_num
+            ),
+            Some(actual) => actual,
+        };
+        if actual != expected {
+            let first_difference = actual
+                .iter()
+                .zip(expected)
+                .position(|(a, e)| a != e)
+                .unwrap_or_else(|| actual.len().min(expected.len()));
+            panic!(
+                "Allow buffer {} of driver {:#x} differs from the expected contents \
+                 at byte {}:\n  expected: {:02x?}\n    actual: {:02x?}",
+                buffer_num, driver_num, first_difference, expected, actual
+            );
+        }
+    }
+
+    /// Returns true if a non-null upcall is currently registered in the given
+    /// driver's subscribe slot. Returns false if the slot was never
+    /// subscribed, was reset to the null upcall (e.g. when a `share::scope`
+    /// ends), or if no such driver exists.
+    ///
+    /// The real Tock kernel has no system call that reports this, so this is
+    /// only available in unit tests.
+    pub fn has_subscription(&self, driver_num: u32, subscribe_num: u32) -> bool {
+        with_kernel_data(|kernel_data| {
+            kernel_data
+                .unwrap()
+                .drivers
+                .get(&driver_num)
+                .and_then(|driver_data| driver_data.upcalls.get(&subscribe_num))
+                .map_or(false, |upcall| !upcall.is_null())
+        })
+    }
+
+    /// Saves the current expected syscall queue and system call log, so that
+    /// they can later be rolled back using `restore`. This allows a single test
+    /// to run several independent interaction sequences.
+    ///
+    /// The checkpoint does not include the upcall queue, registered upcalls,
+    /// shared buffers, or the internal state of the fake drivers (such as a
+    /// fake alarm's clock). Tests should leave those in a known state (e.g. by
+    /// draining all queued upcalls) before restoring.
+    pub fn checkpoint(&self) -> Checkpoint {
+        with_kernel_data(|kernel_data| {
+            let kernel_data = kernel_data.unwrap();
+            Checkpoint {
+                expected_syscalls: kernel_data.expected_syscalls.clone(),
+                syscall_log: kernel_data.syscall_log.clone(),
+            }
+        })
+    }
+
+    /// Rolls the expected syscall queue and system call log back to the state
+    /// they had when `checkpoint` was called.
+    pub fn restore(&self, checkpoint: Checkpoint) {
+        with_kernel_data(|kernel_data| {
+            let kernel_data = kernel_data.unwrap();
+            kernel_data.expected_syscalls = checkpoint.expected_syscalls;
+            kernel_data.syscall_log = checkpoint.syscall_log;
+        });
+    }
+}
+
+/// A snapshot of a `fake::Kernel`'s expected syscall queue and system call log,
+/// returned by `Kernel::checkpoint`.
+pub struct Checkpoint {
+    expected_syscalls: std::collections::VecDeque<ExpectedSyscall>,
+    syscall_log: Vec<SyscallLogEntry>,
 }
 
 impl Drop for Kernel {
@@ -45,3 +45,269 @@ fn syscall_log() {
     assert_eq!(kernel.take_syscall_log(), [YieldNoWait, YieldWait]);
     assert_eq!(kernel.take_syscall_log(), []);
 }
+
+#[test]
+fn assert_no_syscalls() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    kernel.assert_no_syscalls();
+    fake::Syscalls::yield_no_wait();
+    let result = std::panic::catch_unwind(|| kernel.assert_no_syscalls());
+    assert!(result.is_err());
+    // The failed assertion leaves the log in place.
+    assert_eq!(kernel.take_syscall_log(), [SyscallLogEntry::YieldNoWait]);
+    kernel.assert_no_syscalls();
+}
+
+#[cfg(all(feature = "ambient_light", feature = "temperature"))]
+#[test]
+fn with_environment() {
+    use core::cell::Cell;
+    use libtock_platform::{share, DefaultConfig, Syscalls};
+
+    // Reads a sensor whose read command is 1 and whose readings are delivered
+    // through subscribe number 0, as the environment sensors do.
+    fn read<const DRIVER_NUM: u32>() -> Option<u32> {
+        let reading = Cell::<Option<(u32,)>>::new(None);
+        share::scope(|subscribe| {
+            fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, &reading)
+                .unwrap();
+            assert!(fake::Syscalls::command(DRIVER_NUM, 1, 0, 0).is_success());
+            fake::Syscalls::yield_no_wait();
+        });
+        reading.get().map(|(value,)| value)
+    }
+
+    let _kernel = fake::Kernel::with_environment(450, -250);
+    assert_eq!(read::<0x60002>(), Some(450));
+    assert_eq!(read::<0x60000>().map(|value| value as i32), Some(-250));
+}
+
+// Uses fake::Buttons as an arbitrary driver.
+#[cfg(feature = "button")]
+#[test]
+fn checkpoint_restore() {
+    use libtock_platform::{ErrorCode, Syscalls};
+    let kernel = fake::Kernel::new();
+    let buttons = fake::Buttons::<2>::new();
+    kernel.add_driver(&buttons);
+
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: 3,
+        command_id: 1,
+        argument0: 0,
+        argument1: 0,
+        override_return: None,
+    });
+    let checkpoint = kernel.checkpoint();
+
+    // First scenario: enabling the interrupt succeeds.
+    assert!(fake::Syscalls::command(3, 1, 0, 0).is_success());
+    assert!(buttons.get_button_state(0).unwrap().interrupt_enabled);
+    assert_eq!(kernel.take_syscall_log().len(), 1);
+    kernel.restore(checkpoint);
+
+    // Second scenario: the expected syscall is back in the queue, and can be
+    // made to fail.
+    let checkpoint = kernel.checkpoint();
+    with_kernel_data(|kernel_data| {
+        let kernel_data = kernel_data.unwrap();
+        assert_eq!(kernel_data.expected_syscalls.len(), 1);
+        assert!(kernel_data.syscall_log.is_empty());
+        kernel_data.expected_syscalls[0] = ExpectedSyscall::Command {
+            driver_id: 3,
+            command_id: 1,
+            argument0: 0,
+            argument1: 0,
+            override_return: Some(crate::command_return::failure(ErrorCode::Fail)),
+        };
+    });
+    assert_eq!(
+        fake::Syscalls::command(3, 1, 0, 0).get_failure(),
+        Some(ErrorCode::Fail)
+    );
+    assert!(fake::Syscalls::command(3, 3, 1, 0).is_success_u32());
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [
+            SyscallLogEntry::Command {
+                driver_id: 3,
+                command_id: 1,
+                argument0: 0,
+                argument1: 0,
+            },
+            SyscallLogEntry::Command {
+                driver_id: 3,
+                command_id: 3,
+                argument0: 1,
+                argument1: 0,
+            },
+        ]
+    );
+
+    // Restoring puts back the queue from the second checkpoint.
+    kernel.restore(checkpoint);
+    with_kernel_data(|kernel_data| {
+        let kernel_data = kernel_data.unwrap();
+        assert_eq!(kernel_data.expected_syscalls.len(), 1);
+        assert!(kernel_data.syscall_log.is_empty());
+    });
+}
+
+#[cfg(feature = "button")]
+#[test]
+fn has_subscription() {
+    use libtock_platform::{share, DefaultConfig, Syscalls};
+    let kernel = fake::Kernel::new();
+    let buttons = fake::Buttons::<2>::new();
+    kernel.add_driver(&buttons);
+    assert!(!kernel.has_subscription(3, 0));
+    // Unknown drivers never have a subscription.
+    assert!(!kernel.has_subscription(0x12345, 0));
+
+    let listener = core::cell::Cell::<Option<(u32, u32)>>::new(None);
+    share::scope(|subscribe| {
+        assert_eq!(
+            fake::Syscalls::subscribe::<_, _, DefaultConfig, 3, 0>(subscribe, &listener),
+            Ok(())
+        );
+        assert!(kernel.has_subscription(3, 0));
+        assert!(!kernel.has_subscription(3, 1));
+    });
+    // The end of the scope unsubscribes by registering the null upcall.
+    assert!(!kernel.has_subscription(3, 0));
+}
+
+#[cfg(feature = "button")]
+#[test]
+fn assert_last_allow() {
+    use libtock_platform::{share, DefaultConfig, Syscalls};
+    use std::panic::catch_unwind;
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+    let buttons = fake::Buttons::<2>::new();
+    kernel.add_driver(&buttons);
+
+    // Read-Only Allow records the bytes the app shared.
+    share::scope(|allow_ro| {
+        fake::Syscalls::allow_ro::<DefaultConfig, 1, 1>(allow_ro, b"hello").unwrap();
+    });
+    kernel.assert_last_allow(1, 1, b"hello");
+    let message = catch_unwind(|| kernel.assert_last_allow(1, 1, b"help"))
+        .expect_err("failed to catch mismatched buffer")
+        .downcast::<String>()
+        .expect("wrong panic payload type");
+    assert!(message.contains("differs from the expected contents at byte 3"));
+    assert!(message.contains("expected: [68, 65, 6c, 70]"));
+    assert!(message.contains("actual: [68, 65, 6c, 6c, 6f]"));
+
+    // Read-Write Allow records the data the driver wrote.
+    let mut buffer = [0; 12];
+    share::scope(|allow_rw| {
+        fake::Syscalls::allow_rw::<DefaultConfig, 3, 0>(allow_rw, &mut buffer).unwrap();
+        assert!(fake::Syscalls::command(3, 4, 0, 0).is_success());
+        buttons.push_history_event(1, true, 0x0201);
+    });
+    kernel.assert_last_allow(3, 0, &[1, 0, 0, 0, 1, 0, 0, 0, 1, 2, 0, 0]);
+
+    assert!(catch_unwind(|| kernel.assert_last_allow(3, 1, &[]))
+        .expect_err("failed to catch missing buffer")
+        .downcast_ref::<String>()
+        .expect("wrong panic payload type")
+        .contains("No buffer was shared"));
+}
+
+#[test]
+fn expect_builder() {
+    use crate::command_return;
+    use libtock_platform::{ErrorCode, YieldNoWaitReturn};
+    let queue =
+        || with_kernel_data(|kernel_data| format!("{:?}", kernel_data.unwrap().expected_syscalls));
+
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::Subscribe {
+        driver_num: 3,
+        subscribe_num: 0,
+        skip_with_error: None,
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: 3,
+        command_id: 1,
+        argument0: 2,
+        argument1: 0,
+        override_return: None,
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: 3,
+        command_id: 3,
+        argument0: 0,
+        argument1: 0,
+        override_return: Some(command_return::success_u32(1)),
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::YieldNoWait {
+        override_return: Some(YieldNoWaitReturn::Upcall),
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::YieldWait { skip_upcall: true });
+    kernel.add_expected_syscall(ExpectedSyscall::AllowRo {
+        driver_num: 1,
+        buffer_num: 1,
+        return_error: Some(ErrorCode::Busy),
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::AllowRw {
+        driver_num: 1,
+        buffer_num: 1,
+        return_error: None,
+    });
+    let manual = queue();
+    drop(kernel);
+
+    let kernel = fake::Kernel::new();
+    kernel
+        .expect()
+        .subscribe(3, 0)
+        .command(3, 1)
+        .args(2, 0)
+        .command(3, 3)
+        .returns(command_return::success_u32(1))
+        .yield_no_wait()
+        .returns(YieldNoWaitReturn::Upcall)
+        .yield_wait()
+        .skip_upcall()
+        .allow_ro(1, 1)
+        .fails_with(ErrorCode::Busy)
+        .allow_rw(1, 1);
+    assert_eq!(queue(), manual);
+
+    // Modifiers must match the system call they apply to.
+    assert!(std::panic::catch_unwind(|| {
+        let _ = kernel.expect().subscribe(3, 0).args(1, 2);
+    })
+    .expect_err("failed to catch misplaced modifier")
+    .downcast_ref::<String>()
+    .expect("wrong panic payload type")
+    .contains("args() applies to a Command"));
+    assert_eq!(queue(), manual);
+    drop(kernel);
+
+    // Calls are queued as soon as they are described, so a builder kept in a
+    // binding does not hold them back from the system calls made meanwhile.
+    let kernel = fake::Kernel::new();
+    let builder = kernel.expect().subscribe(3, 0);
+    assert_eq!(queue().matches("Subscribe").count(), 1);
+    let builder = builder.command(3, 1);
+    let _ = builder.args(2, 0);
+    assert_eq!(queue().matches("Command").count(), 1);
+    assert!(queue().contains("argument0: 2"));
+
+    // A modifier cannot change a call that a system call already consumed.
+    let builder = kernel.expect().yield_no_wait();
+    with_kernel_data(|kernel_data| kernel_data.unwrap().expected_syscalls.clear());
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = builder.returns(YieldNoWaitReturn::Upcall);
+    }))
+    .expect_err("failed to catch a modifier after the call was consumed")
+    .downcast_ref::<String>()
+    .expect("wrong panic payload type")
+    .contains("consumed"));
+}
@@ -70,7 +70,7 @@ mod tests;
 // Implementation details below
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 2;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::LEDS;
 
 // Command numbers
 const DRIVER_CHECK: u32 = 0;
@@ -69,7 +69,7 @@ impl core::fmt::Display for Message {
 #[cfg(test)]
 mod tests;
 
-const DRIVER_NUM: u32 = 8;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::LOW_LEVEL_DEBUG;
 
 // Command numbers
 const DRIVER_CHECK: u32 = 0;
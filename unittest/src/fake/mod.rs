@@ -10,30 +10,42 @@
 //! (e.g. `fake::Console`).
 
 mod alarm;
+#[cfg(feature = "ambient_light")]
 mod ambient_light;
+#[cfg(feature = "button")]
 mod buttons;
 mod console;
+mod faulty_driver;
 mod gpio;
 mod kernel;
 mod leds;
 mod low_level_debug;
+#[cfg(feature = "proximity")]
 mod proximity;
 mod syscall_driver;
 mod syscalls;
+#[cfg(feature = "temperature")]
 mod temperature;
 
 pub use alarm::Alarm;
+#[cfg(feature = "ambient_light")]
 pub use ambient_light::AmbientLight;
+#[cfg(feature = "button")]
 pub use buttons::Buttons;
 pub use console::Console;
+pub use faulty_driver::FaultyDriver;
 pub use gpio::{Gpio, GpioMode, InterruptEdge, PullMode};
-pub use kernel::Kernel;
+pub use kernel::{Checkpoint, Kernel};
 pub use leds::Leds;
 pub use low_level_debug::{LowLevelDebug, Message};
+#[cfg(feature = "proximity")]
 pub use proximity::Proximity;
 pub use syscall_driver::SyscallDriver;
 pub use syscalls::Syscalls;
+#[cfg(feature = "temperature")]
 pub use temperature::Temperature;
 
+#[cfg(test)]
+mod driver_numbers_tests;
 #[cfg(test)]
 mod kernel_tests;
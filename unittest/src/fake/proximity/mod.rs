@@ -115,7 +115,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 0x60005;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::PROXIMITY;
 
 // Command IDs
 
@@ -43,7 +43,9 @@ pub(super) unsafe fn allow_ro(
                     return Err(error_code);
                 }
             }
-            Some(expected_syscall) => expected_syscall.panic_wrong_call("Read-Only Allow"),
+            Some(expected_syscall) => {
+                expected_syscall.panic_wrong_call(kernel_data.syscall_log.last().unwrap())
+            }
         };
 
         let driver = match kernel_data.drivers.get(&driver_num) {
@@ -78,6 +80,11 @@ pub(super) unsafe fn allow_ro(
     let (address_out, len_out) = with_kernel_data(|option_kernel_data| {
         let kernel_data = option_kernel_data
             .expect("fake::Kernel dropped during fake::SyscallDriver::allow_readonly");
+        if error_code.is_none() && !buffer_out.is_empty() {
+            kernel_data
+                .last_allows
+                .insert((driver_num, buffer_num), buffer_out.to_vec());
+        }
         kernel_data.allow_db.remove_ro_buffer(buffer_out)
     });
 
@@ -29,7 +29,10 @@ fn expected_wrong() {
     .expect_err("failed to catch wrong syscall class")
     .downcast_ref::<String>()
     .expect("wrong panic payload type")
-    .contains("but Read-Write Allow was called instead"));
+    .contains(
+        "Expected Command{driver=0x1 cmd=2 arg0=3 arg1=4}, but \
+         AllowRw{driver=0x1 buffer=2 len=0} was called instead."
+    ));
 
     kernel.add_expected_syscall(ExpectedSyscall::AllowRw {
         driver_num: 1,
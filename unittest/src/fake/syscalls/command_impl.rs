@@ -13,8 +13,10 @@ pub(super) fn command(
 ) -> [Register; 4] {
     let driver_id = driver_id.try_into().expect("Too large driver ID");
     let command_id = command_id.try_into().expect("Too large command ID");
-    let argument0 = argument0.try_into().expect("Too large argument 0");
-    let argument1 = argument1.try_into().expect("Too large argument 1");
+    let registers_32_bit =
+        with_kernel_data(|kernel_data| kernel_data.map_or(false, |data| data.registers_32_bit));
+    let argument0 = argument(argument0, registers_32_bit, "Too large argument 0");
+    let argument1 = argument(argument1, registers_32_bit, "Too large argument 1");
 
     let (driver, override_return) = with_kernel_data(|option_kernel_data| {
         let kernel_data = option_kernel_data.expect("Command called but no fake::Kernel exists");
@@ -58,7 +60,9 @@ pub(super) fn command(
                 );
                 override_return
             }
-            Some(expected_syscall) => expected_syscall.panic_wrong_call("Command"),
+            Some(expected_syscall) => {
+                expected_syscall.panic_wrong_call(kernel_data.syscall_log.last().unwrap())
+            }
         };
 
         let driver = kernel_data
@@ -82,3 +86,12 @@ pub(super) fn command(
     let r0: u32 = return_variant.into();
     [r0.into(), r1.into(), r2.into(), r3.into()]
 }
+
+// Converts an argument register into the value the driver receives. On a
+// 32-bit target, the register only holds the low 32 bits of the argument.
+fn argument(register: Register, registers_32_bit: bool, too_large: &str) -> u32 {
+    match registers_32_bit {
+        true => usize::from(register) as u32,
+        false => register.try_into().expect(too_large),
+    }
+}
@@ -6,9 +6,6 @@ use libtock_platform::{
 use std::convert::TryInto;
 use std::panic::catch_unwind;
 
-// TODO: When another system call is implemented, add a test for the case
-// where a different system call class is expected.
-
 #[test]
 fn driver_support() {
     let kernel = fake::Kernel::new();
@@ -98,6 +95,27 @@ fn expected_wrong_command() {
     );
 }
 
+// Tests command when a different system call class is expected.
+#[test]
+fn expected_wrong_class() {
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::Subscribe {
+        driver_num: 3,
+        subscribe_num: 0,
+        skip_with_error: None,
+    });
+    assert!(
+        catch_unwind(|| command(3u32.into(), 1u32.into(), 0u32.into(), 0u32.into()))
+            .expect_err("failed to catch wrong syscall class")
+            .downcast_ref::<String>()
+            .expect("wrong panic payload type")
+            .contains(
+                "Expected Subscribe{driver=0x3 sub=0}, but \
+                 Command{driver=0x3 cmd=1 arg0=0 arg1=0} was called instead."
+            )
+    );
+}
+
 #[test]
 fn no_kernel() {
     let result = catch_unwind(|| command(1u32.into(), 1u32.into(), 0u32.into(), 0u32.into()));
@@ -231,3 +249,30 @@ fn too_large_driver_id() {
         .expect("wrong panic payload type")
         .contains("Too large driver ID"));
 }
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn truncated_arguments() {
+    let kernel = fake::Kernel::new();
+    kernel.set_32_bit_registers(true);
+    // A packed value whose high bits do not fit in a 32-bit register.
+    let packed = 0x0000_0001_0000_0005usize;
+    command(1u32.into(), 1u32.into(), packed.into(), (!0usize).into());
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Command {
+            driver_id: 1,
+            command_id: 1,
+            argument0: 5,
+            argument1: u32::MAX,
+        }]
+    );
+
+    kernel.set_32_bit_registers(false);
+    let result = catch_unwind(|| command(1u32.into(), 1u32.into(), packed.into(), 0u32.into()));
+    assert!(result
+        .expect_err("failed to catch too-large argument0")
+        .downcast_ref::<String>()
+        .expect("wrong panic payload type")
+        .contains("Too large argument 0"));
+}
@@ -44,7 +44,9 @@ pub(super) unsafe fn subscribe(
                 );
                 skip_with_error
             }
-            Some(expected_syscall) => expected_syscall.panic_wrong_call("Subscribe"),
+            Some(expected_syscall) => {
+                expected_syscall.panic_wrong_call(kernel_data.syscall_log.last().unwrap())
+            }
         };
 
         // Retrieve the number of upcalls for this driver, or None if there is
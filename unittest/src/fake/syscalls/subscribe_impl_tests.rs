@@ -25,7 +25,7 @@ fn expected_wrong() {
         .expect_err("failed to catch wrong syscall")
         .downcast_ref::<String>()
         .expect("wrong panic payload type")
-        .contains("but Subscribe was called instead"));
+        .contains("Expected YieldWait, but Subscribe{driver=0x1 sub=2} was called instead."));
 
     let expected_syscall = ExpectedSyscall::Subscribe {
         driver_num: 1,
@@ -19,7 +19,9 @@ pub(super) unsafe fn yield_no_wait(return_ptr: *mut libtock_platform::YieldNoWai
         match kernel_data.expected_syscalls.pop_front() {
             None => None,
             Some(ExpectedSyscall::YieldNoWait { override_return }) => override_return,
-            Some(expected_syscall) => expected_syscall.panic_wrong_call("yield-no-wait"),
+            Some(expected_syscall) => {
+                expected_syscall.panic_wrong_call(kernel_data.syscall_log.last().unwrap())
+            }
         }
     });
 
@@ -45,7 +47,9 @@ pub(super) fn yield_wait() {
         match kernel_data.expected_syscalls.pop_front() {
             None => false,
             Some(ExpectedSyscall::YieldWait { skip_upcall }) => skip_upcall,
-            Some(expected_syscall) => expected_syscall.panic_wrong_call("yield-wait"),
+            Some(expected_syscall) => {
+                expected_syscall.panic_wrong_call(kernel_data.syscall_log.last().unwrap())
+            }
         }
     });
 
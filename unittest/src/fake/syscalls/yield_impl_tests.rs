@@ -73,7 +73,7 @@ fn yield_no_wait_test() {
         .expect_err("failed to catch mismatched expected syscall")
         .downcast_ref::<String>()
         .expect("wrong panic payload type")
-        .contains("yield-no-wait was called instead"));
+        .contains("Expected YieldWait, but YieldNoWait was called instead."));
     assert_eq!(kernel.take_syscall_log(), [SyscallLogEntry::YieldNoWait]);
 
     // Upcall structures for using copy_args.
@@ -153,7 +153,7 @@ fn yield_wait_test() {
         .expect_err("failed to catch mismatched expected syscall")
         .downcast_ref::<String>()
         .expect("wrong panic payload type")
-        .contains("yield-wait was called instead"));
+        .contains("Expected YieldNoWait, but YieldWait was called instead."));
     assert_eq!(kernel.take_syscall_log(), [SyscallLogEntry::YieldWait]);
 
     // Upcall structures for using copy_args.
@@ -77,7 +77,7 @@ mod tests;
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
 
-const DRIVER_NUM: u32 = 0x60000;
+const DRIVER_NUM: u32 = libtock_platform::driver_numbers::TEMPERATURE;
 
 // Command IDs
 
@@ -21,6 +21,14 @@ pub(crate) struct KernelData {
 
     pub drivers: std::collections::HashMap<u32, DriverData>,
     pub expected_syscalls: std::collections::VecDeque<crate::ExpectedSyscall>,
+
+    // The contents of the last non-empty buffer un-shared through each Allow
+    // buffer number. The key is (driver number, buffer number).
+    pub last_allows: std::collections::HashMap<(u32, u32), Vec<u8>>,
+
+    // If true, Command truncates its argument registers to 32 bits instead of
+    // panicking on larger values. Set by `fake::Kernel::set_32_bit_registers`.
+    pub registers_32_bit: bool,
     pub syscall_log: Vec<crate::SyscallLogEntry>,
     pub upcall_queue: crate::upcall::UpcallQueue,
 }
@@ -6,22 +6,32 @@
 mod allow_db;
 pub mod command_return;
 mod driver_info;
+pub mod executor;
 #[cfg(not(miri))]
 mod exit_test;
+mod expect_builder;
 mod expected_syscall;
 pub mod fake;
 mod kernel_data;
+mod marker_traits;
 mod share_data;
+mod syscall_diff;
 mod syscall_log;
+mod test_lock;
 pub mod upcall;
 
 pub use allow_db::{RoAllowBuffer, RwAllowBuffer};
 pub use driver_info::DriverInfo;
 #[cfg(not(miri))]
 pub use exit_test::{exit_test, ExitCall};
+pub use expect_builder::{ExpectBuilder, Return};
 pub use expected_syscall::ExpectedSyscall;
 pub use share_data::DriverShareRef;
+pub use syscall_diff::{DiffLine, SyscallDiff};
 pub use syscall_log::SyscallLogEntry;
+pub use test_lock::{TestLock, TestLockGuard};
 
 #[cfg(test)]
 mod allow_db_test;
+#[cfg(test)]
+mod syscall_diff_test;
@@ -0,0 +1,37 @@
+//! Compile-time checks of which marker traits (such as `Send` and `Sync`) a
+//! type implements. A failed check is a compile error in the test that makes
+//! it, so these are used to keep the auto traits of driver types from changing
+//! unnoticed.
+
+/// Fails to compile unless `$type` implements every listed trait.
+///
+/// ```ignore
+/// libtock_unittest::assert_impl!(Lux: Send, Sync);
+/// ```
+#[macro_export]
+macro_rules! assert_impl {
+    ($type:ty: $($trait:path),+ $(,)?) => {{
+        fn assert_impl<T: ?Sized $(+ $trait)+>() {}
+        assert_impl::<$type>();
+    }};
+}
+
+/// Fails to compile if `$type` implements any of the listed traits.
+///
+/// ```ignore
+/// libtock_unittest::assert_not_impl!(PoweredUp<fake::Syscalls>: Send, Sync);
+/// ```
+#[macro_export]
+macro_rules! assert_not_impl {
+    ($type:ty: $($trait:path),+ $(,)?) => {$({
+        // If $type implements $trait, both impls apply and the type parameter
+        // of AmbiguousIfImpl cannot be inferred.
+        trait AmbiguousIfImpl<A> {
+            fn some_item() {}
+        }
+        impl<T: ?Sized> AmbiguousIfImpl<()> for T {}
+        struct Invalid;
+        impl<T: ?Sized + $trait> AmbiguousIfImpl<Invalid> for T {}
+        let _ = <$type as AmbiguousIfImpl<_>>::some_item;
+    })+};
+}
@@ -0,0 +1,111 @@
+use crate::{ExpectedSyscall, SyscallLogEntry};
+
+/// A comparison between the system calls a test made (as returned by
+/// `fake::Kernel::take_syscall_log`) and the system calls it was expected to
+/// make. Displaying it gives one line per system call, in the style of a
+/// unified diff:
+///
+/// ```text
+///   Command{driver=0x3 cmd=1 arg0=0 arg1=0}
+/// - Subscribe{driver=0x3 sub=0}
+/// + YieldNoWait
+/// ```
+///
+/// Unprefixed lines were made as expected, `-` lines were expected but not
+/// made, and `+` lines were made but not expected.
+///
+/// # Example
+/// ```ignore
+/// let log = kernel.take_syscall_log();
+/// let diff = SyscallDiff::new(&log, &expected);
+/// assert!(diff.is_match(), "Unexpected system calls:\n{}", diff);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SyscallDiff {
+    lines: Vec<DiffLine>,
+}
+
+/// A single line of a `SyscallDiff`.
+#[derive(Clone, Debug)]
+pub enum DiffLine {
+    /// A system call that was made as expected.
+    Matched(SyscallLogEntry),
+    /// An expected system call that was not made.
+    Missing(ExpectedSyscall),
+    /// A system call that was made but not expected.
+    Extra(SyscallLogEntry),
+}
+
+impl SyscallDiff {
+    /// Compares `log` against `expected`, matching up as many system calls as
+    /// possible while keeping both in order.
+    pub fn new(log: &[SyscallLogEntry], expected: &[ExpectedSyscall]) -> SyscallDiff {
+        // Longest common subsequence table: common[i][j] is the number of
+        // matches between log[i..] and expected[j..].
+        let mut common = vec![vec![0usize; expected.len() + 1]; log.len() + 1];
+        for i in (0..log.len()).rev() {
+            for j in (0..expected.len()).rev() {
+                common[i][j] = if expected[j].matches(&log[i]) {
+                    common[i + 1][j + 1] + 1
+                } else {
+                    common[i + 1][j].max(common[i][j + 1])
+                };
+            }
+        }
+
+        let mut lines = Vec::with_capacity(log.len().max(expected.len()));
+        let (mut i, mut j) = (0, 0);
+        while i < log.len() && j < expected.len() {
+            if expected[j].matches(&log[i]) {
+                lines.push(DiffLine::Matched(log[i].clone()));
+                i += 1;
+                j += 1;
+            } else if common[i][j + 1] >= common[i + 1][j] {
+                lines.push(DiffLine::Missing(expected[j]));
+                j += 1;
+            } else {
+                lines.push(DiffLine::Extra(log[i].clone()));
+                i += 1;
+            }
+        }
+        lines.extend(expected[j..].iter().map(|&e| DiffLine::Missing(e)));
+        lines.extend(log[i..].iter().cloned().map(DiffLine::Extra));
+        SyscallDiff { lines }
+    }
+
+    /// Returns true if the log matched the expectations exactly.
+    pub fn is_match(&self) -> bool {
+        self.divergence().is_none()
+    }
+
+    /// Returns the index of the first line that is not `Matched`, or `None` if
+    /// every line matched.
+    pub fn divergence(&self) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| !matches!(line, DiffLine::Matched(_)))
+    }
+
+    pub fn lines(&self) -> &[DiffLine] {
+        &self.lines
+    }
+}
+
+impl std::fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DiffLine::Matched(entry) => write!(f, "  {}", entry),
+            DiffLine::Missing(expected) => write!(f, "- {}", expected),
+            DiffLine::Extra(entry) => write!(f, "+ {}", entry),
+        }
+    }
+}
+
+impl std::fmt::Display for SyscallDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{fake, DiffLine, ExpectedSyscall, SyscallDiff, SyscallLogEntry};
+use libtock_platform::Syscalls;
+
+fn command(command_id: u32) -> ExpectedSyscall {
+    ExpectedSyscall::Command {
+        driver_id: 3,
+        command_id,
+        argument0: 0,
+        argument1: 0,
+        override_return: None,
+    }
+}
+
+#[test]
+fn matching_log() {
+    let kernel = fake::Kernel::new();
+    let _ = fake::Syscalls::command(3, 0, 0, 0);
+    fake::Syscalls::yield_no_wait();
+    let expected = [
+        command(0),
+        ExpectedSyscall::YieldNoWait {
+            override_return: None,
+        },
+    ];
+    let diff = SyscallDiff::new(&kernel.take_syscall_log(), &expected);
+    assert!(diff.is_match());
+    assert_eq!(diff.divergence(), None);
+    assert_eq!(
+        diff.to_string(),
+        "  Command{driver=0x3 cmd=0 arg0=0 arg1=0}\n  YieldNoWait\n"
+    );
+}
+
+#[test]
+fn wrong_sequence() {
+    let kernel = fake::Kernel::new();
+    let _ = fake::Syscalls::command(3, 0, 0, 0);
+    fake::Syscalls::yield_no_wait();
+    let _ = fake::Syscalls::command(3, 2, 0, 0);
+    let _ = fake::Syscalls::command(3, 5, 0, 0);
+    let expected = [
+        command(0),
+        ExpectedSyscall::Subscribe {
+            driver_num: 3,
+            subscribe_num: 0,
+            skip_with_error: None,
+        },
+        command(2),
+    ];
+    let diff = SyscallDiff::new(&kernel.take_syscall_log(), &expected);
+    assert!(!diff.is_match());
+    assert_eq!(diff.divergence(), Some(1));
+    assert!(matches!(
+        diff.lines()[2],
+        DiffLine::Extra(SyscallLogEntry::YieldNoWait)
+    ));
+    assert_eq!(
+        diff.to_string(),
+        "  Command{driver=0x3 cmd=0 arg0=0 arg1=0}\n\
+         - Subscribe{driver=0x3 sub=0}\n\
+         + YieldNoWait\n  \
+         Command{driver=0x3 cmd=2 arg0=0 arg1=0}\n\
+         + Command{driver=0x3 cmd=5 arg0=0 arg1=0}\n"
+    );
+
+    // Nothing made at all: every expectation is missing.
+    let diff = SyscallDiff::new(&[], &expected);
+    assert_eq!(diff.divergence(), Some(0));
+    assert!(diff
+        .lines()
+        .iter()
+        .all(|line| matches!(line, DiffLine::Missing(_))));
+}
@@ -1,5 +1,5 @@
 /// SyscallLogEntry represents a system call made during test execution.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum SyscallLogEntry {
     // -------------------------------------------------------------------------
     // Yield
@@ -46,3 +46,48 @@ pub enum SyscallLogEntry {
     // TODO: Add Memop.
     // TODO: Add Exit.
 }
+
+impl std::fmt::Display for SyscallLogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            SyscallLogEntry::YieldNoWait => write!(f, "YieldNoWait"),
+            SyscallLogEntry::YieldWait => write!(f, "YieldWait"),
+            SyscallLogEntry::Subscribe {
+                driver_num,
+                subscribe_num,
+            } => write!(
+                f,
+                "Subscribe{{driver={:#x} sub={}}}",
+                driver_num, subscribe_num
+            ),
+            SyscallLogEntry::Command {
+                driver_id,
+                command_id,
+                argument0,
+                argument1,
+            } => write!(
+                f,
+                "Command{{driver={:#x} cmd={} arg0={} arg1={}}}",
+                driver_id, command_id, argument0, argument1
+            ),
+            SyscallLogEntry::AllowRo {
+                driver_num,
+                buffer_num,
+                len,
+            } => write!(
+                f,
+                "AllowRo{{driver={:#x} buffer={} len={}}}",
+                driver_num, buffer_num, len
+            ),
+            SyscallLogEntry::AllowRw {
+                driver_num,
+                buffer_num,
+                len,
+            } => write!(
+                f,
+                "AllowRw{{driver={:#x} buffer={} len={}}}",
+                driver_num, buffer_num, len
+            ),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! A lock for tests that share process-wide state, such as a driver's
+//! `static` flags. The fake kernel is thread-local, but statics are not, so
+//! tests that touch the same static must not run concurrently.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Serializes the tests that hold it. Unlike `std::sync::Mutex`, it can be
+/// created in a `static`, and a test that panics while holding it does not
+/// poison it for the others.
+///
+/// ```ignore
+/// static EXCLUSIVE_LOCK: TestLock = TestLock::new();
+///
+/// #[test]
+/// fn register_exclusive() {
+///     let _lock = EXCLUSIVE_LOCK.lock();
+///     // ...
+/// }
+/// ```
+pub struct TestLock(AtomicBool);
+
+impl TestLock {
+    pub const fn new() -> TestLock {
+        TestLock(AtomicBool::new(false))
+    }
+
+    /// Waits until no other test holds the lock, then holds it until the
+    /// returned guard is dropped.
+    pub fn lock(&self) -> TestLockGuard<'_> {
+        while self
+            .0
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            std::thread::yield_now();
+        }
+        TestLockGuard(self)
+    }
+}
+
+impl Default for TestLock {
+    fn default() -> TestLock {
+        TestLock::new()
+    }
+}
+
+/// Holds a `TestLock` until dropped.
+pub struct TestLockGuard<'lock>(&'lock TestLock);
+
+impl Drop for TestLockGuard<'_> {
+    fn drop(&mut self) {
+        (self.0).0.store(false, Ordering::Release);
+    }
+}